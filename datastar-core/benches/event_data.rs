@@ -0,0 +1,155 @@
+//! Benchmarks the conversion and formatting of events for an activity feed style workload:
+//! many small element and signal patches, a few data lines each.
+//!
+//! Run with `cargo bench -p datastar-core --bench event_data --features axum,components`.
+
+use {
+    criterion::{BatchSize, Criterion, criterion_group, criterion_main},
+    datastar_core::{
+        DatastarEvent,
+        components::{Feed, Status},
+        consts::ElementPatchMode,
+        pool::EventBufferPool,
+        prelude::{ExecuteScript, PatchElements, PatchSignals},
+        serialize::SerializeOptions,
+    },
+    std::{hint::black_box, time::Duration},
+};
+
+fn conversion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("conversion");
+
+    group.bench_function("patch elements into event", |b| {
+        b.iter_batched(entry, DatastarEvent::from, BatchSize::SmallInput)
+    });
+    let patch = entry();
+    group.bench_function("patch elements as event", |b| {
+        b.iter(|| DatastarEvent::from(black_box(&patch)))
+    });
+    let signals = counters();
+    group.bench_function("patch signals as event", |b| {
+        b.iter(|| DatastarEvent::from(black_box(&signals)))
+    });
+    let script = ExecuteScript::new("console.log('Deployed')\nconsole.log('Done')");
+    group.bench_function("execute script as event", |b| {
+        b.iter(|| DatastarEvent::from(black_box(&script)))
+    });
+    let multiline = rows();
+    group.bench_function("20 line patch as event", |b| {
+        b.iter(|| DatastarEvent::from(black_box(&multiline)))
+    });
+
+    group.finish();
+}
+
+fn serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialization");
+
+    let event = DatastarEvent::from(entry());
+    group.bench_function("event to bytes", |b| {
+        b.iter(|| black_box(&event).to_bytes())
+    });
+    let pool = EventBufferPool::new();
+    group.bench_function("event to pooled bytes", |b| {
+        b.iter(|| pool.to_bytes(black_box(&event)))
+    });
+    group.bench_function("event to string", |b| {
+        b.iter(|| black_box(&event).to_string())
+    });
+    let mut out = Vec::with_capacity(256);
+    group.bench_function("event write to", |b| {
+        b.iter(|| {
+            out.clear();
+            black_box(&event).write_to(&mut out)
+        })
+    });
+    group.bench_function("event to strict string", |b| {
+        b.iter(|| black_box(&event).to_string_with(SerializeOptions::strict()))
+    });
+
+    group.bench_function("event into bytes chunks", |b| {
+        b.iter_batched(
+            || DatastarEvent::from(entry()),
+            DatastarEvent::into_bytes_chunks,
+            BatchSize::SmallInput,
+        )
+    });
+    // The events are built in the setup, as a clone has no room left for the blank line.
+    let report = report();
+    group.bench_function("report into bytes", |b| {
+        b.iter_batched(
+            || DatastarEvent::from(&report),
+            |report| report.to_bytes(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("report into bytes chunks", |b| {
+        b.iter_batched(
+            || DatastarEvent::from(&report),
+            DatastarEvent::into_bytes_chunks,
+            BatchSize::LargeInput,
+        )
+    });
+
+    let leaked = DatastarEvent::from(entry()).leak();
+    group.bench_function("leaked event to bytes", |b| {
+        b.iter(|| black_box(&leaked).to_bytes())
+    });
+
+    let keep_alive = DatastarEvent::keep_alive();
+    group.bench_function("keep alive to bytes", |b| {
+        b.iter(|| black_box(&keep_alive).to_bytes())
+    });
+
+    let retried = DatastarEvent::from(counters().retry(Duration::from_secs(5)));
+    group.bench_function("event with retry to bytes", |b| {
+        b.iter(|| black_box(&retried).to_bytes())
+    });
+    let multiline = DatastarEvent::from(rows());
+    group.bench_function("20 line event to bytes", |b| {
+        b.iter(|| black_box(&multiline).to_bytes())
+    });
+
+    let mut feed = Feed::new("feed").capacity(100);
+    group.bench_function("feed push to bytes", |b| {
+        b.iter(|| {
+            for event in feed.push(Status::Done, "<span>Deployed</span>").events() {
+                black_box(event.to_bytes());
+            }
+        })
+    });
+
+    group.finish();
+}
+
+fn entry() -> PatchElements {
+    PatchElements::new(r#"<div id="feed-1" data-status="done"><span>Deployed</span></div>"#)
+        .selector("#feed")
+        .mode(ElementPatchMode::Prepend)
+}
+
+fn counters() -> PatchSignals {
+    PatchSignals::new(r#"{"total":12,"done":9,"warn":2,"fail":1,"info":0}"#)
+}
+
+fn rows() -> PatchElements {
+    PatchElements::new(
+        (0..20)
+            .map(|row| format!("<li id='row-{row}'>Row {row}</li>"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+    .selector("#rows")
+    .mode(ElementPatchMode::Inner)
+}
+
+/// A patch of a table of a thousand rows, on a single line.
+fn report() -> PatchElements {
+    let rows: String = (0..1000)
+        .map(|row| format!("<tr><td>{row}</td><td>Deployed</td></tr>"))
+        .collect();
+    PatchElements::new(format!("<tbody id='report'>{rows}</tbody>"))
+}
+
+criterion_group!(benches, conversion, serialization);
+criterion_main!(benches);
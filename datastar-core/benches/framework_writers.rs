@@ -0,0 +1,53 @@
+//! Benchmarks writing the builders and events into the SSE events of Axum, and of Rocket
+//! and Warp when their features are enabled.
+//!
+//! Run with `cargo bench -p datastar-core --bench framework_writers --features axum,rocket,warp`.
+
+use {
+    criterion::{Criterion, criterion_group, criterion_main},
+    datastar_core::{
+        DatastarEvent,
+        consts::ElementPatchMode,
+        prelude::{ExecuteScript, PatchElements, PatchSignals},
+    },
+    std::{hint::black_box, time::Duration},
+};
+
+/// Benchmarks `$write` for every builder and a [`DatastarEvent`] in the group `$name`.
+macro_rules! bench_writer {
+    ($c:expr, $name:literal, $write:ident) => {{
+        let patch = entry();
+        let signals = counters();
+        let script = ExecuteScript::new("console.log('Deployed')\nconsole.log('Done')");
+        let event = DatastarEvent::from(entry());
+
+        let mut group = $c.benchmark_group($name);
+        group.bench_function("patch elements", |b| b.iter(|| black_box(&patch).$write()));
+        group.bench_function("patch signals", |b| b.iter(|| black_box(&signals).$write()));
+        group.bench_function("execute script", |b| b.iter(|| black_box(&script).$write()));
+        group.bench_function("event", |b| b.iter(|| black_box(&event).$write()));
+        group.finish();
+    }};
+}
+
+fn writers(c: &mut Criterion) {
+    bench_writer!(c, "axum", write_as_axum_sse_event);
+    #[cfg(feature = "rocket")]
+    bench_writer!(c, "rocket", write_as_rocket_sse_event);
+    #[cfg(feature = "warp")]
+    bench_writer!(c, "warp", write_as_warp_sse_event);
+}
+
+fn entry() -> PatchElements {
+    PatchElements::new(r#"<div id="feed-1" data-status="done"><span>Deployed</span></div>"#)
+        .selector("#feed")
+        .mode(ElementPatchMode::Prepend)
+        .retry(Duration::from_secs(5))
+}
+
+fn counters() -> PatchSignals {
+    PatchSignals::new(r#"{"total":12,"done":9,"warn":2,"fail":1,"info":0}"#).id("counts-12")
+}
+
+criterion_group!(benches, writers);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+// Generated by scripts/update-golden.sh, do not edit.
+&[
+    (
+        "executeScriptWithAllOptions",
+        include_str!("executeScriptWithAllOptions/input.json"),
+        include_str!("executeScriptWithAllOptions/output.txt"),
+    ),
+    (
+        "executeScriptWithDefaults",
+        include_str!("executeScriptWithDefaults/input.json"),
+        include_str!("executeScriptWithDefaults/output.txt"),
+    ),
+    (
+        "executeScriptWithMultilineScript",
+        include_str!("executeScriptWithMultilineScript/input.json"),
+        include_str!("executeScriptWithMultilineScript/output.txt"),
+    ),
+    (
+        "patchElementsWithAllOptions",
+        include_str!("patchElementsWithAllOptions/input.json"),
+        include_str!("patchElementsWithAllOptions/output.txt"),
+    ),
+    (
+        "patchElementsWithDefaults",
+        include_str!("patchElementsWithDefaults/input.json"),
+        include_str!("patchElementsWithDefaults/output.txt"),
+    ),
+    (
+        "patchElementsWithExplicitDefaults",
+        include_str!("patchElementsWithExplicitDefaults/input.json"),
+        include_str!("patchElementsWithExplicitDefaults/output.txt"),
+    ),
+    (
+        "patchElementsWithMultilineElements",
+        include_str!("patchElementsWithMultilineElements/input.json"),
+        include_str!("patchElementsWithMultilineElements/output.txt"),
+    ),
+    (
+        "patchSignalsWithAllOptions",
+        include_str!("patchSignalsWithAllOptions/input.json"),
+        include_str!("patchSignalsWithAllOptions/output.txt"),
+    ),
+    (
+        "patchSignalsWithDefaults",
+        include_str!("patchSignalsWithDefaults/input.json"),
+        include_str!("patchSignalsWithDefaults/output.txt"),
+    ),
+    (
+        "patchSignalsWithMultilineSignals",
+        include_str!("patchSignalsWithMultilineSignals/input.json"),
+        include_str!("patchSignalsWithMultilineSignals/output.txt"),
+    ),
+    (
+        "removeElementsWithSelector",
+        include_str!("removeElementsWithSelector/input.json"),
+        include_str!("removeElementsWithSelector/output.txt"),
+    ),
+    (
+        "removeSignals",
+        include_str!("removeSignals/input.json"),
+        include_str!("removeSignals/output.txt"),
+    ),
+    (
+        "sendTwoEvents",
+        include_str!("sendTwoEvents/input.json"),
+        include_str!("sendTwoEvents/output.txt"),
+    ),
+]
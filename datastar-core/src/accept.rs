@@ -0,0 +1,46 @@
+//! Detection of clients which are not able to consume an SSE stream.
+//!
+//! Datastar always sends `Accept: text/event-stream` with its requests. Clients such as
+//! crawlers or a plain `curl` invocation don't, and would otherwise be stuck waiting on an
+//! endless stream. The framework integrations use [`accepts_event_stream`] to answer them
+//! with a non-streaming response instead.
+
+/// The media type of an SSE stream.
+pub const EVENT_STREAM_MEDIA_TYPE: &str = "text/event-stream";
+
+/// The message returned to clients that do not accept an SSE stream.
+#[doc(hidden)]
+pub const NOT_ACCEPTABLE_MESSAGE: &str =
+    "This endpoint streams Server-Sent Events, retry with `Accept: text/event-stream`";
+
+/// Returns `true` if the given `Accept` header value explicitly lists `text/event-stream`.
+///
+/// Wildcards such as `*/*` are not taken into account, as they are sent by clients which
+/// are not aware of SSE at all.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::accept::accepts_event_stream;
+///
+/// assert!(accepts_event_stream("text/event-stream, application/json"));
+/// assert!(accepts_event_stream("Text/Event-Stream;q=0.9"));
+/// assert!(!accepts_event_stream("*/*"));
+/// assert!(!accepts_event_stream("text/event-stream;q=0"));
+/// ```
+pub fn accepts_event_stream(accept: &str) -> bool {
+    accept.split(',').any(|media_range| {
+        let mut params = media_range.split(';');
+        let media_type = params.next().unwrap_or_default().trim();
+        if !media_type.eq_ignore_ascii_case(EVENT_STREAM_MEDIA_TYPE) {
+            return false;
+        }
+        !params.any(|param| {
+            param
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_some_and(|q| q == 0.0)
+        })
+    })
+}
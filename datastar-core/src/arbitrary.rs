@@ -0,0 +1,281 @@
+//! [`proptest::arbitrary::Arbitrary`] and [`arbitrary::Arbitrary`] for the events, with the
+//! `arbitrary` feature.
+//!
+//! The generated events are valid, and can be written and parsed back: ids, selectors and
+//! attributes are on a single line, the elements, signals and scripts can span several, and
+//! none of them contain a carriage return or a NUL, which the SSE wire format can't carry, or
+//! end with a line break, which is dropped when they are split into data lines.
+
+use {
+    crate::{
+        consts::ElementPatchMode,
+        execute_script::ExecuteScript,
+        patch_elements::{Elements, PatchElements},
+        patch_signals::PatchSignals,
+    },
+    ::arbitrary::{Arbitrary, Unstructured},
+    core::time::Duration,
+    proptest::{
+        arbitrary::Arbitrary as ProptestArbitrary,
+        option,
+        prelude::{BoxedStrategy, Just, Strategy, any, prop_oneof},
+        sample::select,
+    },
+    std::collections::BTreeMap,
+};
+
+const MODES: [ElementPatchMode; 8] = [
+    ElementPatchMode::Outer,
+    ElementPatchMode::Inner,
+    ElementPatchMode::Remove,
+    ElementPatchMode::Replace,
+    ElementPatchMode::Prepend,
+    ElementPatchMode::Append,
+    ElementPatchMode::Before,
+    ElementPatchMode::After,
+];
+
+/// The longest `retry` generated, in milliseconds.
+const MAX_RETRY: u64 = 60_000;
+
+impl ProptestArbitrary for ElementPatchMode {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        select(&MODES[..]).boxed()
+    }
+}
+
+/// # Examples
+///
+/// Writing a patch and parsing it back gives the same patch:
+///
+/// ```
+/// use datastar_core::{DatastarEvent, prelude::PatchElements};
+/// use proptest::prelude::*;
+///
+/// proptest!(|(patch: PatchElements)| {
+///     let event = DatastarEvent::from(patch.clone());
+///     let parsed = DatastarEvent::from_sse_str(&event.to_string()).unwrap();
+///
+///     prop_assert_eq!(PatchElements::try_from(parsed).unwrap(), patch);
+/// });
+/// ```
+impl ProptestArbitrary for PatchElements {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        (
+            option::of(line()),
+            option::of(retry()),
+            option::of(lines()),
+            option::of(line()),
+            any::<ElementPatchMode>(),
+            any::<bool>(),
+        )
+            .prop_map(
+                |(id, retry, elements, selector, mode, use_view_transition)| PatchElements {
+                    id: id.map(Into::into),
+                    retry,
+                    elements: required_elements(elements, &selector, mode).map(Into::into),
+                    selector: selector.map(Into::into),
+                    mode,
+                    use_view_transition,
+                    view_transition: None,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl ProptestArbitrary for PatchSignals {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        (
+            option::of(line()),
+            option::of(retry()),
+            signals(),
+            any::<bool>(),
+        )
+            .prop_map(|(id, retry, signals, only_if_missing)| PatchSignals {
+                id: id.map(Into::into),
+                retry,
+                signals: signals.into(),
+                only_if_missing,
+            })
+            .boxed()
+    }
+}
+
+impl ProptestArbitrary for ExecuteScript {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        (
+            option::of(line()),
+            option::of(retry()),
+            lines(),
+            option::of(any::<bool>()),
+            proptest::collection::vec(("[a-z][a-z-]{0,8}", line()), 0..3),
+            any::<bool>(),
+        )
+            .prop_map(
+                |(id, retry, script, auto_remove, attributes, escape)| ExecuteScript {
+                    id: id.map(Into::into),
+                    retry,
+                    script,
+                    auto_remove,
+                    attributes: attributes
+                        .into_iter()
+                        .map(|(name, value)| attribute(&name, &value))
+                        .collect(),
+                    escape,
+                },
+            )
+            .boxed()
+    }
+}
+
+/// Text on a single line, which isn't blank.
+fn line() -> impl Strategy<Value = String> {
+    "[^\\s\0][^\r\n\0]{0,23}"
+}
+
+/// Text on one to four lines, which isn't blank.
+fn lines() -> impl Strategy<Value = String> {
+    "[^\\s\0][^\r\n\0]{0,23}(\n[^\r\n\0]{1,24}){0,3}"
+}
+
+/// Returns `elements`, or placeholder elements if the patch needs them, as only removals with
+/// a selector may leave them out.
+fn required_elements(
+    elements: Option<String>,
+    selector: &Option<String>,
+    mode: ElementPatchMode,
+) -> Option<String> {
+    match elements {
+        None if mode != ElementPatchMode::Remove || selector.is_none() => {
+            Some("<div></div>".to_owned())
+        }
+        elements => elements,
+    }
+}
+
+/// A rendered attribute, with its value escaped.
+fn attribute(name: &str, value: &str) -> String {
+    format!("{name}=\"{}\"", Elements::text(value))
+}
+
+fn retry() -> impl Strategy<Value = Duration> {
+    (0..=MAX_RETRY).prop_map(Duration::from_millis)
+}
+
+/// A JSON object of signals, with nested objects and removed signals.
+fn signals() -> impl Strategy<Value = String> {
+    let leaf = prop_oneof![
+        Just("null".to_owned()),
+        any::<bool>().prop_map(|flag| flag.to_string()),
+        any::<i32>().prop_map(|number| number.to_string()),
+        "[^\"\\\\\\p{Cc}]{0,12}".prop_map(|text| format!("\"{text}\"")),
+    ];
+    let value = leaf.prop_recursive(2, 8, 4, object);
+    object(value)
+}
+
+fn object(value: impl Strategy<Value = String>) -> impl Strategy<Value = String> {
+    proptest::collection::btree_map("[a-z][a-zA-Z0-9]{0,8}", value, 0..4).prop_map(|entries| {
+        let entries: Vec<String> = entries
+            .into_iter()
+            .map(|(key, value)| format!("\"{key}\":{value}"))
+            .collect();
+        format!("{{{}}}", entries.join(","))
+    })
+}
+
+impl<'a> Arbitrary<'a> for ElementPatchMode {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u.choose(&MODES).copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for PatchElements {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mode = u.arbitrary()?;
+        let selector = arbitrary_line(u)?;
+        Ok(PatchElements {
+            id: arbitrary_line(u)?.map(Into::into),
+            retry: arbitrary_retry(u)?,
+            elements: required_elements(arbitrary_lines(u)?, &selector, mode).map(Into::into),
+            selector: selector.map(Into::into),
+            mode,
+            use_view_transition: u.arbitrary()?,
+            view_transition: None,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for PatchSignals {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let signals: BTreeMap<u8, Option<i32>> = u.arbitrary()?;
+        let signals: Vec<String> = signals
+            .into_iter()
+            .map(|(key, value)| match value {
+                Some(value) => format!("\"s{key}\":{value}"),
+                None => format!("\"s{key}\":null"),
+            })
+            .collect();
+        Ok(PatchSignals {
+            id: arbitrary_line(u)?.map(Into::into),
+            retry: arbitrary_retry(u)?,
+            signals: format!("{{{}}}", signals.join(",")).into(),
+            only_if_missing: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ExecuteScript {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let attributes: Vec<(u8, String)> = u.arbitrary()?;
+        Ok(ExecuteScript {
+            id: arbitrary_line(u)?.map(Into::into),
+            retry: arbitrary_retry(u)?,
+            script: arbitrary_lines(u)?.unwrap_or_default(),
+            auto_remove: u.arbitrary()?,
+            attributes: attributes
+                .into_iter()
+                .map(|(name, value)| attribute(&format!("data-a{name}"), &single_line(value)))
+                .collect(),
+            escape: u.arbitrary()?,
+        })
+    }
+}
+
+fn arbitrary_line(u: &mut Unstructured<'_>) -> arbitrary::Result<Option<String>> {
+    let line: Option<String> = u.arbitrary()?;
+    Ok(line.map(single_line).filter(|line| !line.trim().is_empty()))
+}
+
+fn arbitrary_lines(u: &mut Unstructured<'_>) -> arbitrary::Result<Option<String>> {
+    let text: Option<String> = u.arbitrary()?;
+    Ok(text
+        .map(|text| {
+            text.replace(['\r', '\0'], "")
+                .trim_end_matches('\n')
+                .to_owned()
+        })
+        .filter(|text| !text.trim().is_empty()))
+}
+
+fn arbitrary_retry(u: &mut Unstructured<'_>) -> arbitrary::Result<Option<Duration>> {
+    let retry: Option<u64> = u.arbitrary()?;
+    Ok(retry.map(|millis| Duration::from_millis(millis % (MAX_RETRY + 1))))
+}
+
+fn single_line(text: String) -> String {
+    text.replace(['\r', '\n', '\0'], "")
+}
@@ -0,0 +1,1572 @@
+//! Axum integration for Datastar.
+
+#[cfg(feature = "inspector")]
+use crate::inspector::{self, Inspector};
+#[cfg(feature = "multipart")]
+use {
+    crate::multipart::{self, Files},
+    axum::RequestExt,
+};
+use {
+    crate::{
+        Error,
+        accept::{self, accepts_event_stream},
+        batch::{self, BatchExt, DatastarEventBatch},
+        compression::{self, DEFAULT_DECOMPRESSED_LIMIT},
+        consts::DATASTAR_REQ_HEADER_STR,
+        fragment::FragmentResponse,
+        json,
+        pool::EventBufferPool,
+        prelude::{DatastarEvent, ExecuteScript, PatchElements, PatchSignals},
+        replay::{self, Journal, LastEventId},
+        response::{Batch, IntoDatastarResponse},
+        sender::DatastarReceiver,
+        signals::{self, ValidateSignals},
+        sse_payload::{SsePayload, ToSsePayload},
+    },
+    axum::{
+        body::{Body, Bytes},
+        extract::{
+            FromRequest, FromRequestParts, OptionalFromRequest, OptionalFromRequestParts, Query,
+            Request,
+        },
+        http::{self, request::Parts},
+        response::{Html, IntoResponse, Response, sse::Event},
+    },
+    core::task::{Context, Poll},
+    futures_util::{StreamExt, future::BoxFuture, stream},
+    serde::{Deserialize, de::DeserializeOwned},
+    std::{convert::Infallible, sync::Arc},
+    tower_layer::Layer,
+    tower_service::Service,
+};
+
+impl PatchElements {
+    /// Write this [`PatchElements`] into an Axum SSE [`Event`].
+    pub fn write_as_axum_sse_event(&self) -> Event {
+        sse_event(self.sse_payload())
+    }
+}
+
+impl From<PatchElements> for Event {
+    fn from(value: PatchElements) -> Self {
+        value.write_as_axum_sse_event()
+    }
+}
+
+impl From<&PatchElements> for Event {
+    fn from(value: &PatchElements) -> Self {
+        value.write_as_axum_sse_event()
+    }
+}
+
+impl PatchSignals {
+    /// Write this [`PatchSignals`] into an Axum SSE [`Event`].
+    pub fn write_as_axum_sse_event(&self) -> Event {
+        sse_event(self.sse_payload())
+    }
+}
+
+impl From<PatchSignals> for Event {
+    fn from(value: PatchSignals) -> Self {
+        value.write_as_axum_sse_event()
+    }
+}
+
+impl From<&PatchSignals> for Event {
+    fn from(value: &PatchSignals) -> Self {
+        value.write_as_axum_sse_event()
+    }
+}
+
+impl ExecuteScript {
+    /// Write this [`ExecuteScript`] into an Axum SSE [`Event`].
+    pub fn write_as_axum_sse_event(&self) -> Event {
+        sse_event(self.sse_payload())
+    }
+}
+
+impl From<ExecuteScript> for Event {
+    fn from(value: ExecuteScript) -> Self {
+        value.write_as_axum_sse_event()
+    }
+}
+
+impl From<&ExecuteScript> for Event {
+    fn from(value: &ExecuteScript) -> Self {
+        value.write_as_axum_sse_event()
+    }
+}
+
+impl DatastarEvent {
+    /// Turn this [`DatastarEvent`] into an Axum SSE [`Event`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event has custom [`fields`](DatastarEvent::fields), which an Axum [`Event`]
+    /// can't hold, rather than dropping them. [`DatastarResponse`] writes them.
+    pub fn write_as_axum_sse_event(&self) -> Event {
+        let payload = self.sse_payload();
+        payload.assert_fits("Axum", usize::MAX);
+        sse_event(payload)
+    }
+}
+
+/// Builds the Axum [`Event`] of an [`SsePayload`], which has no custom fields.
+fn sse_event(payload: SsePayload<'_>) -> Event {
+    let mut event = payload
+        .comments
+        .iter()
+        .fold(Event::default(), |event, comment| event.comment(comment));
+
+    if let Some(event_type) = payload.event_name() {
+        event = event.event(event_type);
+    }
+    if let Some(id) = payload.id {
+        event = event.id(id.as_ref());
+    }
+    if let Some(retry) = payload.retry {
+        event = event.retry(retry);
+    }
+
+    match payload.data {
+        Some(data) => event.data(data),
+        None => event,
+    }
+}
+
+impl From<DatastarEvent> for Event {
+    fn from(value: DatastarEvent) -> Self {
+        value.write_as_axum_sse_event()
+    }
+}
+
+impl From<&DatastarEvent> for Event {
+    fn from(value: &DatastarEvent) -> Self {
+        value.write_as_axum_sse_event()
+    }
+}
+
+/// [`DatastarResponse`] turns any [`IntoDatastarResponse`] into an Axum SSE [`Response`].
+///
+/// The events which are ready together, such as those of a [`Transaction`](crate::sender::Transaction),
+/// are written as one chunk with [`BatchExt::ready_batches`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     axum::DatastarResponse, prelude::PatchElements, response::IntoDatastarResponse,
+/// };
+///
+/// fn greet(name: &str) -> impl IntoDatastarResponse {
+///     PatchElements::new(format!("<div id='greeting'>Hello, {name}!</div>"))
+/// }
+///
+/// async fn handler() -> DatastarResponse<impl IntoDatastarResponse> {
+///     DatastarResponse(greet("world"))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DatastarResponse<T>(pub T);
+
+impl<T: IntoDatastarResponse> IntoResponse for DatastarResponse<T> {
+    fn into_response(self) -> Response {
+        let stream = self
+            .0
+            .into_datastar_stream()
+            .ready_batches()
+            .map(|batch| Ok::<_, Infallible>(batch.to_bytes()));
+        event_stream_response(Body::from_stream(stream))
+    }
+}
+
+/// [`ZeroCopyResponse`] turns any [`IntoDatastarResponse`] into an Axum SSE [`Response`],
+/// moving the data lines of every event into the body rather than copying them.
+///
+/// Every event is written as the two chunks of [`DatastarEvent::into_bytes_chunks`], so the
+/// events with only their type and the keep-alives don't allocate anything to be written.
+/// Unlike [`DatastarResponse`], the events which are ready together aren't merged into one
+/// chunk, so it suits streams of large patches rather than bursts of small ones.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{axum::ZeroCopyResponse, prelude::PatchElements, response::IntoDatastarResponse};
+///
+/// async fn report() -> ZeroCopyResponse<impl IntoDatastarResponse> {
+///     let rows: String = (0..1000).map(|row| format!("<tr><td>{row}</td></tr>")).collect();
+///     ZeroCopyResponse(PatchElements::new(format!("<tbody id='report'>{rows}</tbody>")))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ZeroCopyResponse<T>(pub T);
+
+impl<T: IntoDatastarResponse> IntoResponse for ZeroCopyResponse<T> {
+    fn into_response(self) -> Response {
+        let stream = self.0.into_datastar_stream().flat_map(|event| {
+            let chunks = event.into_bytes_chunks().into_iter();
+            stream::iter(
+                chunks
+                    .filter(|chunk| !chunk.is_empty())
+                    .map(Ok::<_, Infallible>),
+            )
+        });
+        event_stream_response(Body::from_stream(stream))
+    }
+}
+
+/// [`PooledResponse`] turns any [`IntoDatastarResponse`] into an Axum SSE [`Response`] like
+/// [`DatastarResponse`], framing the events into the buffers of an [`EventBufferPool`].
+///
+/// It suits the streams sending many small events, whose chunks then don't need to be
+/// allocated one by one.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     datastar_core::{
+///         DatastarEvent,
+///         axum::PooledResponse,
+///         pool::EventBufferPool,
+///         prelude::PatchSignals,
+///         response::{DatastarStream, IntoDatastarResponse},
+///     },
+///     futures_util::{StreamExt, stream},
+/// };
+///
+/// async fn telemetry() -> PooledResponse<impl IntoDatastarResponse> {
+///     let frames = stream::iter(0..1000)
+///         .map(|frame| DatastarEvent::from(PatchSignals::new(format!("{{frame: {frame}}}"))));
+///     PooledResponse(DatastarStream(frames), EventBufferPool::global().clone())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PooledResponse<T>(pub T, pub EventBufferPool);
+
+impl<T: IntoDatastarResponse> IntoResponse for PooledResponse<T> {
+    fn into_response(self) -> Response {
+        let Self(events, pool) = self;
+        let stream = events
+            .into_datastar_stream()
+            .ready_batches()
+            .map(move |batch| Ok::<_, Infallible>(pool.batch_to_bytes(&batch)));
+        event_stream_response(Body::from_stream(stream))
+    }
+}
+
+/// Responds with the elements as HTML and the headers telling Datastar how to patch them, or
+/// with the error of an invalid [`FragmentResponse`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{consts::ElementPatchMode, fragment::FragmentResponse};
+///
+/// async fn like() -> FragmentResponse {
+///     FragmentResponse::new("<span>12 likes</span>")
+///         .selector("#likes")
+///         .mode(ElementPatchMode::Inner)
+/// }
+/// ```
+impl IntoResponse for FragmentResponse {
+    fn into_response(self) -> Response {
+        if let Err(err) = self.validate() {
+            return err.into_response();
+        }
+
+        let mut headers = http::HeaderMap::new();
+        for (name, value) in self.headers() {
+            if let Ok(value) = http::HeaderValue::from_str(value) {
+                headers.insert(http::HeaderName::from_static(name), value);
+            }
+        }
+        (headers, Html(self.elements)).into_response()
+    }
+}
+
+/// Returns an SSE [`Response`] with `body`.
+fn event_stream_response(body: Body) -> Response {
+    (
+        [
+            (http::header::CONTENT_TYPE, accept::EVENT_STREAM_MEDIA_TYPE),
+            (http::header::CACHE_CONTROL, "no-cache"),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+impl IntoResponse for PatchElements {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl IntoResponse for PatchSignals {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl IntoResponse for ExecuteScript {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl IntoResponse for DatastarEvent {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl IntoResponse for Batch {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+/// Responds with the events as a single `text/event-stream` body rather than a stream,
+/// closing the connection once it's sent.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     datastar_core::{DatastarEvent, batch::DatastarEventBatch, prelude::PatchSignals},
+///     futures_util::{StreamExt, stream},
+/// };
+///
+/// async fn poll() -> DatastarEventBatch {
+///     stream::iter(1..=3)
+///         .map(|step| DatastarEvent::from(PatchSignals::new(format!("{{step: {step}}}"))))
+///         .collect()
+///         .await
+/// }
+/// ```
+impl IntoResponse for DatastarEventBatch {
+    fn into_response(self) -> Response {
+        let mut response = Body::from(self.to_bytes()).into_response();
+        let headers = response.headers_mut();
+        for (name, value) in batch::RESPONSE_HEADERS {
+            headers.insert(
+                http::HeaderName::from_static(name),
+                http::HeaderValue::from_static(value),
+            );
+        }
+        response
+    }
+}
+
+impl IntoResponse for DatastarReceiver {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl<S> IntoResponse for crate::indicator::Indicator<S>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl<S, G> IntoResponse for crate::event_id::WithEventIds<S, G>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    G: crate::event_id::EventIdGenerator,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl<S, C> IntoResponse for crate::stall::Stall<S, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "broadcast")]
+impl IntoResponse for crate::broadcast::Subscription {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl<S, C> IntoResponse for crate::heartbeat::Heartbeat<S, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S, C> IntoResponse for crate::coalesce::CoalesceSignals<S, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S, W, C> IntoResponse for crate::recording::Recorder<S, W, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    W: std::io::Write + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<C: crate::clock::Clock> IntoResponse for crate::recording::Player<C> {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "broadcast")]
+impl IntoResponse for crate::broadcast::HubMetrics {
+    fn into_response(self) -> Response {
+        (
+            [(
+                http::header::CONTENT_TYPE,
+                crate::broadcast::PROMETHEUS_CONTENT_TYPE,
+            )],
+            self.to_string(),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(feature = "inspector")]
+impl IntoResponse for crate::inspector::InspectorPanel {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "inspector")]
+impl<S> IntoResponse for crate::inspector::Inspected<S>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "redis")]
+impl IntoResponse for crate::redis::RedisSubscription {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+/// [`ReadSignals`] is a request extractor that reads datastar signals from the request.
+///
+/// Read a [`WithRaw<T>`](crate::signals::WithRaw) to get the untyped JSON of the signals as well,
+/// or a [`Lenient<T>`](crate::signals::Lenient) to fall back to defaults for signals which don't fit.
+///
+/// A body with the `application/x-www-form-urlencoded` content type, as submitted by a plain
+/// HTML form, is read as the signals as well.
+///
+/// The body is limited by the [`DefaultBodyLimit`](axum::extract::DefaultBodyLimit) of the
+/// router, 2 MB unless configured, and a larger body is rejected with [`Error::PayloadTooLarge`]
+/// without being read whole.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::axum::ReadSignals;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     foo: String,
+///     bar: i32,
+/// }
+///
+/// async fn handler(ReadSignals(signals): ReadSignals<Signals>) {
+///    println!("foo: {}", signals.foo);
+///    println!("bar: {}", signals.bar);
+/// }
+///
+/// ```
+#[derive(Debug)]
+pub struct ReadSignals<T: DeserializeOwned>(pub T);
+
+impl<T: DeserializeOwned, S: Send + Sync> OptionalFromRequest<S> for ReadSignals<T>
+where
+    Bytes: FromRequest<S>,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        if req.headers().get(DATASTAR_REQ_HEADER_STR).is_none() {
+            return Ok(None);
+        }
+        Ok(Some(
+            <Self as FromRequest<S>>::from_request(req, state).await?,
+        ))
+    }
+}
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for ReadSignals<T>
+where
+    Bytes: FromRequest<S>,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (parts, body) = req.into_parts();
+        let body = match parts.method {
+            http::Method::GET => Bytes::new(),
+            _ => body_bytes(limited_request(&parts, body), state).await?,
+        };
+
+        signals::from_parts(
+            parts.method.as_str(),
+            parts.uri.query(),
+            &parts.headers,
+            &body,
+        )
+        .map(Self)
+    }
+}
+
+/// [`ReadValidSignals`] is a request extractor like [`ReadSignals`], which also rejects signals
+/// whose [`ValidateSignals::validate`] fails with [`Error::Validation`].
+///
+/// # Examples
+///
+/// ```
+/// use axum::response::IntoResponse;
+/// use datastar_core::{
+///     Error,
+///     axum::ReadValidSignals,
+///     prelude::PatchElements,
+///     signals::{ValidateSignals, ValidationErrors},
+/// };
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct SignUp {
+///     email: String,
+/// }
+///
+/// impl ValidateSignals for SignUp {
+///     fn validate(&self) -> Result<(), ValidationErrors> {
+///         let mut errors = ValidationErrors::new();
+///         if !self.email.contains('@') {
+///             errors.push("email", "Enter a valid email address");
+///         }
+///         errors.into_result()
+///     }
+/// }
+///
+/// async fn sign_up(signals: Result<ReadValidSignals<SignUp>, Error>) -> impl IntoResponse {
+///     match signals {
+///         Ok(ReadValidSignals(signals)) => {
+///             PatchElements::new(format!("<p id='result'>Welcome, {}!</p>", signals.email))
+///                 .into_response()
+///         }
+///         // Show the messages next to the inputs rather than failing the request.
+///         Err(Error::Validation(errors)) => errors.to_patch_signals("errors").into_response(),
+///         Err(err) => err.into_response(),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ReadValidSignals<T>(pub T);
+
+impl<T, S> FromRequest<S> for ReadValidSignals<T>
+where
+    T: DeserializeOwned + ValidateSignals,
+    S: Send + Sync,
+    Bytes: FromRequest<S>,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let ReadSignals(signals) =
+            <ReadSignals<T> as FromRequest<S>>::from_request(req, state).await?;
+        signals.validate()?;
+        Ok(Self(signals))
+    }
+}
+
+/// [`ReadSignalsMultipart`] is a request extractor for `multipart/form-data` requests, such as
+/// forms with a file input, which reads the signals and streams the uploaded [`Files`].
+///
+/// See the [`multipart`](crate::multipart) module for the fields which are read as the signals.
+/// The body is limited like that of [`ReadSignals`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{Error, axum::ReadSignalsMultipart};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Upload {
+///     album: String,
+/// }
+///
+/// async fn upload(
+///     ReadSignalsMultipart(signals, mut files): ReadSignalsMultipart<Upload>,
+/// ) -> Result<String, Error> {
+///     let mut count = 0;
+///     while let Some(photo) = files.next_file().await? {
+///         drop(photo);
+///         count += 1;
+///     }
+///     Ok(format!("added {count} photos to {}", signals.album))
+/// }
+/// ```
+#[cfg(feature = "multipart")]
+#[derive(Debug)]
+pub struct ReadSignalsMultipart<T>(pub T, pub Files);
+
+#[cfg(feature = "multipart")]
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for ReadSignalsMultipart<T> {
+    type Rejection = Error;
+
+    async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = content_type(req.headers());
+        // The body is limited by the `DefaultBodyLimit` already.
+        let body = req.into_limited_body().into_data_stream();
+        let (signals, files) =
+            multipart::read_signals(content_type.as_deref(), body, u64::MAX).await?;
+        Ok(Self(signals, files))
+    }
+}
+
+fn query_signals(uri: &http::Uri) -> Result<Option<String>, Error> {
+    let Query(query) = Query::<DatastarParam>::try_from_uri(uri)
+        .map_err(|err| Error::InvalidQuery(Box::new(err)))?;
+    Ok(query.datastar)
+}
+
+/// Creates a request with `body` and the extensions of `parts`, which hold the `DefaultBodyLimit`.
+fn limited_request(parts: &Parts, body: Body) -> Request {
+    let mut req = Request::new(body);
+    *req.extensions_mut() = parts.extensions.clone();
+    req
+}
+
+async fn body_bytes<S: Send + Sync>(req: Request, state: &S) -> Result<Bytes, Error> {
+    Bytes::from_request(req, state).await.map_err(|err| {
+        if err.status() == http::StatusCode::PAYLOAD_TOO_LARGE {
+            Error::PayloadTooLarge
+        } else {
+            Error::Body(Box::new(err))
+        }
+    })
+}
+
+/// Decodes a body sent with the `Content-Encoding` header `content_encoding`.
+fn decode_body(content_encoding: Option<&str>, body: Bytes) -> Result<Bytes, Error> {
+    let decoded = compression::decode_body(content_encoding, &body, DEFAULT_DECOMPRESSED_LIMIT)?;
+    Ok(decoded.map_or(body, Bytes::from))
+}
+
+fn content_type(headers: &http::HeaderMap) -> Option<String> {
+    header(headers, http::header::CONTENT_TYPE)
+}
+
+fn content_encoding(headers: &http::HeaderMap) -> Option<String> {
+    header(headers, http::header::CONTENT_ENCODING)
+}
+
+fn header(headers: &http::HeaderMap, name: http::header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let status = http::StatusCode::from_u16(self.status())
+            .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+        (status, self.to_string()).into_response()
+    }
+}
+
+/// [`IsDatastar`] is a request extractor that checks for the `datastar-request` header,
+/// without consuming the body of the request.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::axum::IsDatastar;
+///
+/// async fn handler(IsDatastar(is_datastar): IsDatastar) -> &'static str {
+///     if is_datastar { "fragment" } else { "full page" }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IsDatastar(pub bool);
+
+impl<S: Send + Sync> FromRequestParts<S> for IsDatastar {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(parts.headers.contains_key(DATASTAR_REQ_HEADER_STR)))
+    }
+}
+
+#[derive(Deserialize)]
+struct DatastarParam {
+    datastar: Option<String>,
+}
+
+/// [`DatastarRequest`] is a request extractor that surfaces the Datastar specific metadata
+/// of a request, so handlers and middleware can branch on it without parsing the request twice.
+///
+/// Unlike [`ReadSignals`], the signals are kept as raw JSON and the extraction does not fail
+/// for requests which were not sent by Datastar. The body is limited like that of [`ReadSignals`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::axum::DatastarRequest;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     delay: u64,
+/// }
+///
+/// async fn handler(req: DatastarRequest) -> String {
+///     if !req.is_datastar {
+///         return "full page".to_owned();
+///     }
+///     match req.signals_as::<Signals>() {
+///         Ok(Some(signals)) => format!("delay: {}", signals.delay),
+///         _ => "fragment".to_owned(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DatastarRequest {
+    /// Whether the request carries the `datastar-request` header.
+    pub is_datastar: bool,
+    /// The HTTP method of the request.
+    pub method: http::Method,
+    /// The signals sent with the request, if any.
+    ///
+    /// For `GET` requests these are read from the `datastar` query parameter,
+    /// for all other methods from the JSON body.
+    pub signals: Option<serde_json::Value>,
+    /// The `Last-Event-ID` header of the request, if any.
+    pub last_event_id: Option<LastEventId>,
+}
+
+impl DatastarRequest {
+    /// Deserializes the raw [`DatastarRequest::signals`] into `T`.
+    pub fn signals_as<T: DeserializeOwned>(&self) -> Result<Option<T>, Error> {
+        Ok(self.signals.as_ref().map(T::deserialize).transpose()?)
+    }
+}
+
+impl<S: Send + Sync> FromRequest<S> for DatastarRequest
+where
+    Bytes: FromRequest<S>,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = req.into_parts();
+
+        let is_datastar = parts.headers.contains_key(DATASTAR_REQ_HEADER_STR);
+        let last_event_id =
+            <LastEventId as OptionalFromRequestParts<S>>::from_request_parts(&mut parts, state)
+                .await?;
+        let method = parts.method.clone();
+
+        let signals = match method {
+            http::Method::GET => query_signals(&parts.uri)?
+                .map(|signals| json::signals_from_slice(signals.as_bytes()))
+                .transpose(),
+            _ => {
+                let content_type = content_type(&parts.headers);
+                let content_encoding = content_encoding(&parts.headers);
+                let body = body_bytes(Request::from_parts(parts, body), state).await?;
+                let body = decode_body(content_encoding.as_deref(), body)?;
+                (!body.is_empty())
+                    .then(|| json::signals_from_body(content_type.as_deref(), &body))
+                    .transpose()
+            }
+        };
+
+        let signals = signals?;
+
+        Ok(Self {
+            is_datastar,
+            method,
+            signals,
+            last_event_id,
+        })
+    }
+}
+
+/// Extracts the [`LastEventId`] from the `Last-Event-ID` header.
+///
+/// Rejects the request with `400 Bad Request` if the header is missing,
+/// use `Option<LastEventId>` for streams which can also be started from scratch.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::replay::LastEventId;
+///
+/// async fn handler(last_event_id: Option<LastEventId>) {
+///     match last_event_id {
+///         Some(id) => println!("resuming after {id}"),
+///         None => println!("starting a new stream"),
+///     }
+/// }
+/// ```
+impl<S: Send + Sync> FromRequestParts<S> for LastEventId {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        <Self as OptionalFromRequestParts<S>>::from_request_parts(parts, state)
+            .await?
+            .ok_or(Error::MissingHeader(replay::LAST_EVENT_ID_HEADER_STR))
+    }
+}
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for LastEventId {
+    type Rejection = Error;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        let Some(value) = parts.headers.get(replay::LAST_EVENT_ID_HEADER_STR) else {
+            return Ok(None);
+        };
+        let value = value
+            .to_str()
+            .map_err(|_| Error::InvalidHeader(replay::LAST_EVENT_ID_HEADER_STR))?;
+        Ok(Some(Self(value.to_owned())))
+    }
+}
+
+/// [`ReplayLayer`] prepends the events a client missed to the SSE stream returned by the
+/// wrapped handler, whenever the request carries a `Last-Event-ID` header.
+///
+/// Handlers don't need to be aware of the replay at all, which makes any existing
+/// stream resumable by adding a single layer to its route.
+///
+/// # Examples
+///
+/// ```
+/// use axum::{Router, routing::get};
+/// use datastar_core::{DatastarEvent, axum::ReplayLayer, replay::Journal};
+///
+/// struct NoJournal;
+///
+/// impl Journal for NoJournal {
+///     async fn events_after(&self, _last_event_id: &str) -> Vec<DatastarEvent> {
+///         Vec::new()
+///     }
+/// }
+///
+/// async fn feed() {}
+///
+/// let app: Router = Router::new().route("/feed", get(feed).route_layer(ReplayLayer::new(NoJournal)));
+/// ```
+#[derive(Debug)]
+pub struct ReplayLayer<J> {
+    journal: Arc<J>,
+}
+
+impl<J> ReplayLayer<J> {
+    /// Creates a new [`ReplayLayer`] which replays events from the given [`Journal`].
+    pub fn new(journal: J) -> Self {
+        Self {
+            journal: Arc::new(journal),
+        }
+    }
+}
+
+impl<J> Clone for ReplayLayer<J> {
+    fn clone(&self) -> Self {
+        Self {
+            journal: self.journal.clone(),
+        }
+    }
+}
+
+impl<S, J> Layer<S> for ReplayLayer<J> {
+    type Service = Replay<S, J>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Replay {
+            inner,
+            journal: self.journal.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`ReplayLayer`].
+#[derive(Debug)]
+pub struct Replay<S, J> {
+    inner: S,
+    journal: Arc<J>,
+}
+
+impl<S: Clone, J> Clone for Replay<S, J> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            journal: self.journal.clone(),
+        }
+    }
+}
+
+impl<S, J> Service<Request> for Replay<S, J>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+    J: Journal,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let last_event_id = req
+            .headers()
+            .get(replay::LAST_EVENT_ID_HEADER_STR)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned);
+        let journal = self.journal.clone();
+        let future = self.inner.call(req);
+
+        Box::pin(async move {
+            let res = future.await?;
+
+            let Some(last_event_id) = last_event_id else {
+                return Ok(res);
+            };
+
+            if !is_event_stream(&res) {
+                return Ok(res);
+            }
+
+            let missed = journal.events_after(&last_event_id).await;
+            if missed.is_empty() {
+                return Ok(res);
+            }
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(%last_event_id, count = missed.len(), "replaying missed events");
+
+            let prefix = Bytes::from(replay::frame_events(&missed));
+            let (parts, body) = res.into_parts();
+            let body = Body::from_stream(
+                stream::once(async move { Ok::<_, axum::Error>(prefix) })
+                    .chain(body.into_data_stream()),
+            );
+
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+/// [`RequireEventStreamLayer`] answers requests which don't accept `text/event-stream`
+/// with a non-streaming response, instead of calling the wrapped handler.
+///
+/// By default a `406 Not Acceptable` response explaining how to consume the endpoint is
+/// returned, a snapshot of the current state can be returned instead using
+/// [`RequireEventStreamLayer::fallback`].
+///
+/// # Examples
+///
+/// ```
+/// use axum::{Router, response::{Html, IntoResponse}, routing::get};
+/// use datastar_core::axum::RequireEventStreamLayer;
+///
+/// async fn feed() {}
+///
+/// let app: Router = Router::new()
+///     .route("/feed", get(feed).route_layer(RequireEventStreamLayer::new()))
+///     .route(
+///         "/counter",
+///         get(feed).route_layer(
+///             RequireEventStreamLayer::new()
+///                 .fallback(|| Html("<div id='counter'>0</div>").into_response()),
+///         ),
+///     );
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequireEventStreamLayer<F = fn() -> Response> {
+    fallback: F,
+}
+
+impl RequireEventStreamLayer {
+    /// Creates a new [`RequireEventStreamLayer`] which responds with `406 Not Acceptable`.
+    pub fn new() -> Self {
+        Self {
+            fallback: not_acceptable,
+        }
+    }
+}
+
+impl Default for RequireEventStreamLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F> RequireEventStreamLayer<F> {
+    /// Sets the function producing the response for clients that don't accept `text/event-stream`.
+    pub fn fallback<G>(self, fallback: G) -> RequireEventStreamLayer<G>
+    where
+        G: Fn() -> Response + Clone + Send + Sync + 'static,
+    {
+        RequireEventStreamLayer { fallback }
+    }
+}
+
+impl<S, F: Clone> Layer<S> for RequireEventStreamLayer<F> {
+    type Service = RequireEventStream<S, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequireEventStream {
+            inner,
+            fallback: self.fallback.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`RequireEventStreamLayer`].
+#[derive(Debug, Clone)]
+pub struct RequireEventStream<S, F> {
+    inner: S,
+    fallback: F,
+}
+
+impl<S, F> Service<Request> for RequireEventStream<S, F>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+    F: Fn() -> Response + Clone + Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let accepted = req
+            .headers()
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(accepts_event_stream);
+
+        if accepted {
+            return Box::pin(self.inner.call(req));
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(uri = %req.uri(), "request does not accept an event stream");
+
+        let res = (self.fallback)();
+        Box::pin(async move { Ok(res) })
+    }
+}
+
+/// [`NoCompressionLayer`] marks SSE responses with `Content-Encoding: identity` and
+/// `Cache-Control: no-transform`, so they are never compressed.
+///
+/// Compression layers such as the one from `tower-http` skip responses which already have a
+/// `Content-Encoding`, and well-behaved proxies respect `no-transform`. A compressed stream
+/// is buffered until the compressor flushes, which shows up as all events arriving at once
+/// when the stream ends. The layer has to be added inside of the compression layer.
+///
+/// # Examples
+///
+/// ```
+/// use axum::{Router, routing::get};
+/// use datastar_core::axum::NoCompressionLayer;
+///
+/// async fn feed() {}
+///
+/// let app: Router = Router::new()
+///     .route("/feed", get(feed))
+///     .layer(NoCompressionLayer);
+/// // .layer(tower_http::compression::CompressionLayer::new());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoCompressionLayer;
+
+impl<S> Layer<S> for NoCompressionLayer {
+    type Service = NoCompression<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        NoCompression { inner }
+    }
+}
+
+/// The [`Service`] created by [`NoCompressionLayer`].
+#[derive(Debug, Clone)]
+pub struct NoCompression<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for NoCompression<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let future = self.inner.call(req);
+
+        Box::pin(async move {
+            let mut res = future.await?;
+            if !is_event_stream(&res) {
+                return Ok(res);
+            }
+
+            let headers = res.headers_mut();
+            headers
+                .entry(http::header::CONTENT_ENCODING)
+                .or_insert(http::HeaderValue::from_static("identity"));
+
+            let cache_control = match headers
+                .get(http::header::CACHE_CONTROL)
+                .and_then(|value| value.to_str().ok())
+            {
+                Some(value) if value.contains("no-transform") => None,
+                Some(value) => http::HeaderValue::try_from(format!("{value}, no-transform")).ok(),
+                None => Some(http::HeaderValue::from_static("no-cache, no-transform")),
+            };
+            if let Some(cache_control) = cache_control {
+                headers.insert(http::header::CACHE_CONTROL, cache_control);
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+/// [`InspectorLayer`] logs every event of the SSE responses with `tracing`, and mirrors them
+/// to the live panels of its [`Inspector`].
+///
+/// The responses of [`INSPECTOR_PATH`](inspector::INSPECTOR_PATH) aren't inspected, so the
+/// panel can be served behind the layer.
+///
+/// # Examples
+///
+/// ```
+/// use axum::{Router, routing::get};
+/// use datastar_core::{
+///     axum::InspectorLayer,
+///     inspector::{INSPECTOR_PATH, Inspector},
+/// };
+///
+/// async fn feed() {}
+///
+/// let inspector = Inspector::new();
+/// let panel = inspector.clone();
+///
+/// let app: Router = Router::new()
+///     .route("/feed", get(feed))
+///     .route(INSPECTOR_PATH, get(move || async move { panel.panel() }))
+///     .layer(InspectorLayer::new(inspector));
+/// ```
+#[cfg(feature = "inspector")]
+#[derive(Debug, Clone, Default)]
+pub struct InspectorLayer {
+    inspector: Inspector,
+}
+
+#[cfg(feature = "inspector")]
+impl InspectorLayer {
+    /// Creates a new [`InspectorLayer`] which mirrors the events to the panels of `inspector`.
+    pub fn new(inspector: Inspector) -> Self {
+        Self { inspector }
+    }
+}
+
+#[cfg(feature = "inspector")]
+impl<S> Layer<S> for InspectorLayer {
+    type Service = Inspect<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Inspect {
+            inner,
+            inspector: self.inspector.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`InspectorLayer`].
+#[cfg(feature = "inspector")]
+#[derive(Debug, Clone)]
+pub struct Inspect<S> {
+    inner: S,
+    inspector: Inspector,
+}
+
+#[cfg(feature = "inspector")]
+impl<S> Service<Request> for Inspect<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let path = req.uri().path();
+        let inspection = (path != inspector::INSPECTOR_PATH).then(|| self.inspector.inspect(path));
+        let future = self.inner.call(req);
+
+        Box::pin(async move {
+            let res = future.await?;
+            let Some(mut inspection) = inspection.filter(|_| is_event_stream(&res)) else {
+                return Ok(res);
+            };
+
+            let (parts, body) = res.into_parts();
+            let body = Body::from_stream(body.into_data_stream().map(move |chunk| {
+                if let Ok(chunk) = &chunk {
+                    inspection.observe(chunk);
+                }
+                chunk
+            }));
+
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+/// [`SignalsCacheLayer`] reads the body of every request other than a `GET` up front and keeps
+/// it in the request extensions, so middleware and extractors can read the signals with
+/// [`CachedSignals`] before the handler reads them with [`ReadSignals`] as usual.
+///
+/// The body is limited like that of [`ReadSignals`], by a `DefaultBodyLimit` added outside
+/// of this layer.
+///
+/// # Examples
+///
+/// ```
+/// use axum::{
+///     Router,
+///     extract::Request,
+///     middleware::{self, Next},
+///     response::Response,
+///     routing::post,
+/// };
+/// use datastar_core::{
+///     Error,
+///     axum::{CachedSignals, ReadSignals, SignalsCacheLayer},
+/// };
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Session {
+///     token: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Todo {
+///     title: String,
+/// }
+///
+/// async fn authenticate(
+///     CachedSignals(session): CachedSignals<Session>,
+///     req: Request,
+///     next: Next,
+/// ) -> Result<Response, Error> {
+///     println!("token: {}", session.token);
+///     Ok(next.run(req).await)
+/// }
+///
+/// async fn add(ReadSignals(todo): ReadSignals<Todo>) -> String {
+///     todo.title
+/// }
+///
+/// let app: Router = Router::new()
+///     .route("/todos", post(add))
+///     .layer(middleware::from_fn(authenticate))
+///     .layer(SignalsCacheLayer);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignalsCacheLayer;
+
+impl<S> Layer<S> for SignalsCacheLayer {
+    type Service = SignalsCache<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SignalsCache { inner }
+    }
+}
+
+/// The [`Service`] created by [`SignalsCacheLayer`].
+#[derive(Debug, Clone)]
+pub struct SignalsCache<S> {
+    inner: S,
+}
+
+/// The body of a request, as kept by [`SignalsCacheLayer`].
+#[derive(Clone)]
+struct CachedBody(Bytes);
+
+impl<S> Service<Request> for SignalsCache<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        // Call the service which was polled ready, leaving a fresh clone in its place.
+        let clone = self.inner.clone();
+        let mut inner = core::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            if req.method() == http::Method::GET {
+                return inner.call(req).await;
+            }
+
+            let (mut parts, body) = req.into_parts();
+            let body = match body_bytes(limited_request(&parts, body), &()).await {
+                Ok(body) => body,
+                Err(err) => return Ok(err.into_response()),
+            };
+
+            parts.extensions.insert(CachedBody(body.clone()));
+            inner
+                .call(Request::from_parts(parts, Body::from(body)))
+                .await
+        })
+    }
+}
+
+/// [`CachedSignals`] is a request extractor which reads the signals without consuming the body,
+/// so it can be used in middleware and alongside [`ReadSignals`].
+///
+/// The signals of `GET` requests are read from the query string, those of other requests from
+/// the body kept by [`SignalsCacheLayer`]. Without the layer, or without signals, the extraction
+/// fails with [`Error::MissingSignals`].
+#[derive(Debug)]
+pub struct CachedSignals<T>(pub T);
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequestParts<S> for CachedSignals<T> {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let body = match parts.method {
+            http::Method::GET => Bytes::new(),
+            _ => match parts.extensions.get::<CachedBody>() {
+                Some(CachedBody(body)) => body.clone(),
+                None => return Err(Error::MissingSignals),
+            },
+        };
+
+        signals::from_parts(
+            parts.method.as_str(),
+            parts.uri.query(),
+            &parts.headers,
+            &body,
+        )
+        .map(Self)
+    }
+}
+
+fn is_event_stream(res: &Response) -> bool {
+    res.headers()
+        .get(http::header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"text/event-stream"))
+}
+
+fn not_acceptable() -> Response {
+    (
+        http::StatusCode::NOT_ACCEPTABLE,
+        accept::NOT_ACCEPTABLE_MESSAGE,
+    )
+        .into_response()
+}
+
+/// Datastar's headers, as typed [`HeaderName`](http::HeaderName)s.
+///
+/// These are the [`names`](crate::consts::headers::names) of
+/// [`consts::headers`](crate::consts::headers), re-exported for Axum.
+pub mod header {
+    use {crate::consts::ElementPatchMode, axum::http::HeaderValue};
+
+    pub use crate::consts::headers::names::*;
+
+    impl From<ElementPatchMode> for HeaderValue {
+        fn from(value: ElementPatchMode) -> Self {
+            HeaderValue::from_static(value.as_str())
+        }
+    }
+
+    #[cfg(feature = "headers")]
+    pub use self::typed::{DatastarMode, DatastarRequest, DatastarSelector};
+
+    #[cfg(feature = "headers")]
+    mod typed {
+        use {
+            crate::consts::ElementPatchMode,
+            axum::http::{HeaderName, HeaderValue},
+            headers::{Error, Header},
+        };
+
+        static DATASTAR_REQUEST: HeaderName = super::DATASTAR_REQUEST;
+        static DATASTAR_SELECTOR: HeaderName = super::DATASTAR_SELECTOR;
+        static DATASTAR_MODE: HeaderName = super::DATASTAR_MODE;
+
+        fn decode_single<'i, I>(values: &mut I) -> Result<&'i HeaderValue, Error>
+        where
+            I: Iterator<Item = &'i HeaderValue>,
+        {
+            let value = values.next().ok_or_else(Error::invalid)?;
+            if values.next().is_some() {
+                return Err(Error::invalid());
+            }
+            Ok(value)
+        }
+
+        /// The typed `datastar-request` header, which is sent by Datastar with every request.
+        ///
+        /// Like the other typed headers it can be extracted with `axum_extra::TypedHeader`.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use axum::http::HeaderMap;
+        /// use datastar_core::axum::header::DatastarRequest;
+        /// use headers::HeaderMapExt;
+        ///
+        /// let mut headers = HeaderMap::new();
+        /// headers.typed_insert(DatastarRequest);
+        ///
+        /// assert_eq!(headers.typed_get(), Some(DatastarRequest));
+        /// ```
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct DatastarRequest;
+
+        impl Header for DatastarRequest {
+            fn name() -> &'static HeaderName {
+                &DATASTAR_REQUEST
+            }
+
+            fn decode<'i, I>(values: &mut I) -> Result<Self, Error>
+            where
+                I: Iterator<Item = &'i HeaderValue>,
+            {
+                decode_single(values).map(|_| Self)
+            }
+
+            fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+                values.extend(std::iter::once(HeaderValue::from_static("true")));
+            }
+        }
+
+        /// The typed `datastar-selector` header, see [`DATASTAR_SELECTOR`](super::DATASTAR_SELECTOR).
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct DatastarSelector(pub String);
+
+        impl Header for DatastarSelector {
+            fn name() -> &'static HeaderName {
+                &DATASTAR_SELECTOR
+            }
+
+            fn decode<'i, I>(values: &mut I) -> Result<Self, Error>
+            where
+                I: Iterator<Item = &'i HeaderValue>,
+            {
+                let value = decode_single(values)?
+                    .to_str()
+                    .map_err(|_| Error::invalid())?;
+                Ok(Self(value.to_owned()))
+            }
+
+            fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+                if let Ok(value) = HeaderValue::from_str(&self.0) {
+                    values.extend(std::iter::once(value));
+                }
+            }
+        }
+
+        /// The typed `datastar-mode` header, see [`DATASTAR_MODE`](super::DATASTAR_MODE).
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct DatastarMode(pub ElementPatchMode);
+
+        impl Header for DatastarMode {
+            fn name() -> &'static HeaderName {
+                &DATASTAR_MODE
+            }
+
+            fn decode<'i, I>(values: &mut I) -> Result<Self, Error>
+            where
+                I: Iterator<Item = &'i HeaderValue>,
+            {
+                decode_single(values)?
+                    .to_str()
+                    .ok()
+                    .and_then(|mode| mode.parse().ok())
+                    .map(Self)
+                    .ok_or_else(Error::invalid)
+            }
+
+            fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+                values.extend(std::iter::once(HeaderValue::from(self.0)));
+            }
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! [`DatastarEventBatch`] writes several events as a single chunk, so events which belong
+//! together, such as a patch of elements and the signals it updates, leave in one write
+//! rather than one write and TCP packet each.
+//!
+//! The response types of the framework integrations write the events of their stream which
+//! are ready together, using [`BatchExt::ready_batches`].
+//!
+//! A [`DatastarEventBatch`] returned by a handler isn't streamed: the framework integrations
+//! write it as a single `text/event-stream` body of a known length and close the connection
+//! once it's sent, for serverless platforms and proxies which buffer SSE until the response
+//! ends. A finite stream of events can be collected into a batch first, with
+//! `StreamExt::collect`.
+
+use {
+    crate::{
+        DatastarEvent,
+        response::{Batch, IntoDatastarResponse},
+        sender::Transaction,
+    },
+    core::{
+        fmt::{self, Display},
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    futures_core::Stream,
+};
+
+/// The headers of the response of a [`DatastarEventBatch`], as lowercase names and values,
+/// which every framework integration sets so their responses can't drift apart.
+#[cfg(any(feature = "axum", feature = "rocket", feature = "warp"))]
+pub(crate) const RESPONSE_HEADERS: [(&str, &str); 4] = [
+    ("content-type", crate::accept::EVENT_STREAM_MEDIA_TYPE),
+    ("cache-control", "no-cache"),
+    ("expires", "0"),
+    ("connection", "close"),
+];
+
+/// The most events [`ReadyBatches`] puts into a single [`DatastarEventBatch`],
+/// so a fast stream still reaches the client in chunks of a bounded size.
+pub const MAX_READY_EVENTS: usize = 64;
+
+/// [`DatastarEventBatch`] is a group of events which is serialized into one contiguous chunk.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     batch::DatastarEventBatch,
+///     prelude::{PatchElements, PatchSignals},
+/// };
+///
+/// let mut batch = DatastarEventBatch::new();
+/// batch.push(PatchElements::new("<li id='entry-1'>Done</li>"));
+/// batch.push(PatchSignals::new("{total: 1}"));
+///
+/// let mut out = Vec::new();
+/// batch.write_to(&mut out)?;
+///
+/// assert_eq!(
+///     out,
+///     b"event: datastar-patch-elements\ndata: elements <li id='entry-1'>Done</li>\n\n\
+///       event: datastar-patch-signals\ndata: signals {total: 1}\n\n"
+/// );
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatastarEventBatch {
+    events: Vec<DatastarEvent>,
+}
+
+impl DatastarEventBatch {
+    /// Creates a new, empty [`DatastarEventBatch`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds any event to the [`DatastarEventBatch`].
+    pub fn push(&mut self, event: impl Into<DatastarEvent>) {
+        self.events.push(event.into());
+    }
+
+    /// Returns the number of events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns whether there are no events.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Returns the events of the [`DatastarEventBatch`].
+    pub fn events(&self) -> &[DatastarEvent] {
+        &self.events
+    }
+
+    /// Returns the events of the [`DatastarEventBatch`], consuming it.
+    pub fn into_events(self) -> Vec<DatastarEvent> {
+        self.events
+    }
+
+    /// Writes the SSE framing of every event into `out`, which is also what its
+    /// [`Display`] implementation writes.
+    pub fn write_fmt_to(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        self.events
+            .iter()
+            .try_for_each(|event| event.write_fmt_to(out))
+    }
+
+    /// Writes the SSE framing of every event into `out` with a single call to
+    /// [`write_all`](std::io::Write::write_all).
+    pub fn write_to(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        out.write_all(self.to_text().as_bytes())
+    }
+
+    /// Returns the SSE framing of every event as one [`Bytes`](bytes::Bytes) chunk,
+    /// ready to be written to a response body.
+    #[cfg(feature = "bytes")]
+    pub fn to_bytes(&self) -> bytes::Bytes {
+        match self.events.as_slice() {
+            [event] => event.to_bytes(),
+            _ => self.to_text().into(),
+        }
+    }
+
+    pub(crate) fn to_text(&self) -> String {
+        // The data lines are framed already, the rest is small.
+        let capacity = self
+            .events
+            .iter()
+            .map(|event| event.data.framed().0.len() + 64)
+            .sum();
+        let mut out = String::with_capacity(capacity);
+        // Writing to a `String` never fails.
+        let _ = self.write_fmt_to(&mut out);
+        out
+    }
+}
+
+impl Display for DatastarEventBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_fmt_to(f)
+    }
+}
+
+impl From<Vec<DatastarEvent>> for DatastarEventBatch {
+    fn from(events: Vec<DatastarEvent>) -> Self {
+        Self { events }
+    }
+}
+
+impl From<Transaction> for DatastarEventBatch {
+    fn from(transaction: Transaction) -> Self {
+        transaction.into_events().into()
+    }
+}
+
+impl<E: Into<DatastarEvent>> FromIterator<E> for DatastarEventBatch {
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        Self {
+            events: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<E: Into<DatastarEvent>> Extend<E> for DatastarEventBatch {
+    fn extend<I: IntoIterator<Item = E>>(&mut self, iter: I) {
+        self.events.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+impl IntoIterator for DatastarEventBatch {
+    type Item = DatastarEvent;
+    type IntoIter = std::vec::IntoIter<DatastarEvent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.into_iter()
+    }
+}
+
+impl IntoDatastarResponse for DatastarEventBatch {
+    type Stream = Batch;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self.into()
+    }
+}
+
+/// [`BatchExt`] adds the [`ready_batches`](BatchExt::ready_batches) combinator
+/// to every [`Stream`] of [`DatastarEvent`]s.
+pub trait BatchExt: Stream<Item = DatastarEvent> + Sized {
+    /// Groups the events which are ready into a [`DatastarEventBatch`] of up to
+    /// [`MAX_READY_EVENTS`] events, without waiting for more events to arrive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{
+    ///     batch::BatchExt,
+    ///     prelude::{PatchElements, PatchSignals},
+    ///     sender::{self, Transaction},
+    /// };
+    /// use tokio_stream::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (sender, receiver) = sender::channel();
+    /// sender
+    ///     .commit(
+    ///         Transaction::new()
+    ///             .patch_elements(PatchElements::new("<li id='entry-1'>Done</li>"))
+    ///             .patch_signals(PatchSignals::new("{total: 1}")),
+    ///     )
+    ///     .ok();
+    /// sender.close();
+    ///
+    /// let batches = receiver.ready_batches().collect::<Vec<_>>().await;
+    ///
+    /// assert_eq!(batches.len(), 1);
+    /// assert_eq!(batches[0].len(), 2);
+    /// # }
+    /// ```
+    fn ready_batches(self) -> ReadyBatches<Self> {
+        ReadyBatches {
+            stream: Box::pin(self),
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>> BatchExt for S {}
+
+/// [`ReadyBatches`] is the [`Stream`] returned by [`BatchExt::ready_batches`].
+///
+/// It ends when the inner stream ends.
+pub struct ReadyBatches<S> {
+    stream: Pin<Box<S>>,
+    done: bool,
+}
+
+// Nothing is pinned structurally, as the stream is boxed.
+impl<S> Unpin for ReadyBatches<S> {}
+
+impl<S> fmt::Debug for ReadyBatches<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadyBatches")
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>> Stream for ReadyBatches<S> {
+    type Item = DatastarEventBatch;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut batch = DatastarEventBatch::new();
+        while !self.done && batch.len() < MAX_READY_EVENTS {
+            match self.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(event)) => batch.push(event),
+                Poll::Ready(None) => self.done = true,
+                Poll::Pending => break,
+            }
+        }
+
+        match (batch.is_empty(), self.done) {
+            (false, _) => Poll::Ready(Some(batch)),
+            (true, true) => Poll::Ready(None),
+            // The inner stream will wake the task once more events are ready.
+            (true, false) => Poll::Pending,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+        let (lower, upper) = self.stream.size_hint();
+        (lower.min(1), upper)
+    }
+}
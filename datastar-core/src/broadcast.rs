@@ -0,0 +1,547 @@
+//! [`Hub`] fans out events to all clients subscribed to a topic.
+//!
+//! This is the building block for chats, feeds and other pages where an update made
+//! by one client has to be pushed to every other connected client.
+//!
+//! The statistics of every topic are available using [`Hub::stats`], and can be served
+//! to Prometheus using [`Hub::prometheus`]. With the `metrics` feature enabled they are
+//! also recorded using the [`metrics`](https://docs.rs/metrics) facade:
+//!
+//! - `datastar_hub_subscribers` (gauge): the number of subscribers.
+//! - `datastar_hub_events_published_total` (counter): the number of published events.
+//! - `datastar_hub_events_dropped_total` (counter): the number of events skipped by lagging subscribers.
+//! - `datastar_hub_journal_depth` (gauge): the number of events kept for replay.
+//!
+//! All of them are labeled with the `topic`.
+
+use {
+    crate::{
+        DatastarEvent,
+        replay::{ReplayBuffer, RingBuffer},
+        response::IntoDatastarResponse,
+        sender::Transaction,
+    },
+    core::{
+        fmt::{self, Display, Write},
+        future::Future,
+        pin::Pin,
+        sync::atomic::{AtomicU64, Ordering},
+        task::{Context, Poll},
+    },
+    futures_core::Stream,
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::{Arc, Mutex, PoisonError},
+    },
+    tokio::sync::broadcast,
+    tokio_stream::wrappers::{BroadcastStream, errors::BroadcastStreamRecvError},
+};
+
+type SnapshotFuture = Pin<Box<dyn Future<Output = Vec<DatastarEvent>> + Send>>;
+
+/// The default number of events buffered per topic for slow subscribers.
+pub const DEFAULT_CAPACITY: usize = 128;
+
+/// The content type of the Prometheus text exposition format, used by [`HubMetrics`].
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// [`Hub`] fans out [`DatastarEvent`]s to all subscribers of a topic.
+///
+/// Cloning a [`Hub`] is cheap, and all clones share the same topics,
+/// so it can be stored directly in the state of a framework.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{broadcast::Hub, prelude::PatchElements};
+/// use tokio_stream::StreamExt;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let hub = Hub::new();
+/// let mut subscription = hub.subscribe("chat");
+///
+/// hub.publish("chat", PatchElements::new("<li>Hello!</li>").selector("#messages"));
+///
+/// let event = subscription.next().await.unwrap();
+/// assert_eq!(event.data, ["selector #messages", "elements <li>Hello!</li>"]);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Hub {
+    topics: Arc<Mutex<HashMap<String, Topic>>>,
+    capacity: usize,
+    replay_capacity: usize,
+}
+
+struct Topic {
+    /// Sends batches of events, so that the events of a [`Transaction`] are received together.
+    sender: broadcast::Sender<Arc<[DatastarEvent]>>,
+    replay: Option<RingBuffer>,
+    snapshot: Option<Arc<dyn ErasedSnapshotProvider>>,
+    counters: Arc<TopicCounters>,
+}
+
+#[derive(Debug, Default)]
+struct TopicCounters {
+    published: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl fmt::Debug for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Topic")
+            .field("sender", &self.sender)
+            .field("replay", &self.replay)
+            .field("snapshot", &self.snapshot.is_some())
+            .field("counters", &self.counters)
+            .finish()
+    }
+}
+
+/// [`TopicStats`] are the statistics of a topic of a [`Hub`], returned by [`Hub::stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TopicStats {
+    /// The name of the topic.
+    pub topic: String,
+    /// The number of current subscribers.
+    pub subscribers: usize,
+    /// The number of events published so far.
+    pub published: u64,
+    /// The number of events skipped by subscribers which fell behind,
+    /// where a [`Transaction`] counts as a single event.
+    pub dropped: u64,
+    /// The number of events kept for replay.
+    pub journal_depth: usize,
+}
+
+/// [`SnapshotProvider`] renders the current state of a topic for clients that just subscribed,
+/// so they don't have to wait for the next update to see anything.
+///
+/// It is implemented for async closures taking the topic name.
+pub trait SnapshotProvider: Send + Sync + 'static {
+    /// Returns the events which bring a new subscriber of `topic` up to date.
+    fn snapshot(&self, topic: &str) -> impl Future<Output = Vec<DatastarEvent>> + Send;
+}
+
+impl<F, Fut> SnapshotProvider for F
+where
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Vec<DatastarEvent>> + Send,
+{
+    fn snapshot(&self, topic: &str) -> impl Future<Output = Vec<DatastarEvent>> + Send {
+        self(topic.to_owned())
+    }
+}
+
+trait ErasedSnapshotProvider: Send + Sync + 'static {
+    fn snapshot(self: Arc<Self>, topic: String) -> SnapshotFuture;
+}
+
+impl<P: SnapshotProvider> ErasedSnapshotProvider for P {
+    fn snapshot(self: Arc<Self>, topic: String) -> SnapshotFuture {
+        Box::pin(async move { SnapshotProvider::snapshot(&*self, &topic).await })
+    }
+}
+
+impl Hub {
+    /// Creates a new [`Hub`] which buffers [`DEFAULT_CAPACITY`] events per topic.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Creates a new [`Hub`] which buffers `capacity` events per topic.
+    ///
+    /// Subscribers which fall behind by more than `capacity` events skip the oldest ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "the capacity of a Hub must be greater than zero"
+        );
+        Self {
+            topics: Default::default(),
+            capacity,
+            replay_capacity: 0,
+        }
+    }
+
+    /// Keeps the last `capacity` events of every topic, so clients reconnecting with a
+    /// `Last-Event-ID` can resume using [`Hub::subscribe_from`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{broadcast::Hub, prelude::PatchSignals};
+    /// use tokio_stream::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let hub = Hub::new().replay(64);
+    ///
+    /// hub.publish("counter", PatchSignals::new("{count: 1}").id("1"));
+    /// hub.publish("counter", PatchSignals::new("{count: 2}").id("2"));
+    ///
+    /// let mut subscription = hub.subscribe_from("counter", Some("1"));
+    /// assert_eq!(subscription.next().await.unwrap().id.as_deref(), Some("2"));
+    /// # }
+    /// ```
+    pub fn replay(mut self, capacity: usize) -> Self {
+        self.replay_capacity = capacity;
+        self
+    }
+
+    /// Registers the [`SnapshotProvider`] of `topic`, which renders the current state for every
+    /// new subscriber before any live events are sent.
+    ///
+    /// Subscribers resuming with a `Last-Event-ID` only receive a snapshot if no events
+    /// could be replayed for them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{broadcast::Hub, prelude::PatchElements};
+    /// use tokio_stream::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let hub = Hub::new();
+    /// hub.set_snapshot_provider("dashboard", |_topic: String| async {
+    ///     vec![PatchElements::new("<div id='visitors'>42</div>").into()]
+    /// });
+    ///
+    /// let mut subscription = hub.subscribe("dashboard");
+    /// let event = subscription.next().await.unwrap();
+    /// assert_eq!(event.data, ["elements <div id='visitors'>42</div>"]);
+    /// # }
+    /// ```
+    pub fn set_snapshot_provider(&self, topic: &str, provider: impl SnapshotProvider) {
+        let mut topics = self.topics.lock().unwrap_or_else(PoisonError::into_inner);
+        self.topic(&mut topics, topic).snapshot = Some(Arc::new(provider));
+    }
+
+    /// Subscribes to all events published to `topic` from now on.
+    pub fn subscribe(&self, topic: &str) -> Subscription {
+        self.subscribe_from(topic, None)
+    }
+
+    /// Subscribes to all events published to `topic` after the event with `last_event_id`.
+    ///
+    /// The missed events are only available if replay is enabled with [`Hub::replay`],
+    /// otherwise this is the same as [`Hub::subscribe`].
+    pub fn subscribe_from(&self, topic: &str, last_event_id: Option<&str>) -> Subscription {
+        let mut topics = self.topics.lock().unwrap_or_else(PoisonError::into_inner);
+        let name = topic;
+        let topic = self.topic(&mut topics, name);
+
+        let missed: VecDeque<_> = match (last_event_id, &topic.replay) {
+            (Some(last_event_id), Some(replay)) => replay.replay(last_event_id).into(),
+            _ => VecDeque::new(),
+        };
+        let snapshot = match &topic.snapshot {
+            Some(provider) if missed.is_empty() => Some(provider.clone().snapshot(name.to_owned())),
+            _ => None,
+        };
+
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("datastar_hub_subscribers", "topic" => name.to_owned()).increment(1);
+
+        Subscription {
+            snapshot,
+            missed,
+            stream: BroadcastStream::new(topic.sender.subscribe()),
+            counters: topic.counters.clone(),
+            #[cfg(feature = "metrics")]
+            topic: name.to_owned(),
+        }
+    }
+
+    /// Publishes an event to all subscribers of `topic`,
+    /// returning the number of subscribers it was sent to.
+    ///
+    /// A [`PatchElements`](crate::patch_elements::PatchElements) with a
+    /// [`ViewTransition`](crate::patch_elements::ViewTransition) is published without its script,
+    /// which [`commit`](Hub::commit) sends with a
+    /// [`Transaction::patch_elements`](crate::sender::Transaction::patch_elements).
+    pub fn publish(&self, topic: &str, event: impl Into<DatastarEvent>) -> usize {
+        self.send(topic, Arc::new([event.into()]))
+    }
+
+    /// Publishes all events of the [`Transaction`] to the subscribers of `topic`,
+    /// returning the number of subscribers they were sent to.
+    ///
+    /// Subscribers receive the events back to back, without events published concurrently
+    /// in between. Subscribers which fell too far behind skip either all of them or none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{
+    ///     broadcast::Hub,
+    ///     prelude::{ExecuteScript, PatchElements},
+    ///     sender::Transaction,
+    /// };
+    /// use tokio_stream::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let hub = Hub::new();
+    /// let mut subscription = hub.subscribe("chat");
+    ///
+    /// hub.commit(
+    ///     "chat",
+    ///     Transaction::new()
+    ///         .patch_elements(PatchElements::new("<li id='message-7'>Hello!</li>").selector("#messages"))
+    ///         .execute_script(ExecuteScript::new("highlight('message-7')")),
+    /// );
+    ///
+    /// let events: Vec<_> = subscription.take(2).collect().await;
+    /// assert_eq!(events.len(), 2);
+    /// # }
+    /// ```
+    pub fn commit(&self, topic: &str, transaction: Transaction) -> usize {
+        let events = transaction.into_events();
+        if events.is_empty() {
+            return 0;
+        }
+        self.send(topic, events.into())
+    }
+
+    fn send(&self, topic: &str, events: Arc<[DatastarEvent]>) -> usize {
+        let mut topics = self.topics.lock().unwrap_or_else(PoisonError::into_inner);
+        let count = events.len() as u64;
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!("datastar_hub_events_published_total", "topic" => topic.to_owned())
+            .increment(count);
+
+        if self.replay_capacity > 0 {
+            let name = topic;
+            let topic = self.topic(&mut topics, name);
+            topic.counters.published.fetch_add(count, Ordering::Relaxed);
+            if let Some(replay) = &topic.replay {
+                for event in events.iter() {
+                    replay.record(event);
+                }
+
+                #[cfg(feature = "metrics")]
+                metrics::gauge!("datastar_hub_journal_depth", "topic" => name.to_owned())
+                    .set(replay.len() as f64);
+            }
+            return topic.sender.send(events).unwrap_or_default();
+        }
+
+        let Some(entry) = topics.get(topic) else {
+            return 0;
+        };
+        entry.counters.published.fetch_add(count, Ordering::Relaxed);
+        match entry.sender.send(events) {
+            Ok(count) => count,
+            Err(_) => {
+                // Everyone unsubscribed, so the topic can be forgotten,
+                // unless it has to remember its snapshot provider.
+                if entry.snapshot.is_none() {
+                    topics.remove(topic);
+                }
+                0
+            }
+        }
+    }
+
+    fn topic<'t>(&self, topics: &'t mut HashMap<String, Topic>, topic: &str) -> &'t mut Topic {
+        topics.entry(topic.to_owned()).or_insert_with(|| Topic {
+            sender: broadcast::channel(self.capacity).0,
+            replay: (self.replay_capacity > 0).then(|| RingBuffer::new(self.replay_capacity)),
+            snapshot: None,
+            counters: Default::default(),
+        })
+    }
+
+    /// Returns the number of subscribers of `topic`.
+    pub fn subscriber_count(&self, topic: &str) -> usize {
+        self.topics
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(topic)
+            .map_or(0, |topic| topic.sender.receiver_count())
+    }
+
+    /// Returns the statistics of all topics, ordered by name.
+    ///
+    /// Topics without subscribers are forgotten on the next publish, unless replay is enabled
+    /// or they have a [`SnapshotProvider`], which resets their statistics.
+    pub fn stats(&self) -> Vec<TopicStats> {
+        let topics = self.topics.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut stats: Vec<_> = topics
+            .iter()
+            .map(|(name, topic)| TopicStats {
+                topic: name.clone(),
+                subscribers: topic.sender.receiver_count(),
+                published: topic.counters.published.load(Ordering::Relaxed),
+                dropped: topic.counters.dropped.load(Ordering::Relaxed),
+                journal_depth: topic.replay.as_ref().map_or(0, RingBuffer::len),
+            })
+            .collect();
+        stats.sort_by(|a, b| a.topic.cmp(&b.topic));
+        stats
+    }
+
+    /// Renders the statistics of all topics in the Prometheus text exposition format.
+    ///
+    /// The returned [`HubMetrics`] can be returned from a handler of every framework integration,
+    /// to serve it as a Prometheus scrape endpoint. The rate of events per second can be derived
+    /// from the counters using the `rate()` function of Prometheus.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{broadcast::Hub, prelude::PatchSignals};
+    ///
+    /// let hub = Hub::new();
+    /// let _subscription = hub.subscribe("counter");
+    /// hub.publish("counter", PatchSignals::new("{count: 1}"));
+    ///
+    /// let metrics = hub.prometheus().to_string();
+    /// assert!(metrics.contains("datastar_hub_subscribers{topic=\"counter\"} 1\n"));
+    /// assert!(metrics.contains("datastar_hub_events_published_total{topic=\"counter\"} 1\n"));
+    /// ```
+    pub fn prometheus(&self) -> HubMetrics {
+        HubMetrics(self.stats())
+    }
+}
+
+/// [`HubMetrics`] renders the statistics of a [`Hub`] in the Prometheus text exposition format,
+/// created by [`Hub::prometheus`].
+#[derive(Debug, Clone)]
+pub struct HubMetrics(Vec<TopicStats>);
+
+impl Display for HubMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metrics: [(&str, &str, &str, fn(&TopicStats) -> u64); 4] = [
+            (
+                "datastar_hub_subscribers",
+                "gauge",
+                "The number of subscribers of a topic.",
+                |stats| stats.subscribers as u64,
+            ),
+            (
+                "datastar_hub_events_published_total",
+                "counter",
+                "The number of events published to a topic.",
+                |stats| stats.published,
+            ),
+            (
+                "datastar_hub_events_dropped_total",
+                "counter",
+                "The number of events skipped by subscribers which fell behind.",
+                |stats| stats.dropped,
+            ),
+            (
+                "datastar_hub_journal_depth",
+                "gauge",
+                "The number of events of a topic kept for replay.",
+                |stats| stats.journal_depth as u64,
+            ),
+        ];
+
+        for (name, kind, help, value) in metrics {
+            writeln!(f, "# HELP {name} {help}")?;
+            writeln!(f, "# TYPE {name} {kind}")?;
+            for stats in &self.0 {
+                write!(f, "{name}{{topic=\"")?;
+                for c in stats.topic.chars() {
+                    match c {
+                        '\\' => f.write_str("\\\\")?,
+                        '"' => f.write_str("\\\"")?,
+                        '\n' => f.write_str("\\n")?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                writeln!(f, "\"}} {}", value(stats))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Hub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// [`Subscription`] is a [`Stream`] of the events published to a topic of a [`Hub`].
+///
+/// It can be returned from a handler as is, using the framework integrations.
+pub struct Subscription {
+    snapshot: Option<SnapshotFuture>,
+    missed: VecDeque<DatastarEvent>,
+    stream: BroadcastStream<Arc<[DatastarEvent]>>,
+    counters: Arc<TopicCounters>,
+    #[cfg(feature = "metrics")]
+    topic: String,
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription")
+            .field("snapshot", &self.snapshot.is_some())
+            .field("missed", &self.missed)
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+impl Stream for Subscription {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(snapshot) = &mut self.snapshot {
+            let Poll::Ready(events) = snapshot.as_mut().poll(cx) else {
+                return Poll::Pending;
+            };
+            self.snapshot = None;
+            self.missed.extend(events);
+        }
+        loop {
+            if let Some(event) = self.missed.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(events))) => self.missed.extend(events.iter().cloned()),
+                Poll::Ready(Some(Err(BroadcastStreamRecvError::Lagged(skipped)))) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(skipped, "subscriber lagged behind, skipping events");
+
+                    #[cfg(feature = "metrics")]
+                    metrics::counter!("datastar_hub_events_dropped_total", "topic" => self.topic.clone())
+                        .increment(skipped);
+
+                    self.counters.dropped.fetch_add(skipped, Ordering::Relaxed);
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        metrics::gauge!("datastar_hub_subscribers", "topic" => self.topic.clone()).decrement(1);
+    }
+}
+
+impl IntoDatastarResponse for Subscription {
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
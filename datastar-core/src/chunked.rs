@@ -0,0 +1,123 @@
+//! Streaming of very large elements, such as a report of many thousand rows, into a single
+//! patch line by line, without holding all of them in memory.
+//!
+//! [`PatchElements::write_lines_to`] reads the elements from a [`BufRead`], and
+//! [`PatchElements::write_lines_async`] from a [`Stream`] of lines with the `tokio` feature.
+//! Both write the fields of the patch first, then an `elements` data line for every line as
+//! soon as it's read, and end the event once the lines run out.
+//!
+//! The streamed lines are written as they are: the `ammonia` feature can't sanitize them, as
+//! it needs all the elements at once. They must be trusted HTML, as for [`PatchElements::new`].
+
+use {
+    crate::{DatastarEvent, consts, patch_elements::PatchElements},
+    std::io::{self, BufRead, Write},
+};
+#[cfg(feature = "tokio")]
+use {
+    core::{future::poll_fn, pin::pin},
+    futures_core::Stream,
+};
+
+impl PatchElements {
+    /// Writes the [`PatchElements`] into `out` as a single event, with the elements read line
+    /// by line from `lines`, after the elements of the patch if it has any.
+    ///
+    /// The event is written in many small pieces, so `out` should be buffered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{consts::ElementPatchMode, prelude::PatchElements};
+    ///
+    /// let report = "<tr><td>1</td></tr>\n<tr><td>2</td></tr>\n";
+    /// let mut out = Vec::new();
+    /// PatchElements::default()
+    ///     .selector("#report")
+    ///     .mode(ElementPatchMode::Inner)
+    ///     .write_lines_to(report.as_bytes(), &mut out)?;
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(out).unwrap(),
+    ///     "event: datastar-patch-elements\ndata: selector #report\ndata: mode inner\n\
+    ///      data: elements <tr><td>1</td></tr>\ndata: elements <tr><td>2</td></tr>\n\n",
+    /// );
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn write_lines_to(&self, mut lines: impl BufRead, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(DatastarEvent::from(self).open_framing().as_bytes())?;
+
+        let mut line = String::new();
+        while lines.read_line(&mut line)? > 0 {
+            for piece in framed_lines(&line) {
+                out.write_all(piece.as_bytes())?;
+            }
+            line.clear();
+        }
+
+        out.write_all(b"\n")
+    }
+
+    /// Writes the [`PatchElements`] into the tokio [`AsyncWrite`](tokio::io::AsyncWrite) `out`
+    /// as a single event and flushes it, with the elements of every item of `lines` written as
+    /// soon as it's yielded, after the elements of the patch if it has any.
+    ///
+    /// Every item starts a new line, and may hold several lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::PatchElements;
+    /// use tokio_stream::{self as stream, StreamExt};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> std::io::Result<()> {
+    /// let rows = stream::iter(0..3).map(|row| format!("<li>{row}</li>"));
+    ///
+    /// let mut out = Vec::new();
+    /// PatchElements::default()
+    ///     .selector("#rows")
+    ///     .write_lines_async(rows, &mut out)
+    ///     .await?;
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(out).unwrap(),
+    ///     "event: datastar-patch-elements\ndata: selector #rows\ndata: elements <li>0</li>\n\
+    ///      data: elements <li>1</li>\ndata: elements <li>2</li>\n\n",
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn write_lines_async(
+        &self,
+        lines: impl Stream<Item = impl AsRef<str>>,
+        out: &mut (impl tokio::io::AsyncWrite + Unpin),
+    ) -> io::Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        out.write_all(DatastarEvent::from(self).open_framing().as_bytes())
+            .await?;
+
+        let mut lines = pin!(lines);
+        while let Some(text) = poll_fn(|cx| lines.as_mut().poll_next(cx)).await {
+            for piece in framed_lines(text.as_ref()) {
+                out.write_all(piece.as_bytes()).await?;
+            }
+        }
+
+        out.write_all(b"\n").await?;
+        out.flush().await
+    }
+}
+
+/// Returns the pieces of an `elements` data line for every line of `text`.
+fn framed_lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut lines = text.lines();
+    let first = lines.next().unwrap_or_default();
+    // A lone carriage return ends the line in SSE, so it has to start a new data line.
+    core::iter::once(first)
+        .chain(lines)
+        .flat_map(|line| line.split('\r'))
+        .flat_map(|line| ["data: ", consts::ELEMENTS_DATALINE_LITERAL, " ", line, "\n"])
+}
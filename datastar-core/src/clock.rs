@@ -0,0 +1,136 @@
+//! [`Clock`] abstracts the time source used for timers, such as the [`heartbeat`](crate::heartbeat).
+//!
+//! This keeps the crate usable where no tokio timer is available, such as on wasm32,
+//! and allows tests to drive timers deterministically using a [`MockClock`].
+
+use {
+    core::{
+        fmt,
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+        time::Duration,
+    },
+    std::sync::{Arc, Mutex, PoisonError},
+};
+
+/// [`Clock`] is a source of time which can sleep.
+pub trait Clock: Send + Sync + 'static {
+    /// The [`Future`] returned by [`Clock::sleep`].
+    type Sleep: Future<Output = ()> + Send + 'static;
+
+    /// Returns the time elapsed since an arbitrary, fixed point in time.
+    fn now(&self) -> Duration;
+
+    /// Returns a [`Future`] which completes after `duration` has elapsed.
+    fn sleep(&self, duration: Duration) -> Self::Sleep;
+}
+
+/// [`TokioClock`] is a [`Clock`] using the tokio timer.
+///
+/// It respects paused time in tokio tests.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+#[cfg(feature = "tokio")]
+impl Clock for TokioClock {
+    type Sleep = tokio::time::Sleep;
+
+    fn now(&self) -> Duration {
+        static START: std::sync::OnceLock<tokio::time::Instant> = std::sync::OnceLock::new();
+        START.get_or_init(tokio::time::Instant::now).elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) -> Self::Sleep {
+        tokio::time::sleep(duration)
+    }
+}
+
+/// [`MockClock`] is a [`Clock`] which only advances when told to, for deterministic tests.
+///
+/// Cloning a [`MockClock`] returns a handle to the same clock.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+/// use datastar_core::clock::{Clock, MockClock};
+///
+/// let clock = MockClock::new();
+/// let sleep = clock.sleep(Duration::from_secs(15));
+///
+/// clock.advance(Duration::from_secs(15));
+///
+/// assert_eq!(clock.now(), Duration::from_secs(15));
+/// # drop(sleep);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockClock(Arc<Mutex<MockState>>);
+
+#[derive(Debug, Default)]
+struct MockState {
+    now: Duration,
+    wakers: Vec<Waker>,
+}
+
+impl MockClock {
+    /// Creates a new [`MockClock`], starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advances the clock by `duration`, completing all sleeps which are due.
+    pub fn advance(&self, duration: Duration) {
+        let wakers = {
+            let mut state = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+            state.now += duration;
+            core::mem::take(&mut state.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+impl Clock for MockClock {
+    type Sleep = MockSleep;
+
+    fn now(&self) -> Duration {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).now
+    }
+
+    fn sleep(&self, duration: Duration) -> Self::Sleep {
+        MockSleep {
+            deadline: self.now() + duration,
+            clock: self.clone(),
+        }
+    }
+}
+
+/// [`MockSleep`] is the [`Future`] returned by [`MockClock::sleep`].
+pub struct MockSleep {
+    clock: MockClock,
+    deadline: Duration,
+}
+
+impl fmt::Debug for MockSleep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockSleep")
+            .field("deadline", &self.deadline)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Future for MockSleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.clock.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.now >= self.deadline {
+            return Poll::Ready(());
+        }
+        state.wakers.push(cx.waker().clone());
+        Poll::Pending
+    }
+}
@@ -0,0 +1,266 @@
+//! [`CoalesceSignals`] merges bursts of [`PatchSignals`] events into a single patch,
+//! for high-frequency updates such as counters and progress bars where the intermediate
+//! states don't matter.
+//!
+//! Signal patches are JSON merge patches, so the merged patch has the same effect as
+//! applying the patches one after another.
+
+#[cfg(feature = "tokio")]
+use crate::clock::TokioClock;
+use {
+    crate::{
+        DatastarEvent,
+        clock::Clock,
+        consts::{self, EventType},
+        parse,
+        patch_signals::PatchSignals,
+        response::IntoDatastarResponse,
+    },
+    core::{
+        fmt,
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+        time::Duration,
+    },
+    futures_core::Stream,
+    serde_json::{Map, Value},
+    std::borrow::Cow,
+};
+
+/// [`CoalesceExt`] adds the [`coalesce_signals`](CoalesceExt::coalesce_signals) combinator
+/// to every [`Stream`] of [`DatastarEvent`]s.
+pub trait CoalesceExt: Stream<Item = DatastarEvent> + Sized {
+    /// Merges consecutive [`PatchSignals`] events into one patch, which is sent at most
+    /// `window` after the first patch it contains.
+    ///
+    /// Any other event first sends the merged patch, so the order of the events is kept.
+    /// Patches which aren't JSON objects or set `onlyIfMissing` are passed on as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use datastar_core::{coalesce::CoalesceExt, prelude::PatchSignals, sender};
+    ///
+    /// let (sender, receiver) = sender::channel();
+    ///
+    /// for percent in 0..=100 {
+    ///     sender
+    ///         .patch_signals(PatchSignals::new(format!(r#"{{"progress":{percent}}}"#)))
+    ///         .ok();
+    /// }
+    ///
+    /// // Return this from a handler instead of `receiver`.
+    /// let response = receiver.coalesce_signals(Duration::from_millis(100));
+    /// # drop((sender, response));
+    /// ```
+    #[cfg(feature = "tokio")]
+    fn coalesce_signals(self, window: Duration) -> CoalesceSignals<Self, TokioClock> {
+        self.coalesce_signals_clock(window, TokioClock)
+    }
+
+    /// Merges consecutive [`PatchSignals`] events into one patch, which is sent at most
+    /// `window` after the first patch it contains, as measured by the given [`Clock`].
+    fn coalesce_signals_clock<C: Clock>(
+        self,
+        window: Duration,
+        clock: C,
+    ) -> CoalesceSignals<Self, C> {
+        CoalesceSignals {
+            stream: Box::pin(self),
+            window,
+            clock,
+            pending: None,
+            next: None,
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>> CoalesceExt for S {}
+
+/// [`CoalesceSignals`] is the [`Stream`] returned by [`CoalesceExt::coalesce_signals`].
+///
+/// It ends when the inner stream ends, after sending the patch merged so far.
+pub struct CoalesceSignals<S, C: Clock> {
+    stream: Pin<Box<S>>,
+    window: Duration,
+    clock: C,
+    /// The patch merged so far, along with the end of its window.
+    pending: Option<Pending<C::Sleep>>,
+    /// An event received while a patch was pending, which is sent right after it.
+    next: Option<DatastarEvent>,
+    done: bool,
+}
+
+// Nothing is pinned structurally, as the stream and the sleep are boxed.
+impl<S, C: Clock> Unpin for CoalesceSignals<S, C> {}
+
+impl<S, C: Clock> fmt::Debug for CoalesceSignals<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CoalesceSignals")
+            .field("window", &self.window)
+            .field(
+                "pending",
+                &self.pending.as_ref().map(|pending| &pending.signals),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+struct Pending<Sleep> {
+    signals: Map<String, Value>,
+    id: Option<Cow<'static, str>>,
+    retry: Option<Duration>,
+    deadline: Pin<Box<Sleep>>,
+}
+
+impl<Sleep> Pending<Sleep> {
+    fn into_event(self) -> DatastarEvent {
+        let mut patch = PatchSignals::from(Value::Object(self.signals));
+        patch.id = self.id;
+        patch.retry = self.retry;
+        patch.into_datastar_event()
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>, C: Clock> CoalesceSignals<S, C> {
+    /// Starts a new pending patch, returning the previous one.
+    fn start(
+        &mut self,
+        signals: Map<String, Value>,
+        event: &DatastarEvent,
+    ) -> Option<DatastarEvent> {
+        let previous = self.pending.take().map(Pending::into_event);
+        self.pending = Some(Pending {
+            signals,
+            id: event.id.clone(),
+            retry: event.retry,
+            deadline: Box::pin(self.clock.sleep(self.window)),
+        });
+        previous
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>, C: Clock> Stream for CoalesceSignals<S, C> {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(event) = self.next.take() {
+            return Poll::Ready(Some(event));
+        }
+
+        while !self.done {
+            let event = match self.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(event)) => event,
+                Poll::Ready(None) => {
+                    self.done = true;
+                    break;
+                }
+                Poll::Pending => {
+                    let Some(pending) = &mut self.pending else {
+                        return Poll::Pending;
+                    };
+                    futures_core::ready!(pending.deadline.as_mut().poll(cx));
+                    return Poll::Ready(self.pending.take().map(Pending::into_event));
+                }
+            };
+
+            let Some(signals) = mergeable_signals(&event) else {
+                return match self.pending.take() {
+                    Some(pending) => {
+                        self.next = Some(event);
+                        Poll::Ready(Some(pending.into_event()))
+                    }
+                    None => Poll::Ready(Some(event)),
+                };
+            };
+
+            match &mut self.pending {
+                Some(pending) if composable(&pending.signals, &signals) => {
+                    merge(&mut pending.signals, signals);
+                    pending.id = event.id;
+                    pending.retry = event.retry;
+                }
+                _ => {
+                    if let Some(previous) = self.start(signals, &event) {
+                        return Poll::Ready(Some(previous));
+                    }
+                }
+            }
+        }
+
+        Poll::Ready(self.pending.take().map(Pending::into_event))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = usize::from(self.pending.is_some()) + usize::from(self.next.is_some());
+        let (lower, upper) = self.stream.size_hint();
+        (
+            lower.min(1).max(buffered),
+            upper.and_then(|upper| upper.checked_add(buffered)),
+        )
+    }
+}
+
+impl<S, C> IntoDatastarResponse for CoalesceSignals<S, C>
+where
+    S: Stream<Item = DatastarEvent> + Send + 'static,
+    C: Clock,
+{
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
+
+/// Returns the signals of a plain [`PatchSignals`] event, whose signals are a JSON object.
+fn mergeable_signals(event: &DatastarEvent) -> Option<Map<String, Value>> {
+    let plain = event.event == Some(EventType::PatchSignals)
+        && event.custom_event.is_none()
+        && event.comments.is_empty()
+        && event.fields.is_empty();
+    if !plain {
+        return None;
+    }
+
+    let mut json = String::new();
+    for line in &event.data {
+        match parse::data_line(line) {
+            (consts::SIGNALS_DATALINE_LITERAL, value) => {
+                json.push_str(value);
+                json.push('\n');
+            }
+            (consts::ONLY_IF_MISSING_DATALINE_LITERAL, "false") => {}
+            _ => return None,
+        }
+    }
+    serde_json::from_str(&json).ok()
+}
+
+/// Returns whether `next` can be merged into `merged`.
+///
+/// A patch replaces a signal which isn't an object, so a nested patch can't follow it:
+/// applied on its own it patches an empty object, merged it would patch the current value.
+fn composable(merged: &Map<String, Value>, next: &Map<String, Value>) -> bool {
+    next.iter()
+        .all(|(key, value)| match (merged.get(key), value) {
+            (Some(Value::Object(merged)), Value::Object(next)) => composable(merged, next),
+            (Some(_), Value::Object(_)) => false,
+            _ => true,
+        })
+}
+
+/// Merges `next` into `merged`, which [`composable`] allows.
+fn merge(merged: &mut Map<String, Value>, next: Map<String, Value>) {
+    for (key, value) in next {
+        match (merged.get_mut(&key), value) {
+            (Some(Value::Object(merged)), Value::Object(next)) => merge(merged, next),
+            (_, value) => {
+                merged.insert(key, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! Reusable server-side components, built from the events of this crate.
+//!
+//! [`Feed`] is an activity feed: a bounded list of entries with a [`Status`] each,
+//! whose counters are kept in signals.
+
+use {
+    crate::{
+        consts::ElementPatchMode, html::el, patch_elements::PatchElements, patch_signals,
+        sender::Transaction,
+    },
+    core::{
+        fmt::{self, Display, Write},
+        str::FromStr,
+    },
+};
+
+/// [`Status`] is the level of an entry of a [`Feed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Status {
+    /// Something finished successfully.
+    Done,
+    /// Something needs attention.
+    Warn,
+    /// Something failed.
+    Fail,
+    /// Something happened.
+    Info,
+}
+
+impl Status {
+    /// Returns the lowercase name of the [`Status`], which is also the name of its counter signal.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Done => "done",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+            Self::Info => "info",
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = ParseStatusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "done" => Ok(Self::Done),
+            "warn" => Ok(Self::Warn),
+            "fail" => Ok(Self::Fail),
+            "info" => Ok(Self::Info),
+            _ => Err(ParseStatusError(s.to_owned())),
+        }
+    }
+}
+
+/// [`ParseStatusError`] is returned when parsing a [`Status`] from an unknown name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseStatusError(String);
+
+impl Display for ParseStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown status: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseStatusError {}
+
+/// [`Counts`] are the counters of a [`Feed`], which are patched into signals of the same names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Counts {
+    /// The number of entries pushed, regardless of their status.
+    pub total: u64,
+    /// The number of [`Status::Done`] entries.
+    pub done: u64,
+    /// The number of [`Status::Warn`] entries.
+    pub warn: u64,
+    /// The number of [`Status::Fail`] entries.
+    pub fail: u64,
+    /// The number of [`Status::Info`] entries.
+    pub info: u64,
+}
+
+impl Counts {
+    /// Returns the counter of `status`.
+    pub fn get(&self, status: Status) -> u64 {
+        match status {
+            Status::Done => self.done,
+            Status::Warn => self.warn,
+            Status::Fail => self.fail,
+            Status::Info => self.info,
+        }
+    }
+
+    fn get_mut(&mut self, status: Status) -> &mut u64 {
+        match status {
+            Status::Done => &mut self.done,
+            Status::Warn => &mut self.warn,
+            Status::Fail => &mut self.fail,
+            Status::Info => &mut self.info,
+        }
+    }
+}
+
+/// [`Feed`] renders entries into the container element with the given id,
+/// and keeps the [`Counts`] of the entries in signals.
+///
+/// Every entry is wrapped in a `<div>` with the id `{id}-{n}` and a `data-status` attribute,
+/// where `n` is the running total. With a [`capacity`](Feed::capacity), the oldest entry is
+/// removed once it is exceeded, so the page doesn't grow without bounds.
+///
+/// [`Feed::push`] returns a [`Transaction`], which keeps the element and signal patches of an
+/// entry together. It can be returned from a handler as a [`Batch`](crate::response::Batch),
+/// sent with [`DatastarSender::commit`](crate::sender::DatastarSender::commit), or published
+/// to every subscriber of a topic with `Hub::commit` of the `broadcast` feature.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     components::{Counts, Feed, Status},
+///     html::el,
+/// };
+///
+/// // Continue counting from the signals sent by the client.
+/// let mut feed = Feed::new("feed")
+///     .capacity(100)
+///     .with_counts(Counts { total: 100, done: 100, ..Counts::default() });
+///
+/// let transaction = feed.push(Status::Warn, el("span").text("Disk almost full"));
+/// let events = transaction.events();
+///
+/// assert_eq!(
+///     events[0].data,
+///     [
+///         "selector #feed",
+///         "mode prepend",
+///         r#"elements <div id="feed-101" data-status="warn"><span>Disk almost full</span></div>"#,
+///     ],
+/// );
+/// // The 101st entry pushes the first one out.
+/// assert_eq!(events[1].data, ["selector #feed-1", "mode remove"]);
+/// assert_eq!(events[2].data, [r#"signals {"total":101,"warn":1}"#]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Feed {
+    id: String,
+    signals: String,
+    mode: ElementPatchMode,
+    capacity: Option<usize>,
+    counts: Counts,
+}
+
+impl Feed {
+    /// Creates a new [`Feed`] rendering into the element with the given `id`.
+    ///
+    /// New entries are prepended inside of it, and the counters are top-level signals.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            signals: String::new(),
+            mode: ElementPatchMode::Prepend,
+            capacity: None,
+            counts: Counts::default(),
+        }
+    }
+
+    /// Sets where new entries are inserted relative to the container element,
+    /// which is one of [`Prepend`](ElementPatchMode::Prepend), [`Append`](ElementPatchMode::Append),
+    /// [`Before`](ElementPatchMode::Before) and [`After`](ElementPatchMode::After).
+    pub fn mode(mut self, mode: ElementPatchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the maximum number of entries which are kept on the page.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Nests the counter signals under the dot separated `path`, such as `feed.counts`.
+    pub fn signals(mut self, path: impl Into<String>) -> Self {
+        self.signals = path.into();
+        self
+    }
+
+    /// Continues counting from `counts`, such as the counters sent by the client.
+    pub fn with_counts(mut self, counts: Counts) -> Self {
+        self.counts = counts;
+        self
+    }
+
+    /// Returns the current [`Counts`] of the [`Feed`].
+    pub fn counts(&self) -> Counts {
+        self.counts
+    }
+
+    /// Adds an entry with the given `status` and trusted `html`, returning the events
+    /// which patch it into the page and update the counters.
+    pub fn push(&mut self, status: Status, html: impl Into<String>) -> Transaction {
+        self.counts.total += 1;
+        *self.counts.get_mut(status) += 1;
+        let total = self.counts.total;
+
+        let entry = el("div")
+            .id(format!("{}-{total}", self.id))
+            .attr("data-status", status.as_str())
+            .raw(html);
+        let mut transaction = Transaction::new().patch_elements(
+            PatchElements::new(entry)
+                .selector(format!("#{}", self.id))
+                .mode(self.mode),
+        );
+
+        if let Some(oldest) = self
+            .capacity
+            .and_then(|capacity| total.checked_sub(capacity as u64))
+            .filter(|&oldest| oldest > 0)
+        {
+            transaction = transaction
+                .patch_elements(PatchElements::new_remove(format!("#{}-{oldest}", self.id)));
+        }
+
+        let mut counts = format!(r#"{{"total":{total},"#);
+        patch_signals::write_json_string(&mut counts, status.as_str());
+        // Writing to a `String` never fails.
+        let _ = write!(counts, ":{}}}", self.counts.get(status));
+        transaction.patch_signals(patch_signals::at_path(&self.signals, &counts))
+    }
+}
@@ -0,0 +1,82 @@
+//! Decoding of request bodies sent with a `Content-Encoding`, for large signals such as
+//! state persisted in local storage which the client compresses before sending.
+//!
+//! With the `compression` feature the signal extractors decode `gzip`, `deflate` and `br`
+//! bodies, up to a limit on the decoded size so a small body can't expand without bound.
+//! Without it, and for any other encoding, they fail with [`Error::UnsupportedEncoding`].
+
+#[cfg(feature = "compression")]
+use std::io::Read;
+
+use crate::Error;
+
+/// The limit on the decoded size of a body of the Axum extractors and of
+/// [`signals::from_parts`](crate::signals::from_parts).
+///
+/// The Warp filters and the Rocket data guards limit the decoded body like the encoded one.
+pub const DEFAULT_DECOMPRESSED_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Decodes `body`, sent with the `Content-Encoding` header `content_encoding`, into at most
+/// `limit` bytes.
+///
+/// Returns `None` if the body isn't encoded.
+pub(crate) fn decode_body(
+    content_encoding: Option<&str>,
+    body: &[u8],
+    limit: usize,
+) -> Result<Option<Vec<u8>>, Error> {
+    // The encodings are listed in the order they were applied.
+    let mut encodings = content_encoding
+        .into_iter()
+        .flat_map(|encoding| encoding.split(','))
+        .map(str::trim)
+        .filter(|encoding| !encoding.is_empty() && !encoding.eq_ignore_ascii_case("identity"))
+        .rev()
+        .peekable();
+
+    if encodings.peek().is_none() {
+        return Ok(None);
+    }
+
+    let mut decoded = body.to_vec();
+    for encoding in encodings {
+        decoded = decode(encoding, &decoded, limit)?;
+    }
+    Ok(Some(decoded))
+}
+
+#[cfg(feature = "compression")]
+fn decode(encoding: &str, body: &[u8], limit: usize) -> Result<Vec<u8>, Error> {
+    let reader: Box<dyn Read + '_> = match encoding.to_ascii_lowercase().as_str() {
+        "gzip" | "x-gzip" => Box::new(flate2::read::MultiGzDecoder::new(body)),
+        "deflate" => Box::new(flate2::read::ZlibDecoder::new(body)),
+        "br" => Box::new(brotli_decompressor::Decompressor::new(body, 4096)),
+        _ => return unsupported(encoding),
+    };
+
+    let mut decoded = Vec::new();
+    // Read one byte past the limit to tell a body at the limit from a larger one.
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|err| Error::Body(Box::new(err)))?;
+    if decoded.len() > limit {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(encoding, limit, "decoded request body exceeds the limit");
+
+        return Err(Error::PayloadTooLarge);
+    }
+    Ok(decoded)
+}
+
+#[cfg(not(feature = "compression"))]
+fn decode(encoding: &str, _body: &[u8], _limit: usize) -> Result<Vec<u8>, Error> {
+    unsupported(encoding)
+}
+
+fn unsupported(encoding: &str) -> Result<Vec<u8>, Error> {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(encoding, "request body has an unsupported encoding");
+
+    Err(Error::UnsupportedEncoding(encoding.to_owned()))
+}
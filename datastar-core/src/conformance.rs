@@ -0,0 +1,218 @@
+//! Conformance tests against the golden output of the SDK test suite, with the `serde` feature.
+//!
+//! The Datastar SDK test suite sends a handler a JSON list of events, and compares the bytes of
+//! the response with the output of the reference SDK. The [cases](cases) of this module are the
+//! `sdk/test-golden` directory of the Datastar repository, vendored in the `golden` directory of
+//! this crate by `scripts/update-golden.sh` with their upstream names, and embedded so an
+//! application or integration can check its own event producer in a unit test, without running
+//! the suite's HTTP harness. `golden/SOURCE` records the upstream commit they were taken from.
+//!
+//! [`run`] feeds every case to an event producer and diffs what it emits with the golden
+//! output, which is written the way [`SerializeOptions::strict`] writes events. The producer
+//! decides how the events are written, so it can go through a web framework and be compared
+//! with what a browser would receive. A global `ammonia` policy changes the elements which are
+//! written, so the cases with multiline elements fail with one.
+//!
+//! # Examples
+//!
+//! ```
+//! use datastar_core::{conformance, serialize::SerializeOptions};
+//!
+//! conformance::run(|case| {
+//!     case.events
+//!         .iter()
+//!         .map(|event| event.to_string_with(SerializeOptions::strict()))
+//!         .collect::<String>()
+//! })?;
+//! # Ok::<(), conformance::Failures>(())
+//! ```
+//!
+//! [`SerializeOptions::strict`]: crate::serialize::SerializeOptions::strict
+
+use {
+    crate::{DatastarEvent, consts},
+    core::{error::Error, fmt, future::Future, time::Duration},
+    serde::Deserialize,
+};
+
+/// A case of the test suite.
+#[derive(Debug, Clone)]
+pub struct Case {
+    /// The name of the case, as in the test suite.
+    pub name: &'static str,
+    /// The JSON the test suite sends, an object with the list of `events`.
+    pub input: &'static str,
+    /// The events of [`input`](Self::input), passed through [`without_default_retry`].
+    pub events: Vec<DatastarEvent>,
+    /// The golden output of the reference SDK.
+    pub expected: &'static str,
+}
+
+/// A [`Case`] whose emitted bytes differ from its golden output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// The name of the case.
+    pub name: &'static str,
+    /// The golden output.
+    pub expected: &'static str,
+    /// The emitted bytes, lossily converted to UTF-8.
+    pub actual: String,
+}
+
+/// [`Failures`] is returned by [`run`] when some cases didn't produce their golden output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failures(pub Vec<Failure>);
+
+/// Returns the cases of the test suite.
+pub fn cases() -> Vec<Case> {
+    #[derive(Deserialize)]
+    struct Input {
+        events: Vec<DatastarEvent>,
+    }
+
+    CASES
+        .iter()
+        .map(|&(name, input, expected)| Case {
+            name,
+            input,
+            events: serde_json::from_str::<Input>(input)
+                .expect("the embedded cases are valid")
+                .events
+                .into_iter()
+                .map(without_default_retry)
+                .collect(),
+            expected,
+        })
+        .collect()
+}
+
+/// Leaves out the `retry` of `event` if it's the default of the SSE specification, as the
+/// reference SDK doesn't send a default `retryDuration` of the test suite.
+///
+/// Deserializing an event keeps its `retryDuration`, so a handler of the test suite passes
+/// the events it receives through this.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+/// use datastar_core::{DatastarEvent, conformance};
+///
+/// let event: DatastarEvent =
+///     serde_json::from_str(r#"{"type": "patchSignals", "signals": {}, "retryDuration": 1000}"#)?;
+/// assert_eq!(event.retry, Some(Duration::from_secs(1)));
+///
+/// assert_eq!(conformance::without_default_retry(event).retry, None);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+pub fn without_default_retry(mut event: DatastarEvent) -> DatastarEvent {
+    if event.retry == Some(Duration::from_millis(consts::DEFAULT_SSE_RETRY_DURATION)) {
+        event.retry = None;
+    }
+    event
+}
+
+/// Feeds every case to `producer`, and compares the bytes it emits with the golden output.
+///
+/// Returns the [`Failures`] of all the cases which differ, whose [`Display`](fmt::Display)
+/// implementation is a line diff of every one.
+pub fn run<B: AsRef<[u8]>>(mut producer: impl FnMut(&Case) -> B) -> Result<(), Failures> {
+    check(cases().iter().map(|case| (case, producer(case))))
+}
+
+/// Feeds every case to the asynchronous `producer` in turn, such as a handler of a web
+/// framework, and compares the bytes it emits with the golden output.
+pub async fn run_async<B, F>(mut producer: impl FnMut(&Case) -> F) -> Result<(), Failures>
+where
+    B: AsRef<[u8]>,
+    F: Future<Output = B>,
+{
+    let cases = cases();
+    let mut emitted = Vec::with_capacity(cases.len());
+    for case in &cases {
+        emitted.push((case, producer(case).await));
+    }
+    check(emitted)
+}
+
+fn check<'a, B: AsRef<[u8]>>(
+    emitted: impl IntoIterator<Item = (&'a Case, B)>,
+) -> Result<(), Failures> {
+    let failures: Vec<Failure> = emitted
+        .into_iter()
+        .filter(|(case, bytes)| bytes.as_ref() != case.expected.as_bytes())
+        .map(|(case, bytes)| Failure {
+            name: case.name,
+            expected: case.expected,
+            actual: String::from_utf8_lossy(bytes.as_ref()).into_owned(),
+        })
+        .collect();
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(Failures(failures))
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "case `{}` differs from its golden output:", self.name)?;
+        let expected: Vec<&str> = self.expected.split_inclusive('\n').collect();
+        let actual: Vec<&str> = self.actual.split_inclusive('\n').collect();
+        for (sign, line) in diff(&expected, &actual) {
+            writeln!(f, "{sign} {line:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Failures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} of {} cases failed", self.0.len(), CASES.len())?;
+        for failure in &self.0 {
+            write!(f, "\n{failure}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Failure {}
+
+impl Error for Failures {}
+
+/// Returns the lines of `expected` and `actual` with their longest common subsequence, marked
+/// with ` `, and the lines only in `expected` or `actual`, marked with `-` or `+`.
+fn diff<'a>(expected: &[&'a str], actual: &[&'a str]) -> Vec<(char, &'a str)> {
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::with_capacity(expected.len().max(actual.len()));
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            lines.push((' ', expected[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if j == actual.len() || (i < expected.len() && common[i + 1][j] >= common[i][j + 1])
+        {
+            lines.push(('-', expected[i]));
+            i += 1;
+        } else {
+            lines.push(('+', actual[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// The name, input and golden output of every case, vendored in `golden` by
+/// `scripts/update-golden.sh`.
+const CASES: &[(&str, &str, &str)] = include!("../golden/cases.rs");
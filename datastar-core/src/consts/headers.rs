@@ -0,0 +1,59 @@
+//! The names of the headers of the requests Datastar sends and of the plain HTML responses
+//! it reads, shared by the framework integrations.
+//!
+//! With the `http` feature, [`names`] has them as typed [`HeaderName`](http::HeaderName)s.
+//!
+//! # Examples
+//!
+//! ```
+//! use datastar_core::consts::headers;
+//!
+//! assert_eq!(headers::DATASTAR_REQUEST, "datastar-request");
+//! ```
+
+/// Sent by Datastar with every request it makes.
+pub const DATASTAR_REQUEST: &str = super::DATASTAR_REQ_HEADER_STR;
+
+/// A CSS selector for the target elements to patch.
+pub const DATASTAR_SELECTOR: &str = "datastar-selector";
+
+/// How to patch the elements (See [`ElementPatchMode`](super::ElementPatchMode)). Defaults to
+/// [`ElementPatchMode::Outer`](super::ElementPatchMode::Outer).
+pub const DATASTAR_MODE: &str = "datastar-mode";
+
+/// Whether to use the [View Transition API](https://developer.mozilla.org/en-US/docs/Web/API/View_Transition_API)
+/// when patching elements.
+pub const DATASTAR_USE_VIEW_TRANSITION: &str = "datastar-use-view-transition";
+
+/// If set to true, only patch signals that don’t already exist.
+pub const DATASTAR_ONLY_IF_MISSING: &str = "datastar-only-if-missing";
+
+/// Sets the script element’s attributes using a JSON encoded string.
+pub const DATASTAR_SCRIPT_ATTRIBUTES: &str = "datastar-script-attributes";
+
+/// The header names as typed [`HeaderName`](http::HeaderName)s, with the `http` feature.
+#[cfg(feature = "http")]
+pub mod names {
+    use http::HeaderName;
+
+    /// Sent by Datastar with every request it makes.
+    pub const DATASTAR_REQUEST: HeaderName = HeaderName::from_static(super::DATASTAR_REQUEST);
+
+    /// A CSS selector for the target elements to patch.
+    pub const DATASTAR_SELECTOR: HeaderName = HeaderName::from_static(super::DATASTAR_SELECTOR);
+
+    /// How to patch the elements, see [`DATASTAR_MODE`](super::DATASTAR_MODE).
+    pub const DATASTAR_MODE: HeaderName = HeaderName::from_static(super::DATASTAR_MODE);
+
+    /// Whether to use the View Transition API when patching elements.
+    pub const DATASTAR_USE_VIEW_TRANSITION: HeaderName =
+        HeaderName::from_static(super::DATASTAR_USE_VIEW_TRANSITION);
+
+    /// If set to true, only patch signals that don’t already exist.
+    pub const DATASTAR_ONLY_IF_MISSING: HeaderName =
+        HeaderName::from_static(super::DATASTAR_ONLY_IF_MISSING);
+
+    /// Sets the script element’s attributes using a JSON encoded string.
+    pub const DATASTAR_SCRIPT_ATTRIBUTES: HeaderName =
+        HeaderName::from_static(super::DATASTAR_SCRIPT_ATTRIBUTES);
+}
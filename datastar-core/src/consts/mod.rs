@@ -0,0 +1,19 @@
+//! Datastar constants, with the [`headers`] of its requests and responses.
+
+// Generated from the Datastar SDK specification, and replaced as a whole when it changes.
+mod generated;
+
+pub mod headers;
+
+pub use generated::*;
+
+impl EventType {
+    /// Returns the [`EventType`] as a string which outlives it, unlike the generated
+    /// [`as_str`](Self::as_str).
+    pub(crate) const fn as_static_str(self) -> &'static str {
+        match self {
+            Self::PatchElements => Self::PatchElements.as_str(),
+            Self::PatchSignals => Self::PatchSignals.as_str(),
+        }
+    }
+}
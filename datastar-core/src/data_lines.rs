@@ -0,0 +1,446 @@
+//! [`DataLines`] are the `data` lines of a [`DatastarEvent`](crate::DatastarEvent).
+
+use core::{
+    fmt::{self, Write},
+    hash::{Hash, Hasher},
+    iter::FusedIterator,
+};
+
+/// The SSE field every line is framed with.
+const PREFIX: &str = "data: ";
+
+/// [`DataLines`] stores the `data` lines of an event in a single buffer, already framed as
+/// `data: {line}\n`, so building an event doesn't allocate once per line and writing it
+/// copies a single slice.
+///
+/// Lines are added with [`push`](DataLines::push) and [`push_literal`](DataLines::push_literal),
+/// or written in place with [`write!`], where a `\n`, `\r\n` or `\r` ends the current line,
+/// as they all end a line of SSE.
+///
+/// # Examples
+///
+/// ```
+/// use core::fmt::Write;
+/// use datastar_core::data_lines::DataLines;
+///
+/// let mut data = DataLines::new();
+/// data.push("selector #feed");
+/// data.push_literal("mode", "append");
+/// write!(data, "elements <li>")?;
+/// write!(data, "Done</li>")?;
+///
+/// assert_eq!(data, ["selector #feed", "mode append", "elements <li>Done</li>"]);
+/// assert_eq!(data.to_text(), "selector #feed\nmode append\nelements <li>Done</li>");
+/// # Ok::<(), core::fmt::Error>(())
+/// ```
+#[derive(Clone, Default)]
+pub struct DataLines {
+    buffer: Buffer,
+    /// Whether the last text written with [`write!`] ended with a `\r`, so a `\n` starting
+    /// the next text is the end of the same `\r\n`.
+    after_cr: bool,
+}
+
+/// Where the framed lines of [`DataLines`] are, each ended by `\n` except for a line which is
+/// still being written.
+#[derive(Clone)]
+enum Buffer {
+    Owned(String),
+    /// The lines of the framing of a whole event leaked by
+    /// [`DatastarEvent::leak`](crate::DatastarEvent::leak), which start at `start` and end
+    /// before its blank line.
+    Leaked {
+        frame: &'static str,
+        start: usize,
+    },
+}
+
+impl Buffer {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Owned(buffer) => buffer,
+            Self::Leaked { frame, start } => &frame[*start..frame.len() - 1],
+        }
+    }
+
+    /// Returns the owned buffer, copying leaked lines into one first.
+    fn to_mut(&mut self) -> &mut String {
+        if let Self::Leaked { .. } = self {
+            *self = Self::Owned(self.as_str().to_owned());
+        }
+        match self {
+            Self::Owned(buffer) => buffer,
+            Self::Leaked { .. } => unreachable!("leaked lines were copied"),
+        }
+    }
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Self::Owned(String::new())
+    }
+}
+
+impl DataLines {
+    /// Creates empty [`DataLines`].
+    pub const fn new() -> Self {
+        Self {
+            buffer: Buffer::Owned(String::new()),
+            after_cr: false,
+        }
+    }
+
+    /// Creates empty [`DataLines`] with room for `capacity` bytes of lines.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buffer: Buffer::Owned(String::with_capacity(capacity + 4 * PREFIX.len())),
+            after_cr: false,
+        }
+    }
+
+    /// Adds a line, which is split into several lines at every `\n`, `\r\n` or `\r`.
+    ///
+    /// A line ending at the end of `line` doesn't add an empty line, which would end the event.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::data_lines::DataLines;
+    ///
+    /// let mut data = DataLines::new();
+    /// data.push("a\n");
+    /// data.push("a\rb");
+    ///
+    /// assert_eq!(data, ["a", "a", "b"]);
+    /// ```
+    pub fn push(&mut self, line: &str) {
+        self.push_lines(&[], line);
+    }
+
+    /// Adds the line `{literal} {value}`, such as `selector #feed`, without formatting it first.
+    ///
+    /// A `value` of several lines, split like the line of [`push`](Self::push), adds a line with
+    /// the `literal` for each of them, as for the `elements` of a patch.
+    pub fn push_literal(&mut self, literal: &str, value: &str) {
+        self.push_lines(&[literal, " "], value);
+    }
+
+    /// Adds a line starting with `pieces` for each line of `value`, or one if it's empty.
+    fn push_lines(&mut self, pieces: &[&str], value: &str) {
+        let at_line_start = self.at_line_start();
+        self.after_cr = false;
+        let buffer = self.buffer.to_mut();
+        if !at_line_start {
+            buffer.push('\n');
+        }
+        let mut lines = split_lines(value);
+        let first = lines.next().unwrap_or_default();
+        for line in core::iter::once(first).chain(lines) {
+            buffer.push_str(PREFIX);
+            pieces.iter().for_each(|piece| buffer.push_str(piece));
+            buffer.push_str(line);
+            buffer.push('\n');
+        }
+    }
+
+    /// Returns the number of lines.
+    pub fn len(&self) -> usize {
+        let ended = self
+            .buffer
+            .as_str()
+            .bytes()
+            .filter(|&byte| byte == b'\n')
+            .count();
+        ended + usize::from(!self.at_line_start())
+    }
+
+    /// Returns whether there are no lines.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.as_str().is_empty()
+    }
+
+    /// Returns an iterator over the lines, without their framing.
+    pub fn iter(&self) -> Lines<'_> {
+        Lines {
+            rest: self.buffer.as_str(),
+        }
+    }
+
+    /// Returns the lines joined by `\n`, as the data of an SSE event of the framework integrations.
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity(self.buffer.as_str().len());
+        for (index, line) in self.iter().enumerate() {
+            if index > 0 {
+                text.push('\n');
+            }
+            text.push_str(line);
+        }
+        text
+    }
+
+    /// Returns the framed lines, and the line ending of a line which is still being written.
+    pub(crate) fn framed(&self) -> (&str, &str) {
+        let ending = if self.at_line_start() { "" } else { "\n" };
+        (self.buffer.as_str(), ending)
+    }
+
+    /// Returns the framed lines followed by the blank line ending an event, reusing the buffer.
+    #[cfg(feature = "bytes")]
+    pub(crate) fn into_event_tail(mut self) -> String {
+        let at_line_start = self.at_line_start();
+        let buffer = self.buffer.to_mut();
+        if !at_line_start {
+            buffer.push('\n');
+        }
+        buffer.push('\n');
+        core::mem::take(buffer)
+    }
+
+    /// Returns the lines of `frame`, the framing of a whole event leaked by
+    /// [`DatastarEvent::leak`](crate::DatastarEvent::leak), whose data lines start at `start`.
+    pub(crate) const fn leaked(frame: &'static str, start: usize) -> Self {
+        Self {
+            buffer: Buffer::Leaked { frame, start },
+            after_cr: false,
+        }
+    }
+
+    /// Returns the framing of the whole event and where its data lines start, if it was leaked.
+    #[cfg(feature = "bytes")]
+    pub(crate) const fn leaked_frame(&self) -> Option<(&'static str, usize)> {
+        match self.buffer {
+            Buffer::Leaked { frame, start } => Some((frame, start)),
+            Buffer::Owned(_) => None,
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        let buffer = self.buffer.as_str();
+        buffer.is_empty() || buffer.ends_with('\n')
+    }
+}
+
+/// [`LineWriter`] is where the builders write their data lines, either into [`DataLines`]
+/// or straight into the data of an [`SsePayload`](crate::sse_payload::SsePayload) through
+/// [`TextLines`].
+///
+/// Text written with [`write!`] continues a line started by an earlier [`write!`], while a
+/// [`push_literal`](LineWriter::push_literal) line is ended and a `\n` starts a new line.
+pub(crate) trait LineWriter: Write {
+    /// Adds the line `{literal} {value}`, with a line for each line of `value`.
+    fn push_literal(&mut self, literal: &str, value: &str);
+}
+
+impl LineWriter for DataLines {
+    fn push_literal(&mut self, literal: &str, value: &str) {
+        DataLines::push_literal(self, literal, value);
+    }
+}
+
+/// [`TextLines`] writes lines joined by `\n` into a [`Write`], such as the [`String`]
+/// data of a framework event.
+pub(crate) struct TextLines<W> {
+    writer: W,
+    line: TextLine,
+}
+
+/// Where [`TextLines`] are, to know when a line needs a `\n` before it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TextLine {
+    /// At the start of the text or right after a `\n`.
+    Start,
+    /// In a line written with [`write!`], which more text continues.
+    Open,
+    /// After a line added with [`push_literal`](LineWriter::push_literal).
+    Ended,
+}
+
+impl<W: Write> TextLines<W> {
+    pub(crate) const fn new(writer: W) -> Self {
+        Self {
+            writer,
+            line: TextLine::Start,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for TextLines<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.is_empty() {
+            return Ok(());
+        }
+        if self.line == TextLine::Ended {
+            self.writer.write_char('\n')?;
+        }
+        self.line = if s.ends_with('\n') {
+            TextLine::Start
+        } else {
+            TextLine::Open
+        };
+        self.writer.write_str(s)
+    }
+}
+
+impl<W: Write> LineWriter for TextLines<W> {
+    fn push_literal(&mut self, literal: &str, value: &str) {
+        let mut lines = split_lines(value);
+        let first = lines.next().unwrap_or_default();
+        for line in core::iter::once(first).chain(lines) {
+            // The writers of the framework integrations never fail.
+            if self.line != TextLine::Start {
+                let _ = self.writer.write_char('\n');
+            }
+            for piece in [literal, " ", line] {
+                let _ = self.writer.write_str(piece);
+            }
+            self.line = TextLine::Ended;
+        }
+    }
+}
+
+impl Write for DataLines {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut rest = s;
+        if self.after_cr && !rest.is_empty() {
+            rest = rest.strip_prefix('\n').unwrap_or(rest);
+            self.after_cr = false;
+        }
+        while !rest.is_empty() {
+            let buffer = self.buffer.to_mut();
+            if buffer.is_empty() || buffer.ends_with('\n') {
+                buffer.push_str(PREFIX);
+            }
+            match rest.find(['\r', '\n']) {
+                Some(end) => {
+                    buffer.push_str(&rest[..end]);
+                    buffer.push('\n');
+                    let ending = if rest[end..].starts_with("\r\n") {
+                        2
+                    } else {
+                        1
+                    };
+                    self.after_cr = &rest[end..] == "\r";
+                    rest = &rest[end + ending..];
+                }
+                None => {
+                    buffer.push_str(rest);
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits `text` at every `\n`, `\r\n` or `\r`, which all end a line of SSE, like
+/// [`str::lines`] without an empty line after a final line ending.
+fn split_lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(text).filter(|text| !text.is_empty());
+    core::iter::from_fn(move || {
+        let text = rest?;
+        let Some(end) = text.find(['\r', '\n']) else {
+            rest = None;
+            return Some(text);
+        };
+        let ending = if text[end..].starts_with("\r\n") {
+            2
+        } else {
+            1
+        };
+        rest = Some(&text[end + ending..]).filter(|rest| !rest.is_empty());
+        Some(&text[..end])
+    })
+}
+
+impl fmt::Debug for DataLines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// A line which is still being written equals the same line once it was ended.
+impl PartialEq for DataLines {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for DataLines {}
+
+impl Hash for DataLines {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.iter().for_each(|line| line.hash(state));
+    }
+}
+
+impl<S: AsRef<str>> PartialEq<[S]> for DataLines {
+    fn eq(&self, other: &[S]) -> bool {
+        self.iter().eq(other.iter().map(AsRef::as_ref))
+    }
+}
+
+impl<S: AsRef<str>, const N: usize> PartialEq<[S; N]> for DataLines {
+    fn eq(&self, other: &[S; N]) -> bool {
+        *self == other[..]
+    }
+}
+
+impl<S: AsRef<str>> PartialEq<Vec<S>> for DataLines {
+    fn eq(&self, other: &Vec<S>) -> bool {
+        *self == other[..]
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for DataLines {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut data = Self::new();
+        data.extend(iter);
+        data
+    }
+}
+
+impl<S: AsRef<str>> Extend<S> for DataLines {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|line| self.push(line.as_ref()));
+    }
+}
+
+impl<'a> IntoIterator for &'a DataLines {
+    type Item = &'a str;
+    type IntoIter = Lines<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// [`Lines`] iterates over [`DataLines`], created by [`DataLines::iter`].
+#[derive(Debug, Clone)]
+pub struct Lines<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        // Lines are short, so scanning the bytes is faster than a `char` pattern.
+        let end = self
+            .rest
+            .bytes()
+            .position(|byte| byte == b'\n')
+            .unwrap_or(self.rest.len());
+        let line = &self.rest[PREFIX.len()..end];
+        self.rest = self.rest.get(end + 1..).unwrap_or_default();
+        Some(line)
+    }
+}
+
+impl FusedIterator for Lines<'_> {}
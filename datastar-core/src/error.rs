@@ -0,0 +1,155 @@
+//! [`Error`] is the error type shared by the fallible constructors and the request extractors.
+
+use {
+    crate::{FieldError, patch_elements::PatchElementsError},
+    core::fmt::{self, Display},
+};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// [`Error`] is returned by the fallible event constructors and by the signal extractors
+/// of the framework integrations.
+///
+/// The framework integrations turn it into a response with the status code
+/// returned by [`Error::status`], so it can be used as the error type of handlers.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{DatastarEvent, Error, FieldError};
+///
+/// let err = DatastarEvent::keep_alive().with_field("data", "injected").unwrap_err();
+///
+/// assert!(matches!(err, Error::InvalidField(FieldError::InvalidName(_))));
+/// assert_eq!(err.status(), 500);
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A comment or custom field of a [`DatastarEvent`](crate::DatastarEvent) would break the SSE framing.
+    InvalidField(FieldError),
+    /// A [`PatchElements`](crate::patch_elements::PatchElements) event can't be applied by the Datastar client.
+    InvalidElements(PatchElementsError),
+    /// Serializing or deserializing JSON, such as the signals of an event, failed.
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+    /// The signals of the request are not valid JSON, or don't deserialize into the requested type.
+    #[cfg(feature = "serde")]
+    InvalidSignals {
+        /// The path of the signal which failed to deserialize, such as `user.emails[1]`,
+        /// or `.` if the signals as a whole failed.
+        path: String,
+        /// Why the signal failed to deserialize.
+        message: String,
+    },
+    /// The request did not carry any signals.
+    MissingSignals,
+    /// The query string of the request could not be decoded.
+    InvalidQuery(BoxError),
+    /// The body of the request could not be read.
+    Body(BoxError),
+    /// The body of the request exceeded the configured limit.
+    PayloadTooLarge,
+    /// A required header is missing from the request.
+    MissingHeader(&'static str),
+    /// A header of the request is not valid UTF-8.
+    InvalidHeader(&'static str),
+    /// The signals of the request deserialized, but some of their values are invalid.
+    #[cfg(feature = "serde")]
+    Validation(crate::signals::ValidationErrors),
+    /// The body of the request has a content type the extractor can't read the signals from,
+    /// such as a multipart form for `ReadSignals`.
+    UnsupportedContentType(String),
+    /// The body of the request has a `Content-Encoding` the extractor can't decode,
+    /// such as `gzip` without the `compression` feature.
+    UnsupportedEncoding(String),
+    /// Rendering a template failed, such as one which doesn't exist.
+    Template(BoxError),
+}
+
+impl Error {
+    /// Returns the HTTP status code of the response this error is turned into.
+    ///
+    /// Errors caused by the request are client errors, invalid events are server errors.
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::InvalidField(_) | Self::InvalidElements(_) | Self::Template(_) => 500,
+            Self::PayloadTooLarge => 413,
+            Self::UnsupportedContentType(_) | Self::UnsupportedEncoding(_) => 415,
+            #[cfg(feature = "serde")]
+            Self::Validation(_) => 422,
+            _ => 400,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidField(err) => write!(f, "invalid event: {err}"),
+            Self::InvalidElements(err) => write!(f, "invalid event: {err}"),
+            #[cfg(feature = "serde")]
+            Self::Json(err) => write!(f, "invalid JSON: {err}"),
+            #[cfg(feature = "serde")]
+            Self::InvalidSignals { path, message } if path == "." => {
+                write!(f, "invalid signals: {message}")
+            }
+            #[cfg(feature = "serde")]
+            Self::InvalidSignals { path, message } => {
+                write!(f, "invalid signals at `{path}`: {message}")
+            }
+            Self::MissingSignals => f.write_str("the request does not contain any signals"),
+            Self::InvalidQuery(err) => write!(f, "failed to decode the query string: {err}"),
+            Self::Body(err) => write!(f, "failed to read the request body: {err}"),
+            Self::PayloadTooLarge => f.write_str("the request body is too large"),
+            Self::MissingHeader(name) => write!(f, "missing {name} header"),
+            Self::InvalidHeader(name) => write!(f, "the {name} header is not valid UTF-8"),
+            #[cfg(feature = "serde")]
+            Self::Validation(errors) => write!(f, "invalid signals: {errors}"),
+            Self::UnsupportedContentType(media_type) => {
+                write!(
+                    f,
+                    "the signals can't be read from a {media_type} request body"
+                )
+            }
+            Self::UnsupportedEncoding(encoding) => {
+                write!(f, "the request body can't be decoded from {encoding}")
+            }
+            Self::Template(err) => write!(f, "failed to render the template: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidField(err) => Some(err),
+            Self::InvalidElements(err) => Some(err),
+            #[cfg(feature = "serde")]
+            Self::Json(err) => Some(err),
+            #[cfg(feature = "serde")]
+            Self::Validation(errors) => Some(errors),
+            Self::InvalidQuery(err) | Self::Body(err) | Self::Template(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<FieldError> for Error {
+    fn from(err: FieldError) -> Self {
+        Self::InvalidField(err)
+    }
+}
+
+impl From<PatchElementsError> for Error {
+    fn from(err: PatchElementsError) -> Self {
+        Self::InvalidElements(err)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
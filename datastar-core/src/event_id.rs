@@ -0,0 +1,225 @@
+//! [`EventIdGenerator`] gives every outgoing event an `id`, so a client which reconnects
+//! sends a `Last-Event-ID` the [replay](crate::replay) machinery can resume from, without
+//! threading ids through every handler.
+//!
+//! Ids are assigned by a [`DatastarSender`](crate::sender::DatastarSender) created with
+//! [`with_event_ids`](crate::sender::DatastarSender::with_event_ids), or by a stream wrapped
+//! with [`EventIdExt::with_event_ids`]. Events which already have an `id` keep it, and
+//! keep-alive comments don't get one.
+
+use {
+    crate::{DatastarEvent, response::IntoDatastarResponse},
+    core::{
+        fmt,
+        pin::Pin,
+        sync::atomic::{AtomicU64, Ordering},
+        task::{Context, Poll},
+    },
+    futures_core::Stream,
+    std::sync::Arc,
+};
+
+/// [`EventIdGenerator`] creates the `id`s of outgoing events.
+///
+/// Every call must return a new id. The [`Counter`] creates increasing numbers, and with the
+/// `ulid` feature `Ulids` creates sortable ids which are unique across processes.
+pub trait EventIdGenerator: Send + Sync + 'static {
+    /// Returns the id of the next event.
+    fn next_id(&self) -> String;
+}
+
+impl<G: EventIdGenerator> EventIdGenerator for Arc<G> {
+    fn next_id(&self) -> String {
+        (**self).next_id()
+    }
+}
+
+/// Gives `event` the next id of `ids`, unless it has an id or is a keep-alive comment.
+pub(crate) fn assign(ids: &dyn EventIdGenerator, event: &mut DatastarEvent) {
+    if event.id.is_none() && (event.event.is_some() || event.custom_event.is_some()) {
+        event.id = Some(ids.next_id().into());
+    }
+}
+
+/// [`Counter`] is an [`EventIdGenerator`] which counts up from `1`.
+///
+/// A counter shared by all connections of a stream, such as next to a
+/// [`RingBuffer`](crate::replay::RingBuffer), gives every event a unique id.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::event_id::{Counter, EventIdGenerator};
+///
+/// let ids = Counter::new();
+/// assert_eq!(ids.next_id(), "1");
+/// assert_eq!(ids.next_id(), "2");
+///
+/// // Continue after the id a reconnecting client received last.
+/// let ids = Counter::resume(Some("41"));
+/// assert_eq!(ids.next_id(), "42");
+/// ```
+#[derive(Debug)]
+pub struct Counter {
+    next: AtomicU64,
+}
+
+impl Counter {
+    /// Creates a new [`Counter`] starting at `1`.
+    pub const fn new() -> Self {
+        Self::starting_at(1)
+    }
+
+    /// Creates a new [`Counter`] starting at `first`.
+    pub const fn starting_at(first: u64) -> Self {
+        Self {
+            next: AtomicU64::new(first),
+        }
+    }
+
+    /// Creates a new [`Counter`] which continues after the `Last-Event-ID` of a reconnecting
+    /// client, or starts at `1` if there is none or it isn't a number.
+    pub fn resume(last_event_id: Option<&str>) -> Self {
+        let last = last_event_id.and_then(|id| id.parse::<u64>().ok());
+        Self::starting_at(last.map_or(1, |last| last.saturating_add(1)))
+    }
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventIdGenerator for Counter {
+    fn next_id(&self) -> String {
+        self.next.fetch_add(1, Ordering::Relaxed).to_string()
+    }
+}
+
+/// [`Ulids`] is an [`EventIdGenerator`] which creates [ULIDs](https://github.com/ulid/spec).
+///
+/// The ids are unique across processes and increase within a generator, so they can be
+/// resumed from after a restart or by another instance behind a load balancer.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::event_id::{EventIdGenerator, Ulids};
+///
+/// let ids = Ulids::new();
+/// let (first, second) = (ids.next_id(), ids.next_id());
+///
+/// assert_eq!(first.len(), 26);
+/// assert!(first < second);
+/// ```
+#[cfg(feature = "ulid")]
+#[derive(Default)]
+pub struct Ulids {
+    generator: std::sync::Mutex<ulid::Generator>,
+}
+
+#[cfg(feature = "ulid")]
+impl Ulids {
+    /// Creates a new [`Ulids`] generator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "ulid")]
+impl fmt::Debug for Ulids {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ulids").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "ulid")]
+impl EventIdGenerator for Ulids {
+    fn next_id(&self) -> String {
+        let mut generator = self
+            .generator
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        // The random part only overflows after 2^80 ids in the same millisecond,
+        // in which case the id isn't increasing but still unique.
+        generator
+            .generate()
+            .unwrap_or_else(|_| ulid::Ulid::new())
+            .to_string()
+    }
+}
+
+/// [`EventIdExt`] adds the [`with_event_ids`](EventIdExt::with_event_ids) combinator
+/// to every [`Stream`] of [`DatastarEvent`]s.
+pub trait EventIdExt: Stream<Item = DatastarEvent> + Sized {
+    /// Gives every event without an `id` the next id of `ids`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{
+    ///     event_id::{Counter, EventIdExt},
+    ///     replay::LastEventId,
+    ///     sender,
+    /// };
+    ///
+    /// fn feed(last_event_id: Option<LastEventId>) -> impl datastar_core::response::IntoDatastarResponse {
+    ///     let (sender, receiver) = sender::channel();
+    /// #   drop(sender);
+    ///     receiver.with_event_ids(Counter::resume(last_event_id.as_deref()))
+    /// }
+    /// # drop(feed(None));
+    /// ```
+    fn with_event_ids<G: EventIdGenerator>(self, ids: G) -> WithEventIds<Self, G> {
+        WithEventIds {
+            stream: Box::pin(self),
+            ids,
+        }
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>> EventIdExt for S {}
+
+/// [`WithEventIds`] is the [`Stream`] returned by [`EventIdExt::with_event_ids`].
+pub struct WithEventIds<S, G> {
+    stream: Pin<Box<S>>,
+    ids: G,
+}
+
+// Nothing is pinned structurally, as the stream is boxed.
+impl<S, G> Unpin for WithEventIds<S, G> {}
+
+impl<S, G> fmt::Debug for WithEventIds<S, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WithEventIds").finish_non_exhaustive()
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>, G: EventIdGenerator> Stream for WithEventIds<S, G> {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut event = futures_core::ready!(self.stream.as_mut().poll_next(cx));
+        if let Some(event) = &mut event {
+            assign(&self.ids, event);
+        }
+        Poll::Ready(event)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+impl<S, G> IntoDatastarResponse for WithEventIds<S, G>
+where
+    S: Stream<Item = DatastarEvent> + Send + 'static,
+    G: EventIdGenerator,
+{
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
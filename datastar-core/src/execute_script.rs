@@ -0,0 +1,320 @@
+//! [`ExecuteScript`] executes JavaScript in the browser.
+//!
+//! This is sugar for `PatchElements` specifically for executing scripts.
+
+use {
+    crate::{
+        DatastarEvent, IntoDatastarEvent,
+        consts::{self, ElementPatchMode, EventType},
+        data_lines::{DataLines, LineWriter},
+        html,
+    },
+    core::time::Duration,
+    std::borrow::Cow,
+};
+
+/// [`ExecuteScript`] executes JavaScript in the browser
+///
+/// It is constructed with [`ExecuteScript::new`] or [`Default`] and configured with the builder
+/// methods, as fields are added in minor releases, see
+/// [Constructing events](crate#constructing-events).
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::prelude::ExecuteScript;
+///
+/// let script = ExecuteScript::new("console.log('Hello, world!')").auto_remove(false);
+///
+/// assert_eq!(script.auto_remove, Some(false));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ExecuteScript {
+    /// `id` can be used by the backend to replay events.
+    /// This is part of the SSE spec and is used to tell the browser how to handle the event.
+    /// For more details see <https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#id>
+    pub id: Option<Cow<'static, str>>,
+    /// `retry` is part of the SSE spec and is used to tell the browser how long to wait before reconnecting if the connection is lost.
+    /// When `None`, no `retry` field is sent and the browser keeps its current reconnection delay.
+    /// For more details see <https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#retry>
+    pub retry: Option<Duration>,
+    /// `script` is a string that represents the JavaScript to be executed by the browser.
+    pub script: String,
+    /// Whether to remove the script after execution, if not provided the Datastar client side will default to `true`.
+    pub auto_remove: Option<bool>,
+    /// A list of attributes to add to the script element, if not provided the Datastar client side will default to `type="module"`.
+    /// Each item in the array ***must*** be properly formatted.
+    pub attributes: Vec<String>,
+    /// Whether closing `</script>` tags in the `script` are escaped, so they can't end the
+    /// script element early. Defaults to `true`, disable it for scripts which are escaped already.
+    pub escape: bool,
+}
+
+impl Default for ExecuteScript {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl ExecuteScript {
+    /// Creates a new [`ExecuteScript`] event with the given script.
+    pub fn new(script: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            retry: None,
+            script: script.into(),
+            auto_remove: Default::default(),
+            attributes: Default::default(),
+            escape: true,
+        }
+    }
+
+    /// Sets the `id` of the [`ExecuteScript`] event.
+    pub fn id(mut self, id: impl Into<Cow<'static, str>>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets the `retry` of the [`ExecuteScript`] event.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Sets the `script` of the [`ExecuteScript`] event.
+    pub fn script(mut self, script: impl Into<String>) -> Self {
+        self.script = script.into();
+        self
+    }
+
+    /// Sets the `auto_remove` of the [`ExecuteScript`] event.
+    pub fn auto_remove(mut self, auto_remove: bool) -> Self {
+        self.auto_remove = Some(auto_remove);
+        self
+    }
+
+    /// Sets the `escape` of the [`ExecuteScript`] event.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::{DatastarEvent, ExecuteScript};
+    ///
+    /// let script = ExecuteScript::new("console.log('</script>')");
+    /// assert!(DatastarEvent::from(&script).to_string().contains(r"console.log('<\/script>')"));
+    ///
+    /// let script = script.escape(false);
+    /// assert!(DatastarEvent::from(&script).to_string().contains("console.log('</script>')"));
+    /// ```
+    pub fn escape(mut self, escape: bool) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    /// Sets the `attribute` of the [`ExecuteScript`] event.
+    pub fn attributes(mut self, attributes: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.attributes = attributes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the `attributes` of the [`ExecuteScript`] event from name and value pairs.
+    ///
+    /// Text values are quoted and escaped, boolean attributes are added without a value
+    /// when `true` and left out when `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::ExecuteScript;
+    ///
+    /// let script = ExecuteScript::new("console.log('Hello, world!')").attributes_map([
+    ///     ("type", "module".into()),
+    ///     ("nonce", r#"a"b"#.into()),
+    ///     ("async", true.into()),
+    ///     ("defer", false.into()),
+    /// ]);
+    ///
+    /// assert_eq!(script.attributes, [r#"type="module""#, r#"nonce="a&quot;b""#, "async"]);
+    /// ```
+    pub fn attributes_map(
+        mut self,
+        attributes: impl IntoIterator<Item = (impl Into<String>, AttributeValue)>,
+    ) -> Self {
+        self.attributes = attributes
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let mut attribute = name.into();
+                match value {
+                    AttributeValue::Text(value) => {
+                        attribute.push_str("=\"");
+                        // Writing into a `String` can't fail.
+                        let _ = html::escape(&mut attribute, &value);
+                        attribute.push('"');
+                    }
+                    AttributeValue::Flag(true) => {}
+                    AttributeValue::Flag(false) => return None,
+                }
+                Some(attribute)
+            })
+            .collect();
+        self
+    }
+
+    /// Converts this [`ExecuteScript`] into a [`DatastarEvent`].
+    #[inline]
+    pub fn into_datastar_event(mut self) -> DatastarEvent {
+        let id = self.id.take();
+        self.convert_to_datastar_event_inner(id)
+    }
+
+    /// Copy this [`ExecuteScript`] as a [`DatastarEvent`].
+    #[inline]
+    pub fn as_datastar_event(&self) -> DatastarEvent {
+        self.convert_to_datastar_event_inner(self.id.clone())
+    }
+
+    fn convert_to_datastar_event_inner(&self, id: Option<Cow<'static, str>>) -> DatastarEvent {
+        let mut data = DataLines::with_capacity(self.data_capacity());
+        self.write_data_lines(&mut data);
+
+        DatastarEvent {
+            event: Some(consts::EventType::PatchElements),
+            custom_event: None,
+            comments: Vec::new(),
+            fields: Vec::new(),
+            id,
+            retry: self.retry,
+            data,
+        }
+    }
+
+    /// Returns the capacity to reserve for the data lines of this [`ExecuteScript`].
+    pub(crate) fn data_capacity(&self) -> usize {
+        self.script.len() + 96
+    }
+
+    /// Writes the data lines of this [`ExecuteScript`], which the framework integrations
+    /// write straight into their events.
+    pub(crate) fn write_data_lines(&self, data: &mut impl LineWriter) {
+        data.push_literal(consts::SELECTOR_DATALINE_LITERAL, "body");
+        data.push_literal(
+            consts::MODE_DATALINE_LITERAL,
+            ElementPatchMode::Append.as_str(),
+        );
+
+        // Writing the data lines never fails.
+        for piece in [consts::ELEMENTS_DATALINE_LITERAL, " <script"] {
+            let _ = data.write_str(piece);
+        }
+
+        if self.auto_remove.unwrap_or(true) {
+            let _ = data.write_str(r##" data-effect="el.remove()""##);
+        }
+
+        for attribute in &self.attributes {
+            for piece in [" ", attribute] {
+                let _ = data.write_str(piece);
+            }
+        }
+
+        let script = if self.escape {
+            escape_closing_tags(&self.script)
+        } else {
+            Cow::Borrowed(self.script.as_str())
+        };
+        // A lone carriage return ends the line in SSE, so it has to start a new data line.
+        let mut scripts_lines = script.lines().flat_map(|line| line.split('\r'));
+
+        for piece in [">", scripts_lines.next().unwrap_or_default()] {
+            let _ = data.write_str(piece);
+        }
+
+        for line in scripts_lines {
+            for piece in ["\n", consts::ELEMENTS_DATALINE_LITERAL, " ", line] {
+                let _ = data.write_str(piece);
+            }
+        }
+
+        let _ = data.write_str("</script>");
+    }
+}
+
+/// Escapes every `</script` as `<\/script`, which is equivalent in strings and regular expressions,
+/// but isn't parsed as the end of the script element by the browser.
+fn escape_closing_tags(script: &str) -> Cow<'_, str> {
+    const TAG: &[u8] = b"</script";
+
+    let bytes = script.as_bytes();
+    let is_closing_tag = |index: usize| {
+        bytes
+            .get(index..index + TAG.len())
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(TAG))
+    };
+    if !(0..bytes.len()).any(is_closing_tag) {
+        return Cow::Borrowed(script);
+    }
+
+    let mut escaped = String::with_capacity(script.len() + 1);
+    let mut last = 0;
+    for index in (0..bytes.len()).filter(|&index| is_closing_tag(index)) {
+        escaped.push_str(&script[last..index + 1]);
+        escaped.push('\\');
+        last = index + 1;
+    }
+    escaped.push_str(&script[last..]);
+    Cow::Owned(escaped)
+}
+
+impl From<&ExecuteScript> for DatastarEvent {
+    #[inline]
+    fn from(val: &ExecuteScript) -> Self {
+        val.as_datastar_event()
+    }
+}
+
+impl From<ExecuteScript> for DatastarEvent {
+    #[inline]
+    fn from(val: ExecuteScript) -> Self {
+        val.into_datastar_event()
+    }
+}
+
+impl IntoDatastarEvent for ExecuteScript {
+    fn event_type(&self) -> Option<EventType> {
+        Some(EventType::PatchElements)
+    }
+}
+
+impl IntoDatastarEvent for &ExecuteScript {
+    fn event_type(&self) -> Option<EventType> {
+        Some(EventType::PatchElements)
+    }
+}
+
+/// The value of a script element attribute, see [`ExecuteScript::attributes_map`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AttributeValue {
+    /// Rendered as `name="value"`, with the value escaped.
+    Text(String),
+    /// Rendered as `name` when `true` and left out when `false`.
+    Flag(bool),
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_owned())
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        Self::Flag(value)
+    }
+}
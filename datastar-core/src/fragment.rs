@@ -0,0 +1,147 @@
+//! [`FragmentResponse`] answers an action with plain HTML rather than an SSE stream.
+//!
+//! Datastar patches the elements of a `text/html` response like those of a
+//! `datastar-patch-elements` event, reading how to patch them from the `datastar-selector`,
+//! `datastar-mode` and `datastar-use-view-transition` [`headers`], so an action which
+//! patches a single fragment doesn't need a stream at all.
+//!
+//! Each framework integration turns a [`FragmentResponse`] into a response: the `IntoResponse`
+//! of Axum, the `Responder` of Rocket and the `Reply` of Warp.
+
+use {
+    crate::{
+        Error,
+        consts::{self, ElementPatchMode, headers},
+        patch_elements::{self, Elements, PatchElements, PatchElementsError},
+    },
+    std::borrow::Cow,
+};
+
+/// The media type of a [`FragmentResponse`].
+pub const HTML_MEDIA_TYPE: &str = "text/html; charset=utf-8";
+
+/// [`FragmentResponse`] is a `text/html` response of elements, with the headers telling
+/// Datastar how to patch them.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{consts::ElementPatchMode, fragment::FragmentResponse};
+///
+/// let response = FragmentResponse::new("<li>Deployed</li>")
+///     .selector("#feed")
+///     .mode(ElementPatchMode::Prepend);
+///
+/// assert_eq!(
+///     response.headers().collect::<Vec<_>>(),
+///     [("datastar-selector", "#feed"), ("datastar-mode", "prepend")],
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FragmentResponse {
+    /// The HTML elements to patch into the DOM, which are the body of the response.
+    pub elements: Cow<'static, str>,
+    /// The CSS selector to use to patch the elements.
+    /// If not provided, Datastar will default to using the id attribute of the elements.
+    pub selector: Option<Cow<'static, str>>,
+    /// The mode to use when patching the elements into the DOM.
+    pub mode: ElementPatchMode,
+    /// Whether to use view transitions, which Datastar defaults to `false`.
+    pub use_view_transition: bool,
+}
+
+impl FragmentResponse {
+    /// Creates a new [`FragmentResponse`] of the given elements.
+    ///
+    /// Strings are trusted as HTML, as with [`PatchElements::new`].
+    pub fn new(elements: impl Into<Elements>) -> Self {
+        Self {
+            elements: elements.into().into_inner(),
+            selector: None,
+            mode: ElementPatchMode::default(),
+            use_view_transition: consts::DEFAULT_ELEMENTS_USE_VIEW_TRANSITIONS,
+        }
+    }
+
+    /// Sets the `selector` of the [`FragmentResponse`].
+    pub fn selector(mut self, selector: impl Into<Cow<'static, str>>) -> Self {
+        self.selector = Some(selector.into());
+        self
+    }
+
+    /// Sets the `mode` of the [`FragmentResponse`].
+    pub fn mode(mut self, mode: ElementPatchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the `use_view_transition` of the [`FragmentResponse`].
+    pub fn use_view_transition(mut self, use_view_transition: bool) -> Self {
+        self.use_view_transition = use_view_transition;
+        self
+    }
+
+    /// Returns the Datastar headers of the [`FragmentResponse`], leaving out those which
+    /// are the defaults of the client.
+    pub fn headers(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        let selector = self
+            .selector
+            .as_deref()
+            .map(|selector| (headers::DATASTAR_SELECTOR, selector));
+        let mode = (self.mode != ElementPatchMode::default())
+            .then(|| (headers::DATASTAR_MODE, self.mode.as_str()));
+        let use_view_transition = (self.use_view_transition
+            != consts::DEFAULT_ELEMENTS_USE_VIEW_TRANSITIONS)
+            .then_some((
+                headers::DATASTAR_USE_VIEW_TRANSITION,
+                if self.use_view_transition {
+                    "true"
+                } else {
+                    "false"
+                },
+            ));
+
+        selector.into_iter().chain(mode).chain(use_view_transition)
+    }
+
+    /// Checks that the [`FragmentResponse`] can be applied by the Datastar client, with the
+    /// rules of [`PatchElements::validate`], and that its selector fits in a header.
+    ///
+    /// The framework integrations respond with the error of an invalid [`FragmentResponse`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{Error, fragment::FragmentResponse, patch_elements::PatchElementsError};
+    ///
+    /// assert!(matches!(
+    ///     FragmentResponse::new("<li>Done</li>").selector("#feed\n").validate(),
+    ///     Err(Error::InvalidElements(PatchElementsError::InvalidSelector)),
+    /// ));
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        if self
+            .selector
+            .as_deref()
+            .is_some_and(|selector| selector.contains(char::is_control))
+        {
+            return Err(PatchElementsError::InvalidSelector.into());
+        }
+
+        patch_elements::validate(self.selector.as_deref(), Some(&self.elements), self.mode)
+            .map_err(Into::into)
+    }
+}
+
+/// Converts a [`PatchElements`] event into a [`FragmentResponse`], dropping its `id` and
+/// `retry`, which only apply to SSE.
+impl From<PatchElements> for FragmentResponse {
+    fn from(patch: PatchElements) -> Self {
+        Self {
+            elements: patch.elements.unwrap_or_default(),
+            selector: patch.selector,
+            mode: patch.mode,
+            use_view_transition: patch.use_view_transition,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! [`Heartbeat`] keeps long-lived SSE connections open by sending a
+//! [`keep_alive`](DatastarEvent::keep_alive) comment whenever the stream is idle.
+//!
+//! Proxies and load balancers commonly close connections which haven't sent anything
+//! for a while, which forces the browser to reconnect.
+
+#[cfg(feature = "tokio")]
+use crate::clock::TokioClock;
+use {
+    crate::{DatastarEvent, clock::Clock, response::IntoDatastarResponse},
+    core::{
+        fmt,
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+        time::Duration,
+    },
+    futures_core::Stream,
+};
+
+/// The default interval between keep-alive comments, used by [`HeartbeatExt::with_default_heartbeat`].
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// [`HeartbeatExt`] adds the [`with_heartbeat`](HeartbeatExt::with_heartbeat) combinator
+/// to every [`Stream`] of [`DatastarEvent`]s.
+pub trait HeartbeatExt: Stream<Item = DatastarEvent> + Sized {
+    /// Interleaves a keep-alive comment whenever no event was sent for `interval`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use datastar_core::{heartbeat::HeartbeatExt, sender};
+    ///
+    /// let (sender, receiver) = sender::channel();
+    ///
+    /// // Return this from a handler instead of `receiver`.
+    /// let response = receiver.with_heartbeat(Duration::from_secs(15));
+    /// # drop((sender, response));
+    /// ```
+    #[cfg(feature = "tokio")]
+    fn with_heartbeat(self, interval: Duration) -> Heartbeat<Self, TokioClock> {
+        self.with_heartbeat_clock(interval, TokioClock)
+    }
+
+    /// Interleaves a keep-alive comment whenever no event was sent for [`DEFAULT_INTERVAL`].
+    #[cfg(feature = "tokio")]
+    fn with_default_heartbeat(self) -> Heartbeat<Self, TokioClock> {
+        self.with_heartbeat(DEFAULT_INTERVAL)
+    }
+
+    /// Interleaves a keep-alive comment whenever no event was sent for `interval`,
+    /// as measured by the given [`Clock`].
+    fn with_heartbeat_clock<C: Clock>(self, interval: Duration, clock: C) -> Heartbeat<Self, C> {
+        Heartbeat {
+            stream: Box::pin(self),
+            interval,
+            sleep: None,
+            clock,
+        }
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>> HeartbeatExt for S {}
+
+/// [`Heartbeat`] is the [`Stream`] returned by [`HeartbeatExt::with_heartbeat`].
+///
+/// It ends when the inner stream ends.
+pub struct Heartbeat<S, C: Clock> {
+    stream: Pin<Box<S>>,
+    interval: Duration,
+    /// The sleep until the next keep-alive, created on the first poll so the stream can be
+    /// built outside of the runtime of its clock.
+    sleep: Option<Pin<Box<C::Sleep>>>,
+    clock: C,
+}
+
+// Nothing is pinned structurally, as the stream and the sleep are boxed.
+impl<S, C: Clock> Unpin for Heartbeat<S, C> {}
+
+impl<S, C: Clock> fmt::Debug for Heartbeat<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Heartbeat")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>, C: Clock> Stream for Heartbeat<S, C> {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let event = match self.stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(event)) => event,
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => {
+                let interval = self.interval;
+                let Self { sleep, clock, .. } = &mut *self;
+                let sleep = sleep.get_or_insert_with(|| Box::pin(clock.sleep(interval)));
+                futures_core::ready!(sleep.as_mut().poll(cx));
+                DatastarEvent::keep_alive()
+            }
+        };
+
+        let sleep = self.clock.sleep(self.interval);
+        self.sleep = Some(Box::pin(sleep));
+        Poll::Ready(Some(event))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.stream.size_hint().0, None)
+    }
+}
+
+impl<S, C> IntoDatastarResponse for Heartbeat<S, C>
+where
+    S: Stream<Item = DatastarEvent> + Send + 'static,
+    C: Clock,
+{
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
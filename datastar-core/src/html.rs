@@ -0,0 +1,205 @@
+//! [`el`] builds HTML elements with escaped content, for use without a template engine.
+//!
+//! Building elements with `format!` makes it easy to inject user input into the page unescaped.
+//! The builder escapes all text and attribute values, while the tag and attribute names are
+//! expected to be trusted.
+
+use {
+    crate::patch_elements::{Elements, PatchElements},
+    core::fmt::{self, Display, Write},
+    std::borrow::Cow,
+};
+
+/// Elements without content or a closing tag.
+pub(crate) const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Creates a new [`Element`] with the given tag.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{html::el, prelude::PatchElements};
+///
+/// let user_input = "<script>alert('hi')</script>";
+///
+/// let element = el("div")
+///     .id("msg")
+///     .class("text-red-500")
+///     .text(user_input);
+///
+/// assert_eq!(
+///     element.to_string(),
+///     r#"<div id="msg" class="text-red-500">&lt;script&gt;alert(&#39;hi&#39;)&lt;/script&gt;</div>"#,
+/// );
+///
+/// let patch = PatchElements::new(element);
+/// # drop(patch);
+/// ```
+pub fn el(tag: impl Into<String>) -> Element {
+    Element {
+        tag: tag.into(),
+        attributes: Vec::new(),
+        children: Vec::new(),
+    }
+}
+
+/// [`Element`] is an HTML element created by [`el`], which renders using [`Display`].
+///
+/// It converts into a [`String`], into [`Elements`] and into [`PatchElements`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Element {
+    tag: String,
+    attributes: Vec<(String, Option<String>)>,
+    children: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Node {
+    Element(Element),
+    Text(String),
+    Raw(String),
+}
+
+impl Element {
+    /// Sets the `id` attribute.
+    pub fn id(self, id: impl Into<String>) -> Self {
+        self.attr("id", id)
+    }
+
+    /// Adds a class to the `class` attribute.
+    pub fn class(mut self, class: impl AsRef<str>) -> Self {
+        let class = class.as_ref();
+        match self.attributes.iter_mut().find(|(name, _)| name == "class") {
+            Some((_, Some(classes))) => {
+                classes.push(' ');
+                classes.push_str(class);
+            }
+            Some((_, value)) => *value = Some(class.to_owned()),
+            None => self
+                .attributes
+                .push(("class".to_owned(), Some(class.to_owned()))),
+        }
+        self
+    }
+
+    /// Sets an attribute, replacing any previous value.
+    ///
+    /// This is also used for the Datastar attributes, such as `data-on:click`.
+    pub fn attr(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_attribute(name.into(), Some(value.into()));
+        self
+    }
+
+    /// Sets a boolean attribute without a value, such as `disabled`.
+    pub fn flag(mut self, name: impl Into<String>) -> Self {
+        self.set_attribute(name.into(), None);
+        self
+    }
+
+    fn set_attribute(&mut self, name: String, value: Option<String>) {
+        match self.attributes.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, previous)) => *previous = value,
+            None => self.attributes.push((name, value)),
+        }
+    }
+
+    /// Appends text, which is escaped when rendered.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.children.push(Node::Text(text.into()));
+        self
+    }
+
+    /// Appends a child element.
+    pub fn child(mut self, child: Element) -> Self {
+        self.children.push(Node::Element(child));
+        self
+    }
+
+    /// Appends multiple child elements.
+    pub fn children(mut self, children: impl IntoIterator<Item = Element>) -> Self {
+        self.children
+            .extend(children.into_iter().map(Node::Element));
+        self
+    }
+
+    /// Appends HTML which is rendered as is.
+    ///
+    /// This must never contain untrusted input, such as the output of a template engine
+    /// which already escaped it.
+    pub fn raw(mut self, html: impl Into<String>) -> Self {
+        self.children.push(Node::Raw(html.into()));
+        self
+    }
+}
+
+impl Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}", self.tag)?;
+        for (name, value) in &self.attributes {
+            write!(f, " {name}")?;
+            if let Some(value) = value {
+                f.write_str("=\"")?;
+                escape(f, value)?;
+                f.write_char('"')?;
+            }
+        }
+        f.write_char('>')?;
+
+        if VOID_ELEMENTS.contains(&self.tag.as_str()) {
+            return Ok(());
+        }
+
+        for child in &self.children {
+            match child {
+                Node::Element(element) => element.fmt(f)?,
+                Node::Text(text) => escape(f, text)?,
+                Node::Raw(html) => f.write_str(html)?,
+            }
+        }
+
+        write!(f, "</{}>", self.tag)
+    }
+}
+
+pub(crate) fn escape(f: &mut impl Write, text: &str) -> fmt::Result {
+    let mut rest = text;
+    while let Some(index) = rest.find(['&', '<', '>', '"', '\'']) {
+        f.write_str(&rest[..index])?;
+        f.write_str(match rest.as_bytes()[index] {
+            b'&' => "&amp;",
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'"' => "&quot;",
+            _ => "&#39;",
+        })?;
+        rest = &rest[index + 1..];
+    }
+    f.write_str(rest)
+}
+
+impl From<Element> for String {
+    fn from(element: Element) -> Self {
+        element.to_string()
+    }
+}
+
+impl From<Element> for Cow<'static, str> {
+    fn from(element: Element) -> Self {
+        Cow::Owned(element.to_string())
+    }
+}
+
+impl From<Element> for Elements {
+    fn from(element: Element) -> Self {
+        Elements::from(element.to_string())
+    }
+}
+
+impl From<Element> for PatchElements {
+    fn from(element: Element) -> Self {
+        PatchElements::new(element)
+    }
+}
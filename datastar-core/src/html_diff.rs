@@ -0,0 +1,342 @@
+//! [`diff`] compares a fragment of HTML with the one sent before it, so an unchanged fragment
+//! isn't sent again and a changed one is sent as the children which changed.
+//!
+//! The fragments are scanned rather than parsed into a tree, which is enough for the HTML
+//! rendered by templates. Anything the scanner doesn't understand, such as an implied end tag,
+//! makes [`diff`] send the whole fragment.
+
+use crate::{html::VOID_ELEMENTS, patch_elements::PatchElements};
+
+/// Elements whose content is text up to their end tag, even when it looks like a tag.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// [`ElementsDiff`] is what [`diff`] decided to send for a new fragment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElementsDiff<'a> {
+    /// The fragment didn't change, so nothing needs to be sent.
+    Unchanged,
+    /// The whole fragment needs to be sent.
+    Full(&'a str),
+    /// Only these elements of the fragment changed. Each has an `id`, so the client
+    /// morphs it into the element with the same `id`.
+    Children(Vec<&'a str>),
+}
+
+impl ElementsDiff<'_> {
+    /// Returns whether nothing needs to be sent.
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, Self::Unchanged)
+    }
+
+    /// Returns the [`PatchElements`] event which sends the diff, if anything changed.
+    ///
+    /// The elements are morphed into the elements with the same `id`, so the [`Full`](Self::Full)
+    /// fragment needs an `id` as well, or a selector set on the returned event.
+    pub fn into_patch(self) -> Option<PatchElements> {
+        match self {
+            Self::Unchanged => None,
+            Self::Full(html) => Some(PatchElements::new(html.to_owned())),
+            Self::Children(children) => Some(PatchElements::new_many(
+                children.into_iter().map(str::to_owned),
+            )),
+        }
+    }
+}
+
+/// Compares the fragment `next` with the fragment `previous` sent before it.
+///
+/// When both fragments are a single element whose children line up, only the children
+/// with an `id` which changed are sent, and their own children are compared the same way.
+/// The whole fragment is sent instead when the changed children aren't any shorter,
+/// or when an element without an `id`, the text between the elements or the tag of an
+/// element which contains changes changed.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::html_diff::{self, ElementsDiff};
+///
+/// let previous = r#"<div id="dashboard">
+///     <p id="cpu">CPU 12%</p>
+///     <p id="memory">Memory 2.1 GB</p>
+///     <p id="disk">Disk 51%</p>
+/// </div>"#;
+/// let next = r#"<div id="dashboard">
+///     <p id="cpu">CPU 14%</p>
+///     <p id="memory">Memory 2.1 GB</p>
+///     <p id="disk">Disk 51%</p>
+/// </div>"#;
+///
+/// assert_eq!(html_diff::diff(previous, previous), ElementsDiff::Unchanged);
+/// assert_eq!(
+///     html_diff::diff(previous, next),
+///     ElementsDiff::Children(vec![r#"<p id="cpu">CPU 14%</p>"#]),
+/// );
+/// ```
+pub fn diff<'a>(previous: &str, next: &'a str) -> ElementsDiff<'a> {
+    if previous == next {
+        return ElementsDiff::Unchanged;
+    }
+
+    let mut children = Vec::new();
+    if !diff_children(previous.trim(), next.trim(), &mut children) {
+        return ElementsDiff::Full(next);
+    }
+
+    let changed: usize = children.iter().map(|child| child.len()).sum();
+    if children.is_empty() && previous.trim() == next.trim() {
+        ElementsDiff::Unchanged
+    } else if children.is_empty() || changed >= next.trim().len() {
+        ElementsDiff::Full(next)
+    } else {
+        ElementsDiff::Children(children)
+    }
+}
+
+/// Adds the changed children of the element `next` to `changed`, returning `false` if
+/// the element has to be sent whole.
+fn diff_children<'a>(previous: &str, next: &'a str, changed: &mut Vec<&'a str>) -> bool {
+    let (Some(previous), Some(next)) = (Element::parse(previous), Element::parse(next)) else {
+        return false;
+    };
+    if previous.start_tag != next.start_tag || previous.children.len() != next.children.len() {
+        return false;
+    }
+
+    let mark = changed.len();
+    for (previous, next) in previous.children.iter().zip(&next.children) {
+        if previous.html == next.html {
+            continue;
+        }
+        let same_element = matches!(
+            (previous.kind, next.kind),
+            (Node::Element { id: Some(previous) }, Node::Element { id: Some(next) })
+                if previous == next
+        );
+        if !same_element {
+            changed.truncate(mark);
+            return false;
+        }
+        // A child which only differs in its end tag has no changed children.
+        let before = changed.len();
+        if !diff_children(previous.html, next.html, changed) || changed.len() == before {
+            changed.push(next.html);
+        }
+    }
+    true
+}
+
+/// An element which is the whole fragment, along with its top-level children.
+struct Element<'a> {
+    start_tag: &'a str,
+    children: Vec<Child<'a>>,
+}
+
+struct Child<'a> {
+    kind: Node<'a>,
+    html: &'a str,
+}
+
+impl<'a> Element<'a> {
+    fn parse(html: &'a str) -> Option<Self> {
+        let (Node::Element { .. }, end) = next_node(html, 0)? else {
+            return None;
+        };
+        if end != html.len() {
+            return None;
+        }
+
+        let tag = StartTag::parse(html)?;
+        // Elements without child elements are sent whole when they change.
+        if tag.is_empty() || tag.is_raw_text() {
+            return None;
+        }
+
+        let mut children = Vec::new();
+        let mut at = tag.end;
+        loop {
+            let (kind, end) = next_node(html, at)?;
+            if let Node::EndTag(_) = kind {
+                break;
+            }
+            children.push(Child {
+                kind,
+                html: &html[at..end],
+            });
+            at = end;
+        }
+
+        Some(Self {
+            start_tag: &html[..tag.end],
+            children,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Text,
+    Comment,
+    Element { id: Option<&'a str> },
+    EndTag(&'a str),
+}
+
+/// Scans the node starting at `at`, returning it and where it ends,
+/// or `None` if the HTML is malformed.
+fn next_node(html: &str, at: usize) -> Option<(Node<'_>, usize)> {
+    let rest = &html[at..];
+    if rest.is_empty() {
+        return None;
+    }
+
+    if let Some(comment) = rest.strip_prefix("<!--") {
+        return Some((Node::Comment, at + 4 + comment.find("-->")? + 3));
+    }
+    if rest.starts_with("<!") || rest.starts_with("<?") {
+        return Some((Node::Comment, at + rest.find('>')? + 1));
+    }
+    if let Some(end_tag) = rest.strip_prefix("</") {
+        let close = end_tag.find('>')?;
+        return Some((
+            Node::EndTag(end_tag[..close].trim_end()),
+            at + 2 + close + 1,
+        ));
+    }
+    if rest.len() > 1 && rest.starts_with('<') && rest.as_bytes()[1].is_ascii_alphabetic() {
+        let tag = StartTag::parse(rest)?;
+        let mut end = tag.end;
+        if tag.is_raw_text() {
+            end = raw_text_end(rest, end, tag.name)?;
+        } else if !tag.is_empty() {
+            loop {
+                let (node, next) = next_node(rest, end)?;
+                end = next;
+                match node {
+                    Node::EndTag(name) if name.eq_ignore_ascii_case(tag.name) => break,
+                    Node::EndTag(_) => return None,
+                    _ => {}
+                }
+            }
+        }
+        return Some((Node::Element { id: tag.id }, at + end));
+    }
+
+    let skip = usize::from(rest.starts_with('<'));
+    let end = rest[skip..]
+        .find('<')
+        .map_or(rest.len(), |index| index + skip);
+    Some((Node::Text, at + end))
+}
+
+/// Returns where the end tag of the raw text element `name`, whose content starts at `at`, ends.
+fn raw_text_end(html: &str, at: usize, name: &str) -> Option<usize> {
+    let mut from = at;
+    loop {
+        let start = from + html[from..].find("</")?;
+        let after = start + 2;
+        let is_end_tag = html
+            .get(after..after + name.len())
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(name));
+        if is_end_tag {
+            return Some(after + html[after..].find('>')? + 1);
+        }
+        from = after;
+    }
+}
+
+struct StartTag<'a> {
+    name: &'a str,
+    id: Option<&'a str>,
+    /// Where the start tag ends, after its `>`.
+    end: usize,
+    self_closing: bool,
+}
+
+impl<'a> StartTag<'a> {
+    /// Scans the start tag at the start of `html`.
+    fn parse(html: &'a str) -> Option<Self> {
+        let bytes = html.as_bytes();
+        let name_end =
+            html[1..].find(|c: char| c.is_ascii_whitespace() || matches!(c, '>' | '/'))? + 1;
+        let name = &html[1..name_end];
+
+        let mut id = None;
+        let mut at = name_end;
+        loop {
+            at = skip_whitespace(bytes, at)?;
+            match bytes[at] {
+                b'>' => {
+                    return Some(Self {
+                        name,
+                        id,
+                        end: at + 1,
+                        self_closing: false,
+                    });
+                }
+                b'/' if bytes.get(at + 1) == Some(&b'>') => {
+                    return Some(Self {
+                        name,
+                        id,
+                        end: at + 2,
+                        self_closing: true,
+                    });
+                }
+                b'/' => {
+                    at += 1;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let attribute_end = at
+                + html[at..]
+                    .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))?;
+            let attribute = &html[at..attribute_end];
+            at = skip_whitespace(bytes, attribute_end)?;
+            if bytes[at] != b'=' {
+                continue;
+            }
+
+            at = skip_whitespace(bytes, at + 1)?;
+            let value = match bytes[at] {
+                quote @ (b'"' | b'\'') => {
+                    let len = html[at + 1..].find(char::from(quote))?;
+                    let value = &html[at + 1..at + 1 + len];
+                    at += len + 2;
+                    value
+                }
+                _ => {
+                    let len = html[at..].find(|c: char| c.is_ascii_whitespace() || c == '>')?;
+                    let value = &html[at..at + len];
+                    at += len;
+                    value
+                }
+            };
+            if attribute.eq_ignore_ascii_case("id") {
+                id = Some(value);
+            }
+        }
+    }
+
+    /// Returns whether the element has no content and no end tag.
+    fn is_empty(&self) -> bool {
+        self.self_closing
+            || VOID_ELEMENTS
+                .iter()
+                .any(|void| void.eq_ignore_ascii_case(self.name))
+    }
+
+    fn is_raw_text(&self) -> bool {
+        !self.self_closing
+            && RAW_TEXT_ELEMENTS
+                .iter()
+                .any(|raw| raw.eq_ignore_ascii_case(self.name))
+    }
+}
+
+fn skip_whitespace(bytes: &[u8], mut at: usize) -> Option<usize> {
+    while bytes.get(at)?.is_ascii_whitespace() {
+        at += 1;
+    }
+    Some(at)
+}
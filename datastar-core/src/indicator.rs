@@ -0,0 +1,117 @@
+//! [`Indicator`] brackets a stream with busy and idle signal patches,
+//! matching how `data-indicator` is used on the client.
+//!
+//! Instead of sending `{generating: true}` at the start of a stream and `{generating: false}`
+//! at its end by hand, the stream is wrapped with [`IndicatorExt::with_indicator`].
+
+use {
+    crate::{DatastarEvent, patch_signals, response::IntoDatastarResponse},
+    core::{
+        fmt,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    futures_core::Stream,
+};
+
+/// [`IndicatorExt`] adds the [`with_indicator`](IndicatorExt::with_indicator) combinator
+/// to every [`Stream`] of [`DatastarEvent`]s.
+pub trait IndicatorExt: Stream<Item = DatastarEvent> + Sized {
+    /// Patches the boolean signal at the dot separated `path` to `true` before the first event,
+    /// and back to `false` once the stream ends.
+    ///
+    /// Handlers which fail should send the error to the browser and end the stream,
+    /// which resets the signal as well. Nothing can be sent when the client disconnects.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{indicator::IndicatorExt, sender};
+    ///
+    /// let (sender, receiver) = sender::channel();
+    ///
+    /// // Sends `{"fetching":{"feed":true}}` first and `{"fetching":{"feed":false}}` last.
+    /// let response = receiver.with_indicator("fetching.feed");
+    /// # drop((sender, response));
+    /// ```
+    fn with_indicator(self, path: impl Into<String>) -> Indicator<Self> {
+        Indicator {
+            stream: Box::pin(self),
+            path: path.into(),
+            state: State::Start,
+        }
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>> IndicatorExt for S {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Start,
+    Streaming,
+    Done,
+}
+
+/// [`Indicator`] is the [`Stream`] returned by [`IndicatorExt::with_indicator`].
+pub struct Indicator<S> {
+    stream: Pin<Box<S>>,
+    path: String,
+    state: State,
+}
+
+// Nothing is pinned structurally, as the stream is boxed.
+impl<S> Unpin for Indicator<S> {}
+
+impl<S> fmt::Debug for Indicator<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Indicator")
+            .field("path", &self.path)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>> Stream for Indicator<S> {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.state {
+            State::Start => {
+                self.state = State::Streaming;
+                Poll::Ready(Some(patch_signals::flag(&self.path, true).into()))
+            }
+            State::Streaming => match futures_core::ready!(self.stream.as_mut().poll_next(cx)) {
+                Some(event) => Poll::Ready(Some(event)),
+                None => {
+                    self.state = State::Done;
+                    Poll::Ready(Some(patch_signals::flag(&self.path, false).into()))
+                }
+            },
+            State::Done => Poll::Ready(None),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let extra = match self.state {
+            State::Start => 2,
+            State::Streaming => 1,
+            State::Done => return (0, Some(0)),
+        };
+        let (lower, upper) = self.stream.size_hint();
+        (
+            lower.saturating_add(extra),
+            upper.and_then(|upper| upper.checked_add(extra)),
+        )
+    }
+}
+
+impl<S> IntoDatastarResponse for Indicator<S>
+where
+    S: Stream<Item = DatastarEvent> + Send + 'static,
+{
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
@@ -0,0 +1,251 @@
+//! Logging of every outgoing event for debugging, with the `inspector` feature.
+//!
+//! Each framework integration has a middleware which inspects the SSE responses of an
+//! application: the `InspectorLayer` of Axum, the `InspectorFairing` of Rocket and the
+//! `inspection` filter of Warp. They log every event with [`tracing`], with its type, the
+//! selector it targets, its size and the time since the stream started.
+//!
+//! The events can also be followed live in the browser, by serving [`Inspector::panel`] at
+//! [`INSPECTOR_PATH`], which appends an entry for every event to a `#datastar-inspector` list:
+//!
+//! ```html
+//! <ol id="datastar-inspector" data-init="@get('/_datastar/inspector')"></ol>
+//! ```
+//!
+//! The panel shows the events of every client, so it must not be served in production.
+
+use {
+    crate::{
+        DatastarEvent,
+        consts::{self, ElementPatchMode},
+        parse::{self, EventStreamDecoder},
+        patch_elements::{Elements, PatchElements},
+        response::IntoDatastarResponse,
+        selector,
+    },
+    core::{
+        fmt,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    futures_channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    futures_core::Stream,
+    std::{
+        sync::{Arc, Mutex, PoisonError},
+        time::Instant,
+    },
+};
+
+/// The path the [`Inspector::panel`] is conventionally served at, whose responses
+/// aren't inspected.
+pub const INSPECTOR_PATH: &str = "/_datastar/inspector";
+
+/// The id of the list the [`Inspector::panel`] appends its entries to.
+pub const PANEL_ID: &str = "datastar-inspector";
+
+/// [`Inspector`] is shared by the middleware inspecting the responses and the live panels.
+///
+/// Cloning an [`Inspector`] returns a handle to the same inspector.
+#[derive(Debug, Clone, Default)]
+pub struct Inspector {
+    panels: Arc<Mutex<Vec<UnboundedSender<DatastarEvent>>>>,
+}
+
+impl Inspector {
+    /// Creates a new [`Inspector`] without any panel.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the stream of a live panel, which appends an entry to `#datastar-inspector`
+    /// for every event inspected from now on.
+    pub fn panel(&self) -> InspectorPanel {
+        let (sender, receiver) = mpsc::unbounded();
+        self.panels
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sender);
+        InspectorPanel { receiver }
+    }
+
+    /// Starts inspecting the response to a request for `path`.
+    pub fn inspect(&self, path: impl Into<String>) -> Inspection {
+        Inspection {
+            inspector: self.clone(),
+            path: path.into(),
+            start: Instant::now(),
+            decoder: EventStreamDecoder::new(),
+        }
+    }
+
+    /// Sends `entry` to every panel, dropping those which were closed.
+    fn mirror(&self, entry: impl FnOnce() -> DatastarEvent) {
+        let mut panels = self.panels.lock().unwrap_or_else(PoisonError::into_inner);
+        if panels.is_empty() {
+            return;
+        }
+        let entry = entry();
+        panels.retain(|panel| panel.unbounded_send(entry.clone()).is_ok());
+    }
+}
+
+/// [`Inspection`] inspects the events of a single response, as they are sent.
+pub struct Inspection {
+    inspector: Inspector,
+    path: String,
+    start: Instant,
+    decoder: EventStreamDecoder,
+}
+
+impl fmt::Debug for Inspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Inspection")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Inspection {
+    /// Inspects the events in the next chunk of the body of the response.
+    ///
+    /// An event split across chunks is inspected once its last chunk is observed.
+    pub fn observe(&mut self, chunk: &[u8]) {
+        self.decoder.feed(chunk);
+        loop {
+            match self.decoder.next_event() {
+                Ok(Some(event)) => self.observe_event(&event),
+                Ok(None) => break,
+                Err(err) => tracing::warn!(path = %self.path, error = %err, "invalid SSE event"),
+            }
+        }
+    }
+
+    /// Inspects an event of the response, before it's written.
+    ///
+    /// Comments, such as keep-alives, aren't inspected.
+    pub fn observe_event(&mut self, event: &DatastarEvent) {
+        let Some(name) = event.event_name() else {
+            return;
+        };
+        let framed = event.to_string();
+        let target = target(event);
+        let elapsed = self.start.elapsed();
+
+        tracing::debug!(
+            path = %self.path,
+            event = name,
+            selector = target.as_deref(),
+            bytes = framed.len(),
+            elapsed_ms = elapsed.as_millis(),
+            "sent a Datastar event",
+        );
+
+        self.inspector.mirror(|| {
+            let target = target
+                .as_deref()
+                .map(|target| format!(" <code>{}</code>", Elements::text(target)))
+                .unwrap_or_default();
+            let entry = format!(
+                "<li><strong>{}</strong> {}{target} {} B +{} ms<pre>{}</pre></li>",
+                Elements::text(name),
+                Elements::text(&self.path),
+                framed.len(),
+                elapsed.as_millis(),
+                Elements::text(framed.trim_end()),
+            );
+            PatchElements::new(Elements::trusted(entry))
+                .selector(format!("#{PANEL_ID}"))
+                .mode(ElementPatchMode::Append)
+                .into()
+        });
+    }
+}
+
+/// Returns the selector targeted by a patch of elements: its selector, or the id of its
+/// first element.
+fn target(event: &DatastarEvent) -> Option<String> {
+    if event.event_name() != Some(consts::EventType::PatchElements.as_str()) {
+        return None;
+    }
+    let mut elements = None;
+    for line in &event.data {
+        match parse::data_line(line) {
+            (consts::SELECTOR_DATALINE_LITERAL, selector) => return Some(selector.to_owned()),
+            (consts::ELEMENTS_DATALINE_LITERAL, line) => {
+                elements.get_or_insert(line);
+            }
+            _ => {}
+        }
+    }
+    selector::first_element_id(elements?).map(selector::id_selector)
+}
+
+/// [`InspectExt`] adds the [`inspected`](InspectExt::inspected) combinator to every [`Stream`]
+/// of [`DatastarEvent`]s, for frameworks whose responses can't be inspected by a middleware.
+pub trait InspectExt: Stream<Item = DatastarEvent> + Sized {
+    /// Inspects every event of the stream with `inspection`, before it's written.
+    fn inspected(self, inspection: Inspection) -> Inspected<Self> {
+        Inspected {
+            stream: Box::pin(self),
+            inspection,
+        }
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>> InspectExt for S {}
+
+/// [`Inspected`] is the [`Stream`] returned by [`InspectExt::inspected`].
+#[derive(Debug)]
+pub struct Inspected<S> {
+    stream: Pin<Box<S>>,
+    inspection: Inspection,
+}
+
+// Nothing is pinned structurally, as the stream is boxed.
+impl<S> Unpin for Inspected<S> {}
+
+impl<S: Stream<Item = DatastarEvent>> Stream for Inspected<S> {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let event = futures_core::ready!(self.stream.as_mut().poll_next(cx));
+        if let Some(event) = &event {
+            self.inspection.observe_event(event);
+        }
+        Poll::Ready(event)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent> + Send + 'static> IntoDatastarResponse for Inspected<S> {
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
+
+/// [`InspectorPanel`] is the stream of a live panel, returned by [`Inspector::panel`].
+#[derive(Debug)]
+pub struct InspectorPanel {
+    receiver: UnboundedReceiver<DatastarEvent>,
+}
+
+impl Stream for InspectorPanel {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl IntoDatastarResponse for InspectorPanel {
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
@@ -0,0 +1,637 @@
+//! The JSON representation of the events, which uses the field names of the SDK test suite.
+//!
+//! [`PatchElements`], [`PatchSignals`] and [`ExecuteScript`] use the camel cased fields of the
+//! test suite, such as `eventId` and `retryDuration`, leaving out the fields at their defaults.
+//! A [`DatastarEvent`] deserializes from such an event tagged with its `type`, as in
+//! `{"type": "patchSignals", "signals": {"count": 1}}`, and otherwise serializes to its SSE fields.
+//!
+//! The signals of requests are deserialized here too, by `simd-json` for large payloads
+//! when the `simd-json` feature is enabled.
+
+use {
+    crate::{
+        DatastarEvent, Error,
+        consts::{self, ElementPatchMode, EventType},
+        data_lines::DataLines,
+        execute_script::{AttributeValue, ExecuteScript},
+        patch_elements::PatchElements,
+        patch_signals::PatchSignals,
+    },
+    core::{fmt, time::Duration},
+    serde::{
+        Deserialize, Deserializer, Serialize, Serializer,
+        de::{self, DeserializeOwned, MapAccess, SeqAccess, Visitor},
+        ser::SerializeMap,
+    },
+    std::borrow::Cow,
+};
+
+/// Signals shorter than this are deserialized by `serde_json`, as copying them for `simd-json`,
+/// which parses in place, costs more than its faster parsing saves.
+#[cfg(feature = "simd-json")]
+const SIMD_JSON_MIN_LEN: usize = 4 * 1024;
+
+/// The media type of a form whose fields are read as the signals.
+const URLENCODED_MEDIA_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// The media type of a form with files, which can't be read as the signals.
+const MULTIPART_MEDIA_TYPE: &str = "multipart/form-data";
+
+/// Deserializes the signals of a request, returning [`Error::InvalidSignals`]
+/// with the path of the signal which failed.
+pub(crate) fn signals_from_slice<T: DeserializeOwned>(json: &[u8]) -> Result<T, Error> {
+    #[cfg(feature = "simd-json")]
+    if json.len() >= SIMD_JSON_MIN_LEN {
+        let mut json = json.to_vec();
+        let mut deserializer = simd_json::Deserializer::from_slice(&mut json)
+            .map_err(|err| invalid_signals(".".to_owned(), err))?;
+        return serde_path_to_error::deserialize(&mut deserializer).map_err(path_error);
+    }
+
+    let mut deserializer = serde_json::Deserializer::from_slice(json);
+    let signals = serde_path_to_error::deserialize(&mut deserializer).map_err(path_error)?;
+    deserializer
+        .end()
+        .map_err(|err| invalid_signals(".".to_owned(), err))?;
+    Ok(signals)
+}
+
+/// Deserializes the signals of the body of a request with the `Content-Type` header `content_type`.
+///
+/// The fields of a URL encoded form, as submitted by a plain HTML form, are read as the signals.
+/// A multipart form returns [`Error::UnsupportedContentType`], any other body is read as JSON.
+pub(crate) fn signals_from_body<T: DeserializeOwned>(
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<T, Error> {
+    let media_type = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .map(str::trim)
+        .unwrap_or_default();
+
+    if media_type.eq_ignore_ascii_case(URLENCODED_MEDIA_TYPE) {
+        return serde_urlencoded::from_bytes(body)
+            .map_err(|err| invalid_signals(".".to_owned(), err));
+    }
+    if media_type.eq_ignore_ascii_case(MULTIPART_MEDIA_TYPE) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            media_type,
+            "request body is a multipart form rather than signals"
+        );
+
+        return Err(Error::UnsupportedContentType(media_type.to_owned()));
+    }
+
+    signals_from_slice(body)
+}
+
+/// Deserializes the signals of the text fields of a form.
+#[cfg(feature = "multipart")]
+pub(crate) fn signals_from_fields<T: DeserializeOwned>(
+    fields: &[(String, String)],
+) -> Result<T, Error> {
+    serde_urlencoded::to_string(fields)
+        .map_err(|err| invalid_signals(".".to_owned(), err))
+        .and_then(|form| {
+            serde_urlencoded::from_str(&form).map_err(|err| invalid_signals(".".to_owned(), err))
+        })
+}
+
+fn path_error<E: fmt::Display>(err: serde_path_to_error::Error<E>) -> Error {
+    // The path is `?` when the JSON is malformed before the first signal.
+    let path = match err.path().to_string() {
+        path if path == "?" => ".".to_owned(),
+        path => path,
+    };
+    invalid_signals(path, err.inner())
+}
+
+fn invalid_signals(path: String, err: impl fmt::Display) -> Error {
+    let message = err.to_string();
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(%path, %message, "failed to deserialize signals from request");
+
+    Error::InvalidSignals { path, message }
+}
+
+impl Serialize for ElementPatchMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ElementPatchMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Cow::<str>::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for EventType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for EventType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Cow::<str>::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PatchElementsJson<'a> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    elements: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    event_id: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_duration: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    selector: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<ElementPatchMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    use_view_transition: Option<bool>,
+}
+
+/// # Examples
+///
+/// ```
+/// use datastar_core::prelude::{ElementPatchMode, PatchElements};
+///
+/// let patch: PatchElements = serde_json::from_str(
+///     r##"{"elements": "<li>Done</li>", "selector": "#feed", "mode": "append", "eventId": "7"}"##,
+/// )?;
+///
+/// assert_eq!(patch.mode, ElementPatchMode::Append);
+/// assert_eq!(
+///     serde_json::to_string(&patch)?,
+///     r##"{"elements":"<li>Done</li>","eventId":"7","selector":"#feed","mode":"append"}"##,
+/// );
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+impl Serialize for PatchElements {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PatchElementsJson {
+            elements: self.elements.as_deref().map(Cow::Borrowed),
+            event_id: self.id.as_deref().map(Cow::Borrowed),
+            retry_duration: retry_duration(self.retry),
+            selector: self.selector.as_deref().map(Cow::Borrowed),
+            mode: (self.mode != ElementPatchMode::default()).then_some(self.mode),
+            use_view_transition: (self.use_view_transition
+                != consts::DEFAULT_ELEMENTS_USE_VIEW_TRANSITIONS)
+                .then_some(self.use_view_transition),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PatchElements {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = PatchElementsJson::deserialize(deserializer)?;
+        let mode = json.mode.unwrap_or_default();
+        let mut patch = Self::new("");
+        // Removals carry no elements, as created by `PatchElements::new_remove`.
+        patch.elements = match json.elements {
+            Some(elements) => Some(elements.into_owned().into()),
+            None => (mode != ElementPatchMode::Remove).then_some(Cow::Borrowed("")),
+        };
+        patch.id = json.event_id.map(|id| id.into_owned().into());
+        patch.retry = retry(json.retry_duration);
+        patch.selector = json.selector.map(|selector| selector.into_owned().into());
+        patch.mode = mode;
+        patch.use_view_transition = json
+            .use_view_transition
+            .unwrap_or(consts::DEFAULT_ELEMENTS_USE_VIEW_TRANSITIONS);
+        Ok(patch)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PatchSignalsJson<'a> {
+    #[serde(default, skip_serializing)]
+    signals: Option<JsonText>,
+    #[serde(rename = "signals-raw", default)]
+    signals_raw: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    event_id: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_duration: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    only_if_missing: Option<bool>,
+}
+
+/// The signals are serialized as the `signals-raw` string, as they aren't necessarily JSON.
+/// They deserialize from either `signals-raw`, or from a `signals` object.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::prelude::PatchSignals;
+///
+/// let patch: PatchSignals = serde_json::from_str(r#"{"signals": {"b": 1, "a": [true, null]}}"#)?;
+///
+/// assert_eq!(patch.signals, r#"{"b":1,"a":[true,null]}"#);
+/// assert_eq!(serde_json::to_string(&patch)?, r#"{"signals-raw":"{\"b\":1,\"a\":[true,null]}"}"#);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+impl Serialize for PatchSignals {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PatchSignalsJson {
+            signals: None,
+            signals_raw: Some(Cow::Borrowed(&self.signals)),
+            event_id: self.id.as_deref().map(Cow::Borrowed),
+            retry_duration: retry_duration(self.retry),
+            only_if_missing: (self.only_if_missing
+                != consts::DEFAULT_PATCH_SIGNALS_ONLY_IF_MISSING)
+                .then_some(self.only_if_missing),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PatchSignals {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = PatchSignalsJson::deserialize(deserializer)?;
+        let signals = match (json.signals_raw, json.signals) {
+            (Some(raw), _) => raw.into_owned(),
+            (None, Some(JsonText(signals))) => signals,
+            (None, None) => String::new(),
+        };
+        let mut patch = Self::new(signals);
+        patch.id = json.event_id.map(|id| id.into_owned().into());
+        patch.retry = retry(json.retry_duration);
+        patch.only_if_missing = json
+            .only_if_missing
+            .unwrap_or(consts::DEFAULT_PATCH_SIGNALS_ONLY_IF_MISSING);
+        Ok(patch)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExecuteScriptJson<'a> {
+    script: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    event_id: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_duration: Option<u64>,
+    #[serde(default, skip_serializing_if = "Attributes::is_empty")]
+    attributes: Attributes,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auto_remove: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    escape: Option<bool>,
+}
+
+/// The attributes are an object of names and values. As in the test suite, values which
+/// aren't strings are rendered as their JSON text, so `false` is rendered as `"false"`.
+/// Attributes without a value are serialized as `true`.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::prelude::ExecuteScript;
+///
+/// let script: ExecuteScript = serde_json::from_str(
+///     r#"{"script": "console.log(1)", "attributes": {"type": "module", "defer": false}}"#,
+/// )?;
+///
+/// assert_eq!(script.attributes, [r#"type="module""#, r#"defer="false""#]);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+impl Serialize for ExecuteScript {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ExecuteScriptJson {
+            script: Cow::Borrowed(&self.script),
+            event_id: self.id.as_deref().map(Cow::Borrowed),
+            retry_duration: retry_duration(self.retry),
+            attributes: Attributes(self.attributes.iter().map(|a| parse_attribute(a)).collect()),
+            auto_remove: self.auto_remove,
+            escape: (!self.escape).then_some(false),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExecuteScript {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = ExecuteScriptJson::deserialize(deserializer)?;
+        let mut script = Self::new(json.script)
+            .attributes_map(json.attributes.0)
+            .escape(json.escape.unwrap_or(true));
+        script.id = json.event_id.map(|id| id.into_owned().into());
+        script.retry = retry(json.retry_duration);
+        script.auto_remove = json.auto_remove;
+        Ok(script)
+    }
+}
+
+/// The attributes of an [`ExecuteScript`], in order.
+#[derive(Default)]
+struct Attributes(Vec<(String, AttributeValue)>);
+
+impl Attributes {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Serialize for Attributes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            match value {
+                AttributeValue::Text(text) => map.serialize_entry(name, text)?,
+                AttributeValue::Flag(flag) => map.serialize_entry(name, flag)?,
+            }
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Attributes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AttributesVisitor;
+
+        impl<'de> Visitor<'de> for AttributesVisitor {
+            type Value = Attributes;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an object of attributes")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut attributes = Vec::new();
+                while let Some((name, value)) = map.next_entry::<String, AttributeJson>()? {
+                    let value = match value {
+                        AttributeJson::Text(text) => text,
+                        AttributeJson::Other(JsonText(json)) => json,
+                    };
+                    attributes.push((name, AttributeValue::Text(value)));
+                }
+                Ok(Attributes(attributes))
+            }
+        }
+
+        deserializer.deserialize_map(AttributesVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AttributeJson {
+    Text(String),
+    Other(JsonText),
+}
+
+/// Splits a rendered attribute, such as `type="module"`, into its name and unescaped value.
+fn parse_attribute(attribute: &str) -> (String, AttributeValue) {
+    let Some((name, value)) = attribute.split_once('=') else {
+        return (attribute.to_owned(), AttributeValue::Flag(true));
+    };
+    let value = ['"', '\'']
+        .into_iter()
+        .find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote))
+        .unwrap_or(value);
+    let value = value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&");
+    (name.to_owned(), AttributeValue::Text(value))
+}
+
+/// A JSON value of any kind as compact JSON text, keeping the order of object keys.
+struct JsonText(String);
+
+impl<'de> Deserialize<'de> for JsonText {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonTextVisitor)
+    }
+}
+
+struct JsonTextVisitor;
+
+impl JsonTextVisitor {
+    fn to_json<E: de::Error>(value: impl Serialize) -> Result<JsonText, E> {
+        serde_json::to_string(&value)
+            .map(JsonText)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Visitor<'de> for JsonTextVisitor {
+    type Value = JsonText;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Self::to_json(value)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Self::to_json(value)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Self::to_json(value)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Self::to_json(value)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Self::to_json(value)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(JsonText("null".to_owned()))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        JsonText::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut json = String::from("[");
+        while let Some(JsonText(element)) = seq.next_element()? {
+            if json.len() > 1 {
+                json.push(',');
+            }
+            json.push_str(&element);
+        }
+        json.push(']');
+        Ok(JsonText(json))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut json = String::from("{");
+        while let Some((key, JsonText(value))) = map.next_entry::<Cow<'_, str>, JsonText>()? {
+            if json.len() > 1 {
+                json.push(',');
+            }
+            json.push_str(&Self::to_json::<A::Error>(key)?.0);
+            json.push(':');
+            json.push_str(&value);
+        }
+        json.push('}');
+        Ok(JsonText(json))
+    }
+}
+
+/// [`DataLines`] serialize to an array of strings, one per line.
+impl Serialize for DataLines {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DataLines {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<Cow<'de, str>>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct DatastarEventJson<'a> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    event: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "<[_]>::is_empty")]
+    comments: Cow<'a, [String]>,
+    #[serde(default, skip_serializing_if = "<[_]>::is_empty")]
+    fields: Cow<'a, [(String, String)]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    event_id: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_duration: Option<u64>,
+    #[serde(default)]
+    data: Cow<'a, DataLines>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum TypedEvent {
+    PatchElements(PatchElements),
+    PatchSignals(PatchSignals),
+    ExecuteScript(ExecuteScript),
+}
+
+/// The `type` of an event, without the rest of its fields.
+#[derive(Deserialize)]
+struct EventTag {
+    #[serde(rename = "type")]
+    tag: Option<String>,
+}
+
+/// A [`DatastarEvent`] serializes to its SSE fields, with the `event` type, `comments`,
+/// custom `fields`, `eventId`, `retryDuration` and `data` lines.
+///
+/// Besides that, it deserializes from a [`PatchElements`], [`PatchSignals`] or [`ExecuteScript`]
+/// tagged with its `type`, so the events of a test case can be deserialized directly.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::DatastarEvent;
+///
+/// let event: DatastarEvent =
+///     serde_json::from_str(r#"{"type": "patchSignals", "signals": {"count": 1}, "eventId": "7"}"#)?;
+///
+/// assert_eq!(
+///     serde_json::to_string(&event)?,
+///     r#"{"event":"datastar-patch-signals","eventId":"7","data":["signals {\"count\":1}"]}"#,
+/// );
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+impl Serialize for DatastarEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        DatastarEventJson {
+            event: self.event_name().map(Cow::Borrowed),
+            comments: Cow::Borrowed(&self.comments),
+            fields: Cow::Borrowed(&self.fields),
+            event_id: self.id.as_deref().map(Cow::Borrowed),
+            retry_duration: retry_duration(self.retry),
+            data: Cow::Borrowed(&self.data),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DatastarEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // The event is captured as JSON text first, so the errors of the typed events
+        // aren't hidden behind a generic error, as with an untagged enum.
+        let JsonText(text) = JsonText::deserialize(deserializer)?;
+        let EventTag { tag } = serde_json::from_str(&text).map_err(de::Error::custom)?;
+        if tag.is_some() {
+            return match serde_json::from_str(&text).map_err(de::Error::custom)? {
+                TypedEvent::PatchElements(patch) => Ok(patch.into()),
+                TypedEvent::PatchSignals(patch) => Ok(patch.into()),
+                TypedEvent::ExecuteScript(script) => Ok(script.into()),
+            };
+        }
+
+        let json: DatastarEventJson<'_> = serde_json::from_str(&text).map_err(de::Error::custom)?;
+        let (event, custom_event) = match json.event {
+            Some(event) => match event.parse() {
+                Ok(event_type) => (Some(event_type), None),
+                Err(_) if !event.is_empty() && !crate::has_newline(&event) => {
+                    (None, Some(event.into_owned()))
+                }
+                Err(_) => return Err(de::Error::custom("invalid SSE event type")),
+            },
+            None => (None, None),
+        };
+        let event = DatastarEvent {
+            event,
+            custom_event,
+            comments: Vec::new(),
+            fields: Vec::new(),
+            id: json.event_id.map(|id| id.into_owned().into()),
+            retry: retry(json.retry_duration),
+            data: json.data.into_owned(),
+        };
+        // The comments and fields are validated as if they were added one by one.
+        let event = json
+            .comments
+            .iter()
+            .try_fold(event, |event, comment| event.with_comment(comment.as_str()))
+            .map_err(de::Error::custom)?;
+        json.fields
+            .iter()
+            .try_fold(event, |event, (name, value)| {
+                event.with_field(name.as_str(), value.as_str())
+            })
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Returns the `retryDuration` in milliseconds.
+fn retry_duration(retry: Option<Duration>) -> Option<u64> {
+    retry.map(|retry| retry.as_millis() as u64)
+}
+
+/// Returns the `retry` of a `retryDuration`.
+fn retry(retry_duration: Option<u64>) -> Option<Duration> {
+    retry_duration.map(Duration::from_millis)
+}
@@ -0,0 +1,814 @@
+//! Core of the Rust implementation of the [Datastar](https://data-star.dev) SDK specification.
+//!
+//! This crate contains the protocol types and is re-exported by the `datastar` crate.
+//! Without any features enabled it only depends on `futures-core`, so integration authors
+//! and embedded users can depend on it directly without pulling in a web framework.
+//!
+//! The framework integrations live here as well, behind the `axum`, `rocket` and `warp` features,
+//! as Rust only allows implementing the framework traits next to the protocol types.
+//!
+//! # Constructing events
+//!
+//! [`PatchElements`], [`PatchSignals`] and [`ExecuteScript`] are `#[non_exhaustive]`, as the
+//! fields of the Datastar specification are added to them in minor releases, with defaults which
+//! keep the events they write unchanged. They are created with their constructors or with
+//! [`Default`], and configured with the builder methods:
+//!
+//! ```
+//! use datastar_core::prelude::{ElementPatchMode, PatchElements};
+//!
+//! let patch = PatchElements::default()
+//!     .elements("<li>Item</li>")
+//!     .selector("#list")
+//!     .mode(ElementPatchMode::Append);
+//!
+//! assert_eq!(
+//!     patch,
+//!     PatchElements::new("<li>Item</li>").selector("#list").mode(ElementPatchMode::Append),
+//! );
+//! ```
+//!
+//! [`DatastarEvent`] is `#[non_exhaustive]` as well, and its custom event type, comments and
+//! fields are private, as they must be validated.
+//!
+//! [`PatchElements`]: patch_elements::PatchElements
+//! [`PatchSignals`]: patch_signals::PatchSignals
+//! [`ExecuteScript`]: execute_script::ExecuteScript
+
+#![forbid(missing_docs)]
+#![forbid(missing_debug_implementations)]
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "broadcast")]
+pub mod broadcast;
+#[cfg(feature = "serde")]
+pub mod coalesce;
+#[cfg(feature = "components")]
+pub mod components;
+#[cfg(feature = "serde")]
+pub mod compression;
+#[cfg(feature = "serde")]
+pub mod conformance;
+#[cfg(feature = "html-diff")]
+pub mod html_diff;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+#[cfg(feature = "minify")]
+pub mod minify;
+#[cfg(feature = "multipart")]
+pub mod multipart;
+#[cfg(feature = "bytes")]
+pub mod pool;
+#[cfg(feature = "serde")]
+pub mod query;
+#[cfg(feature = "serde")]
+pub mod recording;
+#[cfg(feature = "redis")]
+pub mod redis;
+#[cfg(feature = "rocket")]
+pub mod rocket;
+#[cfg(feature = "ammonia")]
+pub mod sanitize;
+#[cfg(feature = "serde")]
+pub mod signal_tracker;
+#[cfg(feature = "serde")]
+pub mod signals;
+#[cfg(feature = "tokio")]
+pub mod stall;
+#[cfg(any(feature = "axum", feature = "rocket", feature = "warp"))]
+pub mod stream;
+#[cfg(any(feature = "minijinja", feature = "tera"))]
+pub mod templates;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "warp")]
+pub mod warp;
+
+pub mod accept;
+pub mod batch;
+pub mod chunked;
+pub mod clock;
+pub mod consts;
+pub mod data_lines;
+mod error;
+pub mod event_id;
+pub mod execute_script;
+pub mod fragment;
+pub mod heartbeat;
+pub mod html;
+pub mod indicator;
+#[cfg(feature = "serde")]
+mod json;
+pub mod migrations;
+pub mod parse;
+pub mod patch_elements;
+pub mod patch_signals;
+pub mod replay;
+pub mod response;
+pub mod scripts;
+pub mod selector;
+pub mod sender;
+pub mod serialize;
+pub mod signal_path;
+pub mod snapshot;
+pub mod sse_payload;
+
+/// The prelude for the `datastar-core` crate
+pub mod prelude {
+    pub use crate::{
+        DatastarEvent, IntoDatastarEvent,
+        consts::ElementPatchMode,
+        execute_script::ExecuteScript,
+        heartbeat::HeartbeatExt,
+        indicator::IndicatorExt,
+        patch_elements::{Elements, PatchElements},
+        patch_signals::PatchSignals,
+        response::IntoDatastarResponse,
+    };
+}
+
+pub use {error::Error, response::respond};
+
+use {
+    core::{fmt::Display, time::Duration},
+    data_lines::DataLines,
+    std::borrow::Cow,
+};
+
+/// [`DatastarEvent`] is a struct that represents a generic Datastar event.
+/// All Datastar events implement `Into<DatastarEvent>`.
+///
+/// It is constructed with [`DatastarEvent::new`], [`DatastarEvent::custom`] or from the other
+/// events, as its comments, custom fields and non-standard event type are validated, see
+/// [Constructing events](crate#constructing-events).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct DatastarEvent {
+    /// `event` is the type of event, which is `None` for events that only carry a comment,
+    /// such as [`DatastarEvent::keep_alive`].
+    pub event: Option<consts::EventType>,
+    /// The name of a non-standard event type, validated by [`DatastarEvent::custom`].
+    custom_event: Option<String>,
+    /// The SSE comments, validated by [`DatastarEvent::with_comment`].
+    comments: Vec<String>,
+    /// The custom SSE fields, validated by [`DatastarEvent::with_field`].
+    fields: Vec<(String, String)>,
+    /// `id` is can be used by the backend to replay events.
+    /// This is part of the SSE spec and is used to tell the browser how to handle the event.
+    /// After a reconnect the browser sends the last received `id` back, which can be read
+    /// using the [`LastEventId`](crate::replay::LastEventId) extractor of each framework integration.
+    /// For more details see <https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#id>
+    pub id: Option<Cow<'static, str>>,
+    /// `retry` is part of the SSE spec and is used to tell the browser how long to wait before reconnecting if the connection is lost.
+    /// When `None`, no `retry` field is sent and the browser keeps its current reconnection delay.
+    /// For more details see <https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#retry>
+    pub retry: Option<Duration>,
+    /// `data` is the data that is sent with the event, one line per `data` field.
+    pub data: DataLines,
+}
+
+impl DatastarEvent {
+    /// Creates a new [`DatastarEvent`] of the given type with the given data lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{DatastarEvent, consts::EventType};
+    ///
+    /// let event = DatastarEvent::new(EventType::PatchSignals, ["signals {count: 0}"]).id("1");
+    ///
+    /// assert_eq!(
+    ///     event.to_string(),
+    ///     "event: datastar-patch-signals\nid: 1\ndata: signals {count: 0}\n\n",
+    /// );
+    /// ```
+    pub fn new(event: consts::EventType, data: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        Self {
+            event: Some(event),
+            custom_event: None,
+            comments: Vec::new(),
+            fields: Vec::new(),
+            id: None,
+            retry: None,
+            data: data.into_iter().collect(),
+        }
+    }
+
+    /// Creates a new [`DatastarEvent`] consisting of only an SSE comment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::DatastarEvent;
+    ///
+    /// assert_eq!(DatastarEvent::comment("still here")?.to_string(), ": still here\n\n");
+    /// # Ok::<(), datastar_core::Error>(())
+    /// ```
+    ///
+    /// Fails with [`Error::InvalidField`] if `comment` contains a newline or carriage return,
+    /// as [`DatastarEvent::with_comment`] does.
+    pub fn comment(comment: impl Into<String>) -> Result<Self, Error> {
+        Self::empty().with_comment(comment)
+    }
+
+    /// Creates a new [`DatastarEvent`] of a non-standard type with the given data lines,
+    /// for Datastar plugins and additions to the specification this crate doesn't support yet.
+    ///
+    /// The event is written by every framework integration, like the standard events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::DatastarEvent;
+    ///
+    /// let event = DatastarEvent::custom("my-plugin-toast", ["message Saved"]);
+    ///
+    /// assert_eq!(event.event_name(), Some("my-plugin-toast"));
+    /// assert_eq!(
+    ///     event.to_string(),
+    ///     "event: my-plugin-toast\ndata: message Saved\n\n",
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `event` is empty or contains a newline or carriage return.
+    pub fn custom(
+        event: impl Into<String>,
+        data: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Self {
+        let event = event.into();
+        assert!(
+            !event.is_empty() && !has_newline(&event),
+            "SSE event types must not be empty or contain newlines"
+        );
+        Self {
+            event: None,
+            custom_event: Some(event),
+            comments: Vec::new(),
+            fields: Vec::new(),
+            id: None,
+            retry: None,
+            data: data.into_iter().collect(),
+        }
+    }
+
+    /// Creates an empty comment, which keeps idle connections from being closed by proxies.
+    ///
+    /// See [`heartbeat`](crate::heartbeat) to send these automatically whenever a stream is idle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::DatastarEvent;
+    ///
+    /// assert_eq!(DatastarEvent::keep_alive().to_string(), ":\n\n");
+    /// ```
+    pub fn keep_alive() -> Self {
+        Self {
+            comments: vec![String::new()],
+            ..Self::empty()
+        }
+    }
+
+    /// An event without anything, which writes nothing but its blank line.
+    fn empty() -> Self {
+        Self {
+            event: None,
+            custom_event: None,
+            comments: Vec::new(),
+            fields: Vec::new(),
+            id: None,
+            retry: None,
+            data: DataLines::new(),
+        }
+    }
+
+    /// Parses the SSE text of a single [`DatastarEvent`], as written by its [`Display`] implementation.
+    ///
+    /// Lines may end with `\n`, `\r\n` or `\r`, and the blank line ending the event is optional.
+    /// Unknown fields are kept in [`fields`](DatastarEvent::fields), and unknown event types
+    /// in [`custom_event`](DatastarEvent::custom_event).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{DatastarEvent, prelude::PatchSignals};
+    ///
+    /// let event = DatastarEvent::from(PatchSignals::new("{count: 1}").id("7"));
+    ///
+    /// assert_eq!(DatastarEvent::from_sse_str(&event.to_string())?, event);
+    /// # Ok::<(), datastar_core::parse::ParseError>(())
+    /// ```
+    pub fn from_sse_str(text: &str) -> Result<Self, parse::ParseError> {
+        parse::event(text)
+    }
+
+    /// Returns the name of the event type written in the `event` field, which is the
+    /// [`custom_event`](DatastarEvent::custom_event) if set, or the name of the [`event`](DatastarEvent::event).
+    pub fn event_name(&self) -> Option<&str> {
+        self.custom_event
+            .as_deref()
+            .or_else(|| self.event.map(|event| event.as_static_str()))
+    }
+
+    /// Returns the name of the non-standard event type of a [`DatastarEvent::custom`] event.
+    pub fn custom_event(&self) -> Option<&str> {
+        self.custom_event.as_deref()
+    }
+
+    /// Returns the SSE comments, which are ignored by the browser but can be read by
+    /// intermediaries, such as a trace id.
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// Returns the custom SSE fields, as names and values, which are ignored by the browser.
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    /// Sets the `id` of the [`DatastarEvent`].
+    pub fn id(mut self, id: impl Into<Cow<'static, str>>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets the `retry` of the [`DatastarEvent`].
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Adds an SSE comment to the [`DatastarEvent`].
+    ///
+    /// Fails with [`Error::InvalidField`] if `comment` contains a newline or carriage return.
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Result<Self, Error> {
+        let comment = comment.into();
+        if has_newline(&comment) {
+            return Err(FieldError::Newline.into());
+        }
+        self.comments.push(comment);
+        Ok(self)
+    }
+
+    /// Adds a custom SSE field to the [`DatastarEvent`].
+    ///
+    /// Fails with [`Error::InvalidField`] if `value` contains a newline or carriage return, or if `name` is empty,
+    /// contains a colon or newline, or is one of the standard `event`, `id`, `retry` and `data` fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{DatastarEvent, prelude::PatchSignals};
+    ///
+    /// let event = DatastarEvent::from(PatchSignals::new("{count: 1}"))
+    ///     .with_comment("trace-id 4bf92f35")?
+    ///     .with_field("x-tenant", "acme")?;
+    ///
+    /// assert_eq!(
+    ///     event.to_string(),
+    ///     ": trace-id 4bf92f35\nevent: datastar-patch-signals\nx-tenant: acme\ndata: signals {count: 1}\n\n",
+    /// );
+    /// assert!(DatastarEvent::keep_alive().with_field("data", "injected").is_err());
+    /// # Ok::<(), datastar_core::Error>(())
+    /// ```
+    pub fn with_field(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let (name, value) = (name.into(), value.into());
+        if name.is_empty()
+            || name.contains(':')
+            || has_newline(&name)
+            || matches!(name.as_str(), "event" | "id" | "retry" | "data")
+        {
+            return Err(FieldError::InvalidName(name).into());
+        }
+        if has_newline(&value) {
+            return Err(FieldError::Newline.into());
+        }
+        self.fields.push((name, value));
+        Ok(self)
+    }
+
+    /// Writes the SSE framing of the [`DatastarEvent`] into `out`, which is also what its
+    /// [`Display`] implementation writes.
+    pub fn write_fmt_to(&self, out: &mut impl std::fmt::Write) -> std::fmt::Result {
+        let mut retry = itoa::Buffer::new();
+        self.try_for_each_piece(self.retry_value(&mut retry), |piece| out.write_str(piece))
+    }
+
+    /// Writes the SSE framing of the [`DatastarEvent`] into `out`, without formatting it
+    /// into a [`String`] first.
+    ///
+    /// The event is written in many small pieces, so `out` should be buffered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::{DatastarEvent, PatchSignals};
+    ///
+    /// let mut out = Vec::new();
+    /// DatastarEvent::from(PatchSignals::new("{count: 1}")).write_to(&mut out)?;
+    ///
+    /// assert_eq!(out, b"event: datastar-patch-signals\ndata: signals {count: 1}\n\n");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn write_to(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        let mut retry = itoa::Buffer::new();
+        self.try_for_each_piece(self.retry_value(&mut retry), |piece| {
+            out.write_all(piece.as_bytes())
+        })
+    }
+
+    /// Writes the SSE framing of the [`DatastarEvent`] into the tokio [`AsyncWrite`](tokio::io::AsyncWrite)
+    /// `out` and flushes it, without formatting it into a [`String`] first.
+    ///
+    /// The event is written in many small pieces, so `out` should be buffered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::{DatastarEvent, PatchSignals};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> std::io::Result<()> {
+    /// let mut out = Vec::new();
+    /// DatastarEvent::from(PatchSignals::new("{count: 1}"))
+    ///     .write_async(&mut out)
+    ///     .await?;
+    ///
+    /// assert_eq!(out, b"event: datastar-patch-signals\ndata: signals {count: 1}\n\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn write_async(
+        &self,
+        out: &mut (impl tokio::io::AsyncWrite + Unpin),
+    ) -> std::io::Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let mut retry = itoa::Buffer::new();
+        for piece in self.framing(self.retry_value(&mut retry)) {
+            out.write_all(piece.as_bytes()).await?;
+        }
+        out.flush().await
+    }
+
+    /// Writes the SSE framing of the [`DatastarEvent`] into the `futures-io`
+    /// [`AsyncWrite`](futures_util::io::AsyncWrite) `out` and flushes it,
+    /// like `write_async` does for tokio.
+    #[cfg(feature = "futures-io")]
+    pub async fn write_futures_io(
+        &self,
+        out: &mut (impl futures_util::io::AsyncWrite + Unpin),
+    ) -> std::io::Result<()> {
+        use futures_util::io::AsyncWriteExt;
+
+        let mut retry = itoa::Buffer::new();
+        for piece in self.framing(self.retry_value(&mut retry)) {
+            out.write_all(piece.as_bytes()).await?;
+        }
+        out.flush().await
+    }
+
+    /// Returns the SSE framing of the [`DatastarEvent`] as [`Bytes`](bytes::Bytes),
+    /// ready to be written to a response body.
+    #[cfg(feature = "bytes")]
+    pub fn to_bytes(&self) -> bytes::Bytes {
+        if let Some(bytes) = self.static_bytes() {
+            return bytes;
+        }
+
+        let mut retry = itoa::Buffer::new();
+        let retry = self.retry_value(&mut retry);
+        let mut out = Vec::with_capacity(self.framed_len());
+        let _ = self.try_for_each_piece(retry, |piece| {
+            out.extend_from_slice(piece.as_bytes());
+            Ok::<_, core::convert::Infallible>(())
+        });
+        out.into()
+    }
+
+    /// Returns the SSE framing of a [leaked](Self::leak) event or a keep-alive, which is
+    /// static.
+    #[cfg(feature = "bytes")]
+    pub(crate) fn static_bytes(&self) -> Option<bytes::Bytes> {
+        if let Some((frame, _)) = self.leaked_frame() {
+            return Some(bytes::Bytes::from_static(frame.as_bytes()));
+        }
+        (self.data.is_empty() && self.static_head() == Some(":\n"))
+            .then(|| bytes::Bytes::from_static(b":\n\n"))
+    }
+
+    /// Returns the SSE framing of the [`DatastarEvent`] as two [`Bytes`](bytes::Bytes) chunks,
+    /// the fields before the data lines and the data lines with the blank line ending the
+    /// event, without copying the data lines.
+    ///
+    /// The first chunk of an event with only its type, or of a [`keep_alive`](Self::keep_alive),
+    /// is static, so framing the common events doesn't allocate it, and both chunks of a
+    /// [leaked](Self::leak) event are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::{DatastarEvent, PatchSignals};
+    ///
+    /// let [fields, data] = DatastarEvent::from(PatchSignals::new("{count: 1}")).into_bytes_chunks();
+    ///
+    /// assert_eq!(fields, "event: datastar-patch-signals\n");
+    /// assert_eq!(data, "data: signals {count: 1}\n\n");
+    /// ```
+    #[cfg(feature = "bytes")]
+    pub fn into_bytes_chunks(mut self) -> [bytes::Bytes; 2] {
+        use bytes::Bytes;
+
+        let fields = match (self.leaked_frame(), self.static_head()) {
+            (Some((frame, start)), _) => Bytes::from_static(&frame.as_bytes()[..start]),
+            (None, Some(head)) => Bytes::from_static(head.as_bytes()),
+            (None, None) => {
+                let mut retry = itoa::Buffer::new();
+                let mut out = Vec::with_capacity(64);
+                let _ = self.try_for_each_head_piece(self.retry_value(&mut retry), |piece| {
+                    out.extend_from_slice(piece.as_bytes());
+                    Ok::<_, core::convert::Infallible>(())
+                });
+                out.into()
+            }
+        };
+
+        let data = match self.data.leaked_frame() {
+            Some((frame, start)) => Bytes::from_static(&frame.as_bytes()[start..]),
+            None if self.data.is_empty() => Bytes::from_static(b"\n"),
+            None => core::mem::take(&mut self.data).into_event_tail().into(),
+        };
+
+        [fields, data]
+    }
+
+    /// Serializes the [`DatastarEvent`] once and leaks its framing, so cloning it doesn't copy
+    /// its data lines, and [`to_bytes`](Self::to_bytes) and
+    /// [`into_bytes_chunks`](Self::into_bytes_chunks) share the leaked framing rather than
+    /// writing it again.
+    ///
+    /// It's meant for the events which are sent again and again for as long as the program
+    /// runs, such as a loading indicator, leaked once in a [`LazyLock`](std::sync::LazyLock):
+    /// leaking an event in a handler leaks memory on every request. A leaked event can still
+    /// be changed, which copies its data lines or writes its other fields again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::{DatastarEvent, PatchSignals};
+    /// use std::sync::LazyLock;
+    ///
+    /// static GENERATING: LazyLock<DatastarEvent> = LazyLock::new(|| {
+    ///     DatastarEvent::from(PatchSignals::new_static(r#"{"generating": true}"#)).leak()
+    /// });
+    ///
+    /// let event = GENERATING.clone();
+    ///
+    /// assert_eq!(
+    ///     event.to_string(),
+    ///     "event: datastar-patch-signals\ndata: signals {\"generating\": true}\n\n",
+    /// );
+    /// ```
+    pub fn leak(mut self) -> Self {
+        let (data, ending) = self.data.framed();
+        let tail = data.len() + ending.len() + 1;
+        let frame: &'static str = self.to_string().leak();
+        self.data = DataLines::leaked(frame, frame.len() - tail);
+        self
+    }
+
+    /// Returns the leaked framing of the [`DatastarEvent`] and where its data lines start, if
+    /// it was [leaked](Self::leak) and its fields weren't changed since.
+    #[cfg(feature = "bytes")]
+    fn leaked_frame(&self) -> Option<(&'static str, usize)> {
+        let (frame, start) = self.data.leaked_frame()?;
+        let mut retry = itoa::Buffer::new();
+        let mut head = &frame[..start];
+        self.try_for_each_head_piece(self.retry_value(&mut retry), |piece| {
+            head = head.strip_prefix(piece).ok_or(())?;
+            Ok::<_, ()>(())
+        })
+        .ok()?;
+        head.is_empty().then_some((frame, start))
+    }
+
+    /// Returns the fields before the data lines if they are always the same, as for an event
+    /// with only its type or a keep-alive.
+    #[cfg(feature = "bytes")]
+    fn static_head(&self) -> Option<&'static str> {
+        if self.custom_event.is_some()
+            || self.id.is_some()
+            || self.retry.is_some()
+            || !self.fields.is_empty()
+        {
+            return None;
+        }
+
+        match (self.comments.as_slice(), self.event) {
+            ([], Some(consts::EventType::PatchElements)) => {
+                Some("event: datastar-patch-elements\n")
+            }
+            ([], Some(consts::EventType::PatchSignals)) => Some("event: datastar-patch-signals\n"),
+            ([comment], None) if comment.is_empty() => Some(":\n"),
+            ([], None) => Some(""),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the `retry` field formatted into `buffer`, or an empty string if
+    /// it is omitted.
+    fn retry_value<'b>(&self, buffer: &'b mut itoa::Buffer) -> &'b str {
+        match self.retry {
+            Some(retry) => buffer.format(retry.as_millis()),
+            None => "",
+        }
+    }
+
+    /// Returns the length of the SSE framing of the [`DatastarEvent`], in bytes.
+    #[cfg(feature = "bytes")]
+    pub(crate) fn framed_len(&self) -> usize {
+        let mut retry = itoa::Buffer::new();
+        let mut len = 0;
+        let _ = self.try_for_each_piece(self.retry_value(&mut retry), |piece| {
+            len += piece.len();
+            Ok::<_, core::convert::Infallible>(())
+        });
+        len
+    }
+
+    /// Returns the pieces of the SSE framing, which the asynchronous writers write in order.
+    #[cfg(any(feature = "tokio", feature = "futures-io"))]
+    fn framing<'a>(&'a self, retry: &'a str) -> Vec<&'a str> {
+        let lines = self.comments.len() + self.fields.len() + 3;
+        let mut pieces = Vec::with_capacity(lines * 4 + 3);
+        let _ = self.try_for_each_piece(retry, |piece| {
+            pieces.push(piece);
+            Ok::<_, core::convert::Infallible>(())
+        });
+        pieces
+    }
+
+    /// Returns the SSE framing of the [`DatastarEvent`] without the blank line ending it, so
+    /// more data lines can be written after it.
+    pub(crate) fn open_framing(&self) -> String {
+        let mut retry = itoa::Buffer::new();
+        let mut out = String::with_capacity(self.data.framed().0.len() + 64);
+        let _ = self.try_for_each_head_piece(self.retry_value(&mut retry), |piece| {
+            out.push_str(piece);
+            Ok::<_, core::convert::Infallible>(())
+        });
+        let (data, ending) = self.data.framed();
+        out.push_str(data);
+        out.push_str(ending);
+        out
+    }
+
+    /// Calls `f` with the pieces of the SSE framing in order, without collecting them first.
+    fn try_for_each_piece<'a, E>(
+        &'a self,
+        retry: &'a str,
+        mut f: impl FnMut(&'a str) -> Result<(), E>,
+    ) -> Result<(), E> {
+        self.try_for_each_head_piece(retry, &mut f)?;
+
+        let (data, ending) = self.data.framed();
+        for piece in [data, ending, "\n"] {
+            f(piece)?;
+        }
+        Ok(())
+    }
+
+    /// Calls `f` with the pieces of the fields before the data lines in order.
+    fn try_for_each_head_piece<'a, E>(
+        &'a self,
+        retry: &'a str,
+        mut f: impl FnMut(&'a str) -> Result<(), E>,
+    ) -> Result<(), E> {
+        for comment in &self.comments {
+            if comment.is_empty() {
+                f(":\n")?;
+            } else {
+                for piece in [": ", comment, "\n"] {
+                    f(piece)?;
+                }
+            }
+        }
+
+        if let Some(event) = self.event_name() {
+            for piece in ["event: ", event, "\n"] {
+                f(piece)?;
+            }
+        }
+
+        if let Some(id) = &self.id {
+            for piece in ["id: ", id, "\n"] {
+                f(piece)?;
+            }
+        }
+
+        if !retry.is_empty() {
+            for piece in ["retry: ", retry, "\n"] {
+                f(piece)?;
+            }
+        }
+
+        for (name, value) in &self.fields {
+            for piece in [name.as_str(), ": ", value, "\n"] {
+                f(piece)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn has_newline(value: &str) -> bool {
+    value.contains(['\n', '\r'])
+}
+
+/// Error returned when a comment or custom field of a [`DatastarEvent`] would break the SSE framing,
+/// or can't be held by the SSE event type of a framework.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FieldError {
+    /// The comment or field value contains a newline or carriage return.
+    Newline,
+    /// The field name is empty, contains a colon or newline, or is a standard SSE field.
+    InvalidName(String),
+    /// The SSE event type of `framework` can't hold a `part` of the event, such as its custom
+    /// fields, so it would be sent truncated.
+    Unsupported {
+        /// The framework, such as `Warp`.
+        framework: &'static str,
+        /// The part of the event, such as `custom fields`.
+        part: &'static str,
+    },
+}
+
+impl Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Newline => f.write_str("SSE comments and field values must not contain newlines"),
+            Self::InvalidName(name) => write!(f, "invalid SSE field name: {name:?}"),
+            Self::Unsupported { framework, part } => {
+                write!(f, "{framework} SSE events can't hold {part}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+impl Display for DatastarEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_fmt_to(f)
+    }
+}
+
+/// [`IntoDatastarEvent`] is implemented by everything Datastar can send as a single event,
+/// so libraries can accept any of them with one bound rather than `Into<DatastarEvent>`.
+///
+/// The events themselves can also be returned from a handler, through
+/// [`IntoDatastarResponse`](response::IntoDatastarResponse).
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     DatastarEvent, IntoDatastarEvent,
+///     consts::EventType,
+///     prelude::{ExecuteScript, PatchSignals},
+/// };
+///
+/// fn is_patch(event: &impl IntoDatastarEvent) -> bool {
+///     event.event_type().is_some()
+/// }
+///
+/// assert_eq!(PatchSignals::new("{count: 1}").event_type(), Some(EventType::PatchSignals));
+/// assert_eq!(ExecuteScript::new("alert('hi')").event_type(), Some(EventType::PatchElements));
+/// assert!(!is_patch(&DatastarEvent::keep_alive()));
+/// ```
+pub trait IntoDatastarEvent: Into<DatastarEvent> {
+    /// Returns the type of the event, or `None` for comments and custom events.
+    fn event_type(&self) -> Option<consts::EventType>;
+
+    /// Converts this value into a [`DatastarEvent`].
+    fn into_datastar_event(self) -> DatastarEvent {
+        self.into()
+    }
+}
+
+impl IntoDatastarEvent for DatastarEvent {
+    fn event_type(&self) -> Option<consts::EventType> {
+        self.event
+    }
+}
@@ -0,0 +1,337 @@
+//! Conversion of events serialized under older versions of the Datastar specification.
+//!
+//! Applications which persist events in journals, queues or databases can use [`migrate`]
+//! and [`migrate_sse`] to convert them into the current representation after upgrading.
+//!
+//! The following pre-1.0 events are supported:
+//!
+//! | Old event                   | Converted to                             |
+//! |-----------------------------|------------------------------------------|
+//! | `datastar-merge-fragments`  | `datastar-patch-elements`                |
+//! | `datastar-remove-fragments` | `datastar-patch-elements` in remove mode |
+//! | `datastar-merge-signals`    | `datastar-patch-signals`                 |
+//! | `datastar-remove-signals`   | `datastar-patch-signals` setting `null`  |
+//! | `datastar-execute-script`   | `datastar-patch-elements` with a script  |
+//!
+//! Events of the current specification are passed through unchanged. The `settleDuration`
+//! dataline no longer exists and is dropped.
+
+use {
+    crate::{
+        DatastarEvent,
+        consts::{self, ElementPatchMode, EventType},
+        execute_script::ExecuteScript,
+        patch_elements::PatchElements,
+        patch_signals::PatchSignals,
+    },
+    core::{
+        fmt::{self, Display, Write},
+        time::Duration,
+    },
+    std::collections::BTreeMap,
+};
+
+/// [`MigrationError`] is returned when an event cannot be converted to the current specification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MigrationError {
+    /// The event type is not known to any version of the specification.
+    UnknownEvent(String),
+    /// The merge mode has no equivalent in the current specification.
+    UnsupportedMergeMode(String),
+    /// A `datastar-remove-fragments` event without a selector.
+    MissingSelector,
+    /// The `retry` field is not a number of milliseconds.
+    InvalidRetry(String),
+}
+
+impl Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownEvent(event) => write!(f, "unknown event type `{event}`"),
+            Self::UnsupportedMergeMode(mode) => {
+                write!(
+                    f,
+                    "merge mode `{mode}` has no equivalent element patch mode"
+                )
+            }
+            Self::MissingSelector => f.write_str("remove fragments event without a selector"),
+            Self::InvalidRetry(retry) => write!(f, "invalid retry duration `{retry}`"),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// Converts an event with the given type and datalines to the current specification.
+///
+/// The `id` and `retry` of the returned event are left unset.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::migrations::migrate;
+///
+/// let event = migrate(
+///     "datastar-merge-fragments",
+///     ["selector #feed", "mergeMode append", "fragments <li>New</li>"],
+/// )
+/// .unwrap();
+///
+/// assert_eq!(
+///     event.to_string(),
+///     "event: datastar-patch-elements\ndata: selector #feed\ndata: mode append\ndata: elements <li>New</li>\n\n",
+/// );
+/// ```
+pub fn migrate<I>(event: &str, data: I) -> Result<DatastarEvent, MigrationError>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let datalines = data.into_iter().map(|line| {
+        let line = line.as_ref();
+        match line.split_once(' ') {
+            Some((key, value)) => (key.to_owned(), value.to_owned()),
+            None => (line.to_owned(), String::new()),
+        }
+    });
+
+    match event {
+        "datastar-patch-elements" => Ok(DatastarEvent::new(
+            EventType::PatchElements,
+            datalines.map(join_dataline),
+        )),
+        "datastar-patch-signals" => Ok(DatastarEvent::new(
+            EventType::PatchSignals,
+            datalines.map(join_dataline),
+        )),
+        "datastar-merge-fragments" => migrate_merge_fragments(datalines),
+        "datastar-remove-fragments" => migrate_remove_fragments(datalines),
+        "datastar-merge-signals" => Ok(migrate_merge_signals(datalines)),
+        "datastar-remove-signals" => Ok(migrate_remove_signals(datalines)),
+        "datastar-execute-script" => Ok(migrate_execute_script(datalines)),
+        _ => Err(MigrationError::UnknownEvent(event.to_owned())),
+    }
+}
+
+/// Converts all events in the given SSE text to the current specification.
+///
+/// The `id` and `retry` fields, comments and custom fields of the events are preserved.
+/// Blocks without an event or data, such as keep-alive comments, become events without a type.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::migrations::migrate_sse;
+///
+/// let events = migrate_sse(
+///     "event: datastar-remove-signals\nid: 42\ndata: paths user.name\n\n",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(
+///     events[0].to_string(),
+///     "event: datastar-patch-signals\nid: 42\ndata: signals {\"user\":{\"name\":null}}\n\n",
+/// );
+/// ```
+pub fn migrate_sse(input: &str) -> Result<Vec<DatastarEvent>, MigrationError> {
+    let input = input.replace("\r\n", "\n");
+    let mut events = Vec::new();
+
+    for block in input.split("\n\n") {
+        let mut event = None;
+        let mut id = None;
+        let mut retry = None;
+        let mut data = Vec::new();
+        let mut comments = Vec::new();
+        let mut fields = Vec::new();
+
+        for line in block.lines() {
+            if line.is_empty() {
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "" => comments.push(value.to_owned()),
+                "event" => event = Some(value),
+                "id" => id = Some(value),
+                "retry" => retry = Some(value),
+                "data" => data.push(value),
+                _ => fields.push((field.to_owned(), value.to_owned())),
+            }
+        }
+
+        let mut migrated = if event.is_none() && data.is_empty() {
+            if comments.is_empty() && fields.is_empty() {
+                continue;
+            }
+            DatastarEvent::keep_alive()
+        } else {
+            migrate(event.unwrap_or("message"), data)?
+        };
+        migrated.comments = comments;
+        migrated.fields = fields;
+        migrated.id = id.map(|id| id.to_owned().into());
+        if let Some(retry) = retry {
+            let millis = retry
+                .parse()
+                .map_err(|_| MigrationError::InvalidRetry(retry.to_owned()))?;
+            migrated.retry = Some(Duration::from_millis(millis));
+        }
+        events.push(migrated);
+    }
+
+    Ok(events)
+}
+
+fn join_dataline((key, value): (String, String)) -> String {
+    if value.is_empty() {
+        key
+    } else {
+        format!("{key} {value}")
+    }
+}
+
+fn migrate_merge_fragments(
+    datalines: impl Iterator<Item = (String, String)>,
+) -> Result<DatastarEvent, MigrationError> {
+    let mut fragments = Vec::new();
+    let mut selector = None;
+    let mut mode = ElementPatchMode::default();
+    let mut use_view_transition = false;
+
+    for (key, value) in datalines {
+        match key.as_str() {
+            "fragments" => fragments.push(value),
+            "selector" => selector = Some(value.into()),
+            "mergeMode" => mode = migrate_merge_mode(&value)?,
+            "useViewTransition" => use_view_transition = value == "true",
+            _ => {}
+        }
+    }
+
+    let mut patch_elements = PatchElements::new(fragments.join("\n"))
+        .mode(mode)
+        .use_view_transition(use_view_transition);
+    patch_elements.selector = selector;
+    Ok(patch_elements.into_datastar_event())
+}
+
+fn migrate_merge_mode(mode: &str) -> Result<ElementPatchMode, MigrationError> {
+    match mode {
+        "morph" => Ok(ElementPatchMode::Outer),
+        "outer" => Ok(ElementPatchMode::Replace),
+        "inner" => Ok(ElementPatchMode::Inner),
+        "prepend" => Ok(ElementPatchMode::Prepend),
+        "append" => Ok(ElementPatchMode::Append),
+        "before" => Ok(ElementPatchMode::Before),
+        "after" => Ok(ElementPatchMode::After),
+        _ => Err(MigrationError::UnsupportedMergeMode(mode.to_owned())),
+    }
+}
+
+fn migrate_remove_fragments(
+    mut datalines: impl Iterator<Item = (String, String)>,
+) -> Result<DatastarEvent, MigrationError> {
+    let (_, selector) = datalines
+        .find(|(key, _)| key == "selector")
+        .ok_or(MigrationError::MissingSelector)?;
+    Ok(PatchElements::new_remove(selector).into_datastar_event())
+}
+
+fn migrate_merge_signals(datalines: impl Iterator<Item = (String, String)>) -> DatastarEvent {
+    let mut signals = Vec::new();
+    let mut only_if_missing = consts::DEFAULT_PATCH_SIGNALS_ONLY_IF_MISSING;
+
+    for (key, value) in datalines {
+        match key.as_str() {
+            "signals" => signals.push(value),
+            "onlyIfMissing" => only_if_missing = value == "true",
+            _ => {}
+        }
+    }
+
+    PatchSignals::new(signals.join("\n"))
+        .only_if_missing(only_if_missing)
+        .into_datastar_event()
+}
+
+#[derive(Default)]
+struct SignalPaths(BTreeMap<String, SignalPaths>);
+
+impl SignalPaths {
+    fn insert(&mut self, path: &str) {
+        let mut node = self;
+        for segment in path.split('.') {
+            node = node.0.entry(segment.to_owned()).or_default();
+        }
+    }
+
+    fn write_json(&self, out: &mut String) {
+        if self.0.is_empty() {
+            out.push_str("null");
+            return;
+        }
+        out.push('{');
+        for (i, (key, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push('"');
+            for c in key.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    c if c.is_control() => {
+                        // Assumption: std::fmt::write does not fail ever for [`String`].
+                        let _ = write!(out, "\\u{:04x}", c as u32);
+                    }
+                    c => out.push(c),
+                }
+            }
+            out.push_str("\":");
+            value.write_json(out);
+        }
+        out.push('}');
+    }
+}
+
+fn migrate_remove_signals(datalines: impl Iterator<Item = (String, String)>) -> DatastarEvent {
+    let mut paths = SignalPaths::default();
+    for (key, value) in datalines {
+        if key == "paths" {
+            value.split_whitespace().for_each(|path| paths.insert(path));
+        }
+    }
+
+    let mut signals = String::new();
+    if paths.0.is_empty() {
+        signals.push_str("{}");
+    } else {
+        paths.write_json(&mut signals);
+    }
+    PatchSignals::new(signals).into_datastar_event()
+}
+
+fn migrate_execute_script(datalines: impl Iterator<Item = (String, String)>) -> DatastarEvent {
+    let mut script = Vec::new();
+    let mut auto_remove = None;
+    let mut attributes = Vec::new();
+
+    for (key, value) in datalines {
+        match key.as_str() {
+            "script" => script.push(value),
+            "autoRemove" => auto_remove = Some(value == "true"),
+            "attributes" => attributes.push(match value.split_once(' ') {
+                Some((name, value)) => format!("{name}=\"{value}\""),
+                None => value,
+            }),
+            _ => {}
+        }
+    }
+
+    let mut execute_script = ExecuteScript::new(script.join("\n")).attributes(attributes);
+    execute_script.auto_remove = auto_remove;
+    execute_script.into_datastar_event()
+}
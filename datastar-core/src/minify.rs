@@ -0,0 +1,122 @@
+//! Minification of the elements of [`PatchElements`](crate::patch_elements::PatchElements),
+//! with the `minify` feature.
+//!
+//! Template engines emit indented HTML, whose indentation is sent again with every event,
+//! and split into a data line for every line of the elements. [`PatchElements::minify`]
+//! passes the elements of an event through [`minify`], so the events of a template engine
+//! can opt in while the others are sent as they are.
+//!
+//! [`PatchElements::minify`]: crate::patch_elements::PatchElements::minify
+
+use std::borrow::Cow;
+
+/// Elements whose content is kept as is, as its whitespace is significant or isn't HTML.
+const PRESERVED_ELEMENTS: &[&str] = &["pre", "script", "style", "textarea"];
+
+/// Removes the comments and the whitespace between tags from `html`.
+///
+/// Whitespace between two tags which contains a line break is indentation and is removed,
+/// while whitespace on a single line is collapsed into one space, as it separates inline
+/// elements. Text and the content of `pre`, `script`, `style` and `textarea` elements is
+/// kept as is.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::minify::minify;
+///
+/// let html = "<ul id=\"feed\">\n  <!-- entries -->\n  <li><b>New</b> <i>entry</i></li>\n</ul>\n";
+///
+/// assert_eq!(minify(html), r#"<ul id="feed"><li><b>New</b> <i>entry</i></li></ul>"#);
+/// ```
+pub fn minify(html: &str) -> Cow<'_, str> {
+    let mut minified = String::with_capacity(html.len());
+    let mut at = 0;
+    while at < html.len() {
+        let rest = &html[at..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            match comment.find("-->") {
+                Some(end) => at += 4 + end + 3,
+                // An unterminated comment is kept, rather than dropping the rest of the elements.
+                None => {
+                    minified.push_str(rest);
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if rest.starts_with('<') {
+            let end = tag_end(rest);
+            let name = tag_name(&rest[..end]);
+            let end = match PRESERVED_ELEMENTS
+                .iter()
+                .find(|preserved| preserved.eq_ignore_ascii_case(name))
+            {
+                Some(preserved) => content_end(rest, end, preserved),
+                None => end,
+            };
+            minified.push_str(&rest[..end]);
+            at += end;
+            continue;
+        }
+
+        let end = rest.find('<').unwrap_or(rest.len());
+        let text = &rest[..end];
+        if !text.trim_ascii().is_empty() {
+            minified.push_str(text);
+        } else if !text.contains('\n') && at > 0 && end < rest.len() {
+            minified.push(' ');
+        }
+        at += end;
+    }
+
+    if minified == html {
+        Cow::Borrowed(html)
+    } else {
+        Cow::Owned(minified)
+    }
+}
+
+/// Returns where the tag at the start of `html` ends, after its `>`, skipping quoted
+/// attribute values.
+fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (index, byte) in html.bytes().enumerate().skip(1) {
+        match (quote, byte) {
+            (Some(open), _) if byte == open => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(byte),
+            (None, b'>') => return index + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// Returns the name of the start tag `tag`, which is empty for end tags and declarations.
+fn tag_name(tag: &str) -> &str {
+    let name = &tag[1..];
+    let end = name
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(name.len());
+    &name[..end]
+}
+
+/// Returns where the end tag of the preserved element `name`, whose content starts at `at`,
+/// ends, or the end of `html` if it has none.
+fn content_end(html: &str, at: usize, name: &str) -> usize {
+    let mut from = at;
+    while let Some(start) = html[from..].find("</").map(|index| from + index) {
+        let after = start + 2;
+        let is_end_tag = html
+            .get(after..after + name.len())
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(name));
+        if is_end_tag {
+            return start + tag_end(&html[start..]);
+        }
+        from = after;
+    }
+    html.len()
+}
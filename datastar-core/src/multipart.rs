@@ -0,0 +1,124 @@
+//! [`Files`] streams the files of a `multipart/form-data` request, such as a form with a file
+//! input, whose other fields are read as the signals by the `ReadSignalsMultipart` extractors
+//! of the framework integrations.
+//!
+//! The signals are the JSON of the field named `datastar` if there is one, and otherwise the
+//! text fields of the form, as for a URL encoded form. Only the fields before the first file
+//! are read as signals, as the files are streamed rather than buffered.
+
+use {
+    crate::{Error, json},
+    core::fmt,
+    futures_core::Stream,
+    serde::de::DeserializeOwned,
+};
+
+pub use multer::Field;
+
+/// The name of the field whose JSON is read as the signals, like the query parameter of `GET` requests.
+const SIGNALS_FIELD: &str = "datastar";
+
+/// [`Files`] is the stream of the files uploaded with the signals of a multipart request.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{Error, multipart::Files};
+///
+/// async fn save(mut files: Files) -> Result<(), Error> {
+///     while let Some(mut file) = files.next_file().await? {
+///         let name = file.file_name().unwrap_or("upload").to_owned();
+///         let mut size = 0;
+///         while let Some(chunk) = file.chunk().await.map_err(|err| Error::Body(Box::new(err)))? {
+///             size += chunk.len();
+///         }
+///         println!("received {name}, {size} bytes");
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct Files {
+    /// The first file, which was read while looking for the signals.
+    next: Option<Field<'static>>,
+    multipart: multer::Multipart<'static>,
+}
+
+impl Files {
+    /// Returns the next uploaded file, or `None` if there are no more.
+    ///
+    /// The previous file has to be dropped first. Text fields after the first file are skipped.
+    pub async fn next_file(&mut self) -> Result<Option<Field<'static>>, Error> {
+        if let Some(field) = self.next.take() {
+            return Ok(Some(field));
+        }
+        while let Some(field) = self.multipart.next_field().await.map_err(body_error)? {
+            if field.file_name().is_some() {
+                return Ok(Some(field));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl fmt::Debug for Files {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Files").finish_non_exhaustive()
+    }
+}
+
+/// Reads the signals of the multipart `body` with the `Content-Type` header `content_type`,
+/// returning them along with the stream of its files, or [`Error::PayloadTooLarge`] once
+/// more than `limit` bytes were read.
+///
+/// The framework integrations call this from their `ReadSignalsMultipart` extractors,
+/// other frameworks can call it with the body stream of the request.
+pub async fn read_signals<T, S, O, E>(
+    content_type: Option<&str>,
+    body: S,
+    limit: u64,
+) -> Result<(T, Files), Error>
+where
+    T: DeserializeOwned,
+    S: Stream<Item = Result<O, E>> + Send + 'static,
+    O: Into<bytes::Bytes> + 'static,
+    E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+{
+    let content_type = content_type.ok_or(Error::MissingHeader("content-type"))?;
+    let boundary = multer::parse_boundary(content_type)
+        .map_err(|_| Error::UnsupportedContentType(content_type.to_owned()))?;
+    let constraints =
+        multer::Constraints::new().size_limit(multer::SizeLimit::new().whole_stream(limit));
+    let mut multipart = multer::Multipart::with_constraints(body, boundary, constraints);
+
+    let mut json = None;
+    let mut fields = Vec::new();
+    let mut next = None;
+    while let Some(field) = multipart.next_field().await.map_err(body_error)? {
+        if field.file_name().is_some() {
+            next = Some(field);
+            break;
+        }
+        let name = field.name().unwrap_or_default().to_owned();
+        let value = field.text().await.map_err(body_error)?;
+        if name == SIGNALS_FIELD {
+            json = Some(value);
+        } else {
+            fields.push((name, value));
+        }
+    }
+
+    let signals = match json {
+        Some(json) => json::signals_from_slice(json.as_bytes())?,
+        None => json::signals_from_fields(&fields)?,
+    };
+    Ok((signals, Files { next, multipart }))
+}
+
+fn body_error(err: multer::Error) -> Error {
+    match err {
+        multer::Error::StreamSizeExceeded { .. } | multer::Error::FieldSizeExceeded { .. } => {
+            Error::PayloadTooLarge
+        }
+        err => Error::Body(Box::new(err)),
+    }
+}
@@ -0,0 +1,424 @@
+//! Parsing the SSE wire format back into [`DatastarEvent`]s, for tests, proxies and tooling.
+//!
+//! [`DatastarEvent::from_sse_str`] parses the text of a single event, which can then be decoded
+//! into a [`PatchElements`](crate::patch_elements::PatchElements) or a
+//! [`PatchSignals`](crate::patch_signals::PatchSignals) with [`TryFrom`].
+//!
+//! # Examples
+//!
+//! ```
+//! use datastar_core::{DatastarEvent, prelude::{ElementPatchMode, PatchElements}};
+//!
+//! let event = DatastarEvent::from_sse_str(
+//!     "event: datastar-patch-elements\n\
+//!      data: selector #feed\n\
+//!      data: mode append\n\
+//!      data: elements <li>Done</li>\n\n",
+//! )?;
+//! let patch = PatchElements::try_from(event)?;
+//!
+//! assert_eq!(patch.selector.as_deref(), Some("#feed"));
+//! assert_eq!(patch.mode, ElementPatchMode::Append);
+//! assert_eq!(patch.elements.as_deref(), Some("<li>Done</li>"));
+//! # Ok::<(), datastar_core::parse::ParseError>(())
+//! ```
+//!
+//! [`EventStreamDecoder`] parses a whole event stream incrementally, as its bytes arrive.
+//!
+//! [`ElementPatchMode`] and [`EventType`] are parsed from their names with [`FromStr`],
+//! and displayed as them.
+
+use {
+    crate::{
+        DatastarEvent,
+        consts::{ElementPatchMode, EventType},
+        data_lines::DataLines,
+    },
+    core::{
+        fmt::{self, Display},
+        str::FromStr,
+        time::Duration,
+    },
+};
+
+/// [`ParseError`] is returned when SSE text can't be parsed into a [`DatastarEvent`],
+/// or a [`DatastarEvent`] can't be decoded into a typed event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The text does not contain an event.
+    Empty,
+    /// The text contains more than one event.
+    MultipleEvents,
+    /// The name is not an [`EventType`].
+    UnknownEventType(String),
+    /// The name is not an [`ElementPatchMode`].
+    UnknownElementPatchMode(String),
+    /// The `retry` field is not a number of milliseconds.
+    InvalidRetry(String),
+    /// The event is not of the type it is decoded into.
+    UnexpectedEventType {
+        /// The type of the event being decoded into.
+        expected: EventType,
+        /// The type of the event.
+        found: Option<EventType>,
+    },
+    /// A data line is not valid for the type of the event.
+    InvalidDataLine(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("the text does not contain an event"),
+            Self::MultipleEvents => f.write_str("the text contains more than one event"),
+            Self::UnknownEventType(event) => write!(f, "unknown event type: {event:?}"),
+            Self::UnknownElementPatchMode(mode) => {
+                write!(f, "unknown element patch mode: {mode:?}")
+            }
+            Self::InvalidRetry(retry) => write!(f, "invalid retry: {retry:?}"),
+            Self::UnexpectedEventType { expected, found } => match found {
+                Some(found) => write!(f, "expected a {expected} event, found {found}"),
+                None => write!(f, "expected a {expected} event, found none"),
+            },
+            Self::InvalidDataLine(line) => write!(f, "invalid data line: {line:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses an [`ElementPatchMode`] from its lowercase name, as in the `mode` data line.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::consts::ElementPatchMode;
+///
+/// let mode: ElementPatchMode = "append".parse()?;
+///
+/// assert_eq!(mode, ElementPatchMode::Append);
+/// assert_eq!(mode.to_string(), "append");
+/// assert!("Append".parse::<ElementPatchMode>().is_err());
+/// # Ok::<(), datastar_core::parse::ParseError>(())
+/// ```
+impl FromStr for ElementPatchMode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "outer" => Ok(Self::Outer),
+            "inner" => Ok(Self::Inner),
+            "remove" => Ok(Self::Remove),
+            "replace" => Ok(Self::Replace),
+            "prepend" => Ok(Self::Prepend),
+            "append" => Ok(Self::Append),
+            "before" => Ok(Self::Before),
+            "after" => Ok(Self::After),
+            _ => Err(ParseError::UnknownElementPatchMode(s.to_owned())),
+        }
+    }
+}
+
+impl TryFrom<&str> for ElementPatchMode {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for ElementPatchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses an [`EventType`] from its name, as in the `event` field.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::consts::EventType;
+///
+/// let event_type = EventType::try_from("datastar-patch-signals")?;
+///
+/// assert_eq!(event_type, EventType::PatchSignals);
+/// assert_eq!(event_type.to_string(), "datastar-patch-signals");
+/// # Ok::<(), datastar_core::parse::ParseError>(())
+/// ```
+impl FromStr for EventType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "datastar-patch-elements" => Ok(Self::PatchElements),
+            "datastar-patch-signals" => Ok(Self::PatchSignals),
+            _ => Err(ParseError::UnknownEventType(s.to_owned())),
+        }
+    }
+}
+
+impl TryFrom<&str> for EventType {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// [`EventStreamDecoder`] incrementally decodes a `text/event-stream` into [`DatastarEvent`]s,
+/// for integration tests and Rust clients of Datastar backends.
+///
+/// Bytes are fed in chunks of any size, which may split lines and even UTF-8 characters.
+/// Events are yielded once the blank line ending them was received, so an incomplete event
+/// at the end of the stream is discarded, as specified by SSE.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{parse::EventStreamDecoder, prelude::PatchSignals};
+///
+/// let mut decoder = EventStreamDecoder::new();
+/// decoder.feed(b"event: datastar-patch-signals\r\ndata: signals {count:");
+/// assert_eq!(decoder.next_event()?, None);
+///
+/// decoder.feed(b" 1}\r\n\r\n: keep-alive\r\n");
+/// let event = decoder.next_event()?.expect("the event is complete");
+/// assert_eq!(PatchSignals::try_from(event)?.signals, "{count: 1}");
+/// assert_eq!(decoder.next_event()?, None);
+/// # Ok::<(), datastar_core::parse::ParseError>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct EventStreamDecoder {
+    buffer: Vec<u8>,
+    /// The start of the first line which wasn't decoded yet.
+    start: usize,
+    /// Where to continue looking for the end of the line, which isn't in the buffer before.
+    scanned: usize,
+    builder: EventBuilder,
+    /// The last line ended with `\r`, so a `\n` starting the next chunk belongs to it.
+    skip_lf: bool,
+    /// An invalid line was found, so the rest of its event is skipped.
+    skipping: bool,
+    /// The first line was decoded, after which a byte order mark isn't stripped anymore.
+    started: bool,
+}
+
+impl EventStreamDecoder {
+    /// Creates a new [`EventStreamDecoder`] at the start of a stream.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the next chunk of the stream.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        // Drops the decoded lines before growing the buffer.
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.scanned -= self.start;
+            self.start = 0;
+        }
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Returns the next complete event, or `None` once more bytes have to be fed.
+    ///
+    /// After an error, the rest of the invalid event is skipped and decoding can continue.
+    pub fn next_event(&mut self) -> Result<Option<DatastarEvent>, ParseError> {
+        while let Some(line) = self.next_line() {
+            if line.is_empty() {
+                if core::mem::take(&mut self.skipping) {
+                    continue;
+                }
+                if let Some(event) = self.builder.finish() {
+                    return Ok(Some(event));
+                }
+            } else if !self.skipping {
+                if let Err(err) = self.builder.line(&line) {
+                    self.builder.finish();
+                    self.skipping = true;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the next complete line, without its line ending.
+    fn next_line(&mut self) -> Option<String> {
+        if self.skip_lf && self.start < self.buffer.len() {
+            self.skip_lf = false;
+            if self.buffer[self.start] == b'\n' {
+                self.start += 1;
+                self.scanned = self.scanned.max(self.start);
+            }
+        }
+
+        let Some(offset) = self.buffer[self.scanned..]
+            .iter()
+            .position(|&byte| byte == b'\r' || byte == b'\n')
+        else {
+            self.scanned = self.buffer.len();
+            return None;
+        };
+        let end = self.scanned + offset;
+        let line = String::from_utf8_lossy(&self.buffer[self.start..end]).into_owned();
+
+        self.start = end + 1;
+        if self.buffer[end] == b'\r' {
+            match self.buffer.get(self.start) {
+                Some(b'\n') => self.start += 1,
+                Some(_) => {}
+                None => self.skip_lf = true,
+            }
+        }
+        self.scanned = self.start;
+
+        if !core::mem::replace(&mut self.started, true) {
+            if let Some(line) = line.strip_prefix('\u{feff}') {
+                return Some(line.to_owned());
+            }
+        }
+        Some(line)
+    }
+}
+
+/// Parses the text of a single event, see [`DatastarEvent::from_sse_str`].
+pub(crate) fn event(text: &str) -> Result<DatastarEvent, ParseError> {
+    let mut builder = EventBuilder::default();
+    let mut lines = lines(text);
+    for line in lines.by_ref() {
+        if !line.is_empty() {
+            builder.line(line)?;
+        } else if !builder.is_empty() {
+            break;
+        }
+    }
+    if lines.any(|line| !line.is_empty()) {
+        return Err(ParseError::MultipleEvents);
+    }
+    builder.finish().ok_or(ParseError::Empty)
+}
+
+/// Splits `text` into lines ending with `\r\n`, `\n` or `\r`, as specified by SSE.
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    core::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let Some(end) = rest.find(['\r', '\n']) else {
+            return Some(core::mem::take(&mut rest));
+        };
+        let line = &rest[..end];
+        let terminator = if rest[end..].starts_with("\r\n") {
+            2
+        } else {
+            1
+        };
+        rest = &rest[end + terminator..];
+        Some(line)
+    })
+}
+
+/// [`EventBuilder`] collects the non-blank lines of an event.
+#[derive(Debug, Default)]
+pub(crate) struct EventBuilder {
+    event: Option<DatastarEvent>,
+}
+
+impl EventBuilder {
+    /// Returns whether no line was added since the last event.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.event.is_none()
+    }
+
+    /// Adds a non-blank line, without its line ending.
+    pub(crate) fn line(&mut self, line: &str) -> Result<(), ParseError> {
+        let event = self.event.get_or_insert_with(|| DatastarEvent {
+            event: None,
+            custom_event: None,
+            comments: Vec::new(),
+            fields: Vec::new(),
+            id: None,
+            retry: None,
+            data: DataLines::new(),
+        });
+
+        if let Some(comment) = line.strip_prefix(':') {
+            event.comments.push(strip_space(comment).to_owned());
+            return Ok(());
+        }
+
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name, strip_space(value)),
+            None => (line, ""),
+        };
+        match name {
+            "event" => match value.parse() {
+                Ok(event_type) => event.event = Some(event_type),
+                Err(_) => event.custom_event = Some(value.to_owned()),
+            },
+            "data" => event.data.push(value),
+            "id" => event.id = Some(value.to_owned().into()),
+            "retry" => event.retry = Some(retry(value)?),
+            _ => event.fields.push((name.to_owned(), value.to_owned())),
+        }
+        Ok(())
+    }
+
+    /// Returns the event made of the lines added since the last event, if any.
+    pub(crate) fn finish(&mut self) -> Option<DatastarEvent> {
+        self.event.take()
+    }
+}
+
+fn strip_space(value: &str) -> &str {
+    value.strip_prefix(' ').unwrap_or(value)
+}
+
+fn retry(value: &str) -> Result<Duration, ParseError> {
+    value
+        .bytes()
+        .all(|byte| byte.is_ascii_digit())
+        .then(|| value.parse().ok())
+        .flatten()
+        .map(Duration::from_millis)
+        .ok_or_else(|| ParseError::InvalidRetry(value.to_owned()))
+}
+
+/// Checks that `event` is of the `expected` type before decoding it.
+pub(crate) fn expect_type(event: &DatastarEvent, expected: EventType) -> Result<(), ParseError> {
+    match event.event {
+        Some(found) if found == expected => Ok(()),
+        found => Err(ParseError::UnexpectedEventType { expected, found }),
+    }
+}
+
+/// Splits a data line into its literal, such as `selector`, and its value.
+pub(crate) fn data_line(line: &str) -> (&str, &str) {
+    line.split_once(' ').unwrap_or((line, ""))
+}
+
+/// Parses the boolean value of a data line.
+pub(crate) fn data_bool(line: &str, value: &str) -> Result<bool, ParseError> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(ParseError::InvalidDataLine(line.to_owned())),
+    }
+}
+
+/// Parses the [`ElementPatchMode`] value of a data line.
+pub(crate) fn data_mode(line: &str, value: &str) -> Result<ElementPatchMode, ParseError> {
+    value
+        .parse()
+        .map_err(|_| ParseError::InvalidDataLine(line.to_owned()))
+}
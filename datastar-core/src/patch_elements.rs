@@ -0,0 +1,821 @@
+//! [`PatchElements`] patches HTML elements into the DOM.
+
+use {
+    crate::{
+        DatastarEvent, IntoDatastarEvent,
+        consts::{self, ElementPatchMode, EventType},
+        data_lines::{DataLines, LineWriter},
+        execute_script::ExecuteScript,
+        html,
+        parse::{self, ParseError},
+        patch_signals::write_json_string,
+        selector,
+    },
+    core::{
+        fmt::{self, Display},
+        time::Duration,
+    },
+    std::borrow::Cow,
+};
+
+/// [`PatchElements`] patches HTML elements into the DOM.
+///
+/// It is created with [`PatchElements::new`], [`PatchElements::new_remove`] or [`Default`] and
+/// the builder methods, as fields such as [`view_transition`](PatchElements::view_transition)
+/// are added in minor releases, see [Constructing events](crate#constructing-events).
+///
+/// The text fields are [`Cow`]s, so static elements and selectors, such as `"#feed"`,
+/// are borrowed rather than allocated for every event. The elements are set from
+/// [`Elements`], which are trusted HTML, or text escaped with [`Elements::text`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{consts::ElementPatchMode, prelude::PatchElements};
+///
+/// let patch = PatchElements::new("<li>Item</li>")
+///     .selector("#list")
+///     .mode(ElementPatchMode::Append);
+///
+/// assert_eq!(patch.selector.as_deref(), Some("#list"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct PatchElements {
+    /// `id` is can be used by the backend to replay events.
+    /// This is part of the SSE spec and is used to tell the browser how to handle the event.
+    /// For more details see <https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#id>
+    pub id: Option<Cow<'static, str>>,
+    /// `retry` is part of the SSE spec and is used to tell the browser how long to wait before reconnecting if the connection is lost.
+    /// When `None`, no `retry` field is sent and the browser keeps its current reconnection delay.
+    /// For more details see <https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#retry>
+    pub retry: Option<Duration>,
+    /// The HTML elements to patched into the DOM.
+    ///
+    /// In case of [`ElementPatchMode::Remove`] this attribute will be `None`.
+    pub elements: Option<Cow<'static, str>>,
+    /// The CSS selector to use to patch the elements.
+    /// If not provided, Datastar will default to using the id attribute of the elements.
+    pub selector: Option<Cow<'static, str>>,
+    /// The mode to use when patching the element into the DOM.
+    /// If not provided the Datastar client side will default to [`ElementPatchMode::Outer`].
+    pub mode: ElementPatchMode,
+    /// Whether to use view transitions, if not provided the Datastar client side will default to `false`.
+    pub use_view_transition: bool,
+    /// The [`ViewTransition`] to coordinate with the patch, which is applied by a companion
+    /// [`ExecuteScript`] rather than sent with the event.
+    pub view_transition: Option<ViewTransition>,
+}
+
+/// The default [`PatchElements`] has no elements, which have to be set before it is sent.
+impl Default for PatchElements {
+    fn default() -> Self {
+        Self {
+            id: None,
+            retry: None,
+            elements: None,
+            selector: None,
+            mode: ElementPatchMode::default(),
+            use_view_transition: consts::DEFAULT_ELEMENTS_USE_VIEW_TRANSITIONS,
+            view_transition: None,
+        }
+    }
+}
+
+impl PatchElements {
+    /// Creates a new [`PatchElements`] event with the given elements.
+    ///
+    /// Strings are trusted as HTML, so user input has to be escaped with [`Elements::text`],
+    /// or with the [`html`](crate::html) builder or a template engine.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{patch_elements::Elements, prelude::PatchElements};
+    ///
+    /// let name = "<script>alert('hi')</script>";
+    /// let patch = PatchElements::new(Elements::text(name)).selector("#name");
+    ///
+    /// assert_eq!(
+    ///     patch.elements.as_deref(),
+    ///     Some("&lt;script&gt;alert(&#39;hi&#39;)&lt;/script&gt;"),
+    /// );
+    /// ```
+    pub fn new(elements: impl Into<Elements>) -> Self {
+        Self {
+            id: None,
+            retry: None,
+            elements: Some(elements.into().0),
+            selector: None,
+            mode: ElementPatchMode::default(),
+            use_view_transition: consts::DEFAULT_ELEMENTS_USE_VIEW_TRANSITIONS,
+            view_transition: None,
+        }
+    }
+
+    /// Creates a new [`PatchElements`] event with static elements, in a `const` or a `static`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::PatchElements;
+    ///
+    /// const SPINNER: PatchElements =
+    ///     PatchElements::new_static("<div id='status' class='spinner'></div>");
+    ///
+    /// assert_eq!(SPINNER, PatchElements::new("<div id='status' class='spinner'></div>"));
+    /// ```
+    pub const fn new_static(elements: &'static str) -> Self {
+        Self {
+            id: None,
+            retry: None,
+            elements: Some(Cow::Borrowed(elements)),
+            selector: None,
+            mode: ElementPatchMode::Outer,
+            use_view_transition: consts::DEFAULT_ELEMENTS_USE_VIEW_TRANSITIONS,
+            view_transition: None,
+        }
+    }
+
+    /// Creates a new [`PatchElements`] event patching several top-level elements at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{DatastarEvent, prelude::PatchElements};
+    ///
+    /// let patch = PatchElements::new_many(["<div id='a'>A</div>", "<div id='b'>B</div>"]);
+    ///
+    /// assert_eq!(
+    ///     DatastarEvent::from(patch).data,
+    ///     ["elements <div id='a'>A</div>", "elements <div id='b'>B</div>"],
+    /// );
+    /// ```
+    pub fn new_many(elements: impl IntoIterator<Item = impl Into<Elements>>) -> Self {
+        elements.into_iter().fold(Self::new(""), |patch, element| {
+            patch.append_element(element)
+        })
+    }
+
+    /// Creates a new [`PatchElements`] to delete the elements for the given selector.
+    pub fn new_remove(selector: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            id: None,
+            retry: None,
+            elements: None,
+            selector: Some(selector.into()),
+            mode: ElementPatchMode::Remove,
+            use_view_transition: consts::DEFAULT_ELEMENTS_USE_VIEW_TRANSITIONS,
+            view_transition: None,
+        }
+    }
+
+    /// Sets the `id` of the [`PatchElements`] event.
+    pub fn id(mut self, id: impl Into<Cow<'static, str>>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets the `retry` of the [`PatchElements`] event.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Sets the `elements` of the [`PatchElements`] event.
+    pub fn elements(mut self, elements: impl Into<Elements>) -> Self {
+        self.elements = Some(elements.into().0);
+        self
+    }
+
+    /// Appends a top-level element to the `elements` of the [`PatchElements`] event.
+    pub fn append_element(mut self, element: impl Into<Elements>) -> Self {
+        let element = element.into().0;
+        match &mut self.elements {
+            Some(elements) if !elements.is_empty() => {
+                let elements = elements.to_mut();
+                elements.push('\n');
+                elements.push_str(&element);
+            }
+            elements => *elements = Some(element),
+        }
+        self
+    }
+
+    /// Sets the `selector` of the [`PatchElements`] event.
+    pub fn selector(mut self, selector: impl Into<Cow<'static, str>>) -> Self {
+        self.selector = Some(selector.into());
+        self
+    }
+
+    /// Sets the `selector` to the `id` of the first top-level element, if no selector is set.
+    ///
+    /// This is meant for elements with a single top-level element, as every top-level element
+    /// is patched into the target of a selector. With the `tracing` feature, a warning is
+    /// logged when there is neither a selector nor an `id`, as the patch would target nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::PatchElements;
+    ///
+    /// let patch = PatchElements::new(r#"<ul id="feed"><li>Done</li></ul>"#).infer_selector();
+    /// assert_eq!(patch.selector.as_deref(), Some("#feed"));
+    ///
+    /// let patch = PatchElements::new("<li>Done</li>").infer_selector();
+    /// assert_eq!(patch.selector, None);
+    /// ```
+    pub fn infer_selector(mut self) -> Self {
+        if self.selector.is_some() {
+            return self;
+        }
+
+        if let Some(id) = self
+            .elements
+            .as_deref()
+            .and_then(selector::first_element_id)
+        {
+            self.selector = Some(selector::id_selector(id).into());
+            return self;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            mode = self.mode.as_str(),
+            "patching elements without an id or a selector targets nothing"
+        );
+        self
+    }
+
+    /// Sets the `mode` of the [`PatchElements`] event.
+    pub fn mode(mut self, mode: ElementPatchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the `use_view_transition` of the [`PatchElements`] event.
+    pub fn use_view_transition(mut self, use_view_transition: bool) -> Self {
+        self.use_view_transition = use_view_transition;
+        self
+    }
+
+    /// Minifies the elements of the [`PatchElements`] event with [`minify`](crate::minify::minify),
+    /// removing the comments and the indentation between their tags.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{DatastarEvent, prelude::PatchElements};
+    ///
+    /// let patch = PatchElements::new("<ul id='feed'>\n  <li>Deployed</li>\n</ul>").minify();
+    ///
+    /// assert_eq!(
+    ///     DatastarEvent::from(patch).data,
+    ///     ["elements <ul id='feed'><li>Deployed</li></ul>"],
+    /// );
+    /// ```
+    #[cfg(feature = "minify")]
+    pub fn minify(mut self) -> Self {
+        if let Some(elements) = &self.elements {
+            if let Cow::Owned(minified) = crate::minify::minify(elements) {
+                self.elements = Some(minified.into());
+            }
+        }
+        self
+    }
+
+    /// Sets the [`ViewTransition`] of the [`PatchElements`] event, which also enables
+    /// `use_view_transition`.
+    ///
+    /// The classes of the [`ViewTransition`] are toggled by the script of
+    /// [`PatchElements::view_transition_script`], which [`Transaction::patch_elements`],
+    /// [`DatastarSender::patch_elements`], [`Collector::patch_elements`] and the responses of
+    /// the framework integrations send before the patch. Converting the patch into a single
+    /// [`DatastarEvent`] leaves the script out, which is logged as a warning in debug builds
+    /// with the `tracing` feature.
+    ///
+    /// [`Transaction::patch_elements`]: crate::sender::Transaction::patch_elements
+    /// [`DatastarSender::patch_elements`]: crate::sender::DatastarSender::patch_elements
+    /// [`Collector::patch_elements`]: crate::response::Collector::patch_elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{
+    ///     patch_elements::ViewTransition,
+    ///     prelude::PatchElements,
+    ///     sender::Transaction,
+    /// };
+    ///
+    /// let patch = PatchElements::new("<main id='page'>Step 2</main>")
+    ///     .view_transition(ViewTransition::new().class("slide-left"));
+    /// let transaction = Transaction::new().patch_elements(patch);
+    ///
+    /// assert_eq!(transaction.events().len(), 2);
+    /// assert_eq!(
+    ///     transaction.events()[1].data,
+    ///     ["useViewTransition true", "elements <main id='page'>Step 2</main>"],
+    /// );
+    /// ```
+    pub fn view_transition(mut self, view_transition: ViewTransition) -> Self {
+        self.use_view_transition = true;
+        self.view_transition = Some(view_transition);
+        self
+    }
+
+    /// Returns the [`ExecuteScript`] which toggles the classes of the [`ViewTransition`] of
+    /// this [`PatchElements`], to be sent right before it.
+    ///
+    /// Returns `None` without a [`ViewTransition`], or if the patch doesn't use view transitions.
+    pub fn view_transition_script(&self) -> Option<ExecuteScript> {
+        self.view_transition
+            .as_ref()
+            .filter(|_| self.use_view_transition)
+            .and_then(ViewTransition::script)
+    }
+
+    /// Takes the [`ViewTransition`] out of this [`PatchElements`], returning its script, so the
+    /// patch can be converted without warning that the script is left out.
+    pub(crate) fn take_view_transition_script(&mut self) -> Option<ExecuteScript> {
+        let script = self.view_transition_script();
+        self.view_transition = None;
+        script
+    }
+
+    /// Checks that this [`PatchElements`] event can be applied by the Datastar client.
+    ///
+    /// Invalid events are otherwise silently ignored by the client, which is hard to debug.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{
+    ///     Error,
+    ///     patch_elements::{PatchElements, PatchElementsError},
+    /// };
+    ///
+    /// assert!(matches!(
+    ///     PatchElements::new("").selector("#feed").validate(),
+    ///     Err(Error::InvalidElements(PatchElementsError::EmptyElements)),
+    /// ));
+    /// assert!(matches!(
+    ///     PatchElements::new_remove("  ").validate(),
+    ///     Err(Error::InvalidElements(PatchElementsError::BlankSelector)),
+    /// ));
+    /// assert!(PatchElements::new_remove("#feed").validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<(), crate::Error> {
+        validate(
+            self.selector.as_deref(),
+            self.elements.as_deref(),
+            self.mode,
+        )
+        .map_err(Into::into)
+    }
+
+    /// Converts this [`PatchElements`] into a [`DatastarEvent`].
+    #[inline]
+    pub fn into_datastar_event(mut self) -> DatastarEvent {
+        let id = self.id.take();
+        self.convert_to_datastar_event_inner(id)
+    }
+
+    /// Copy this [`PatchElements`] as a [`DatastarEvent`].
+    #[inline]
+    pub fn as_datastar_event(&self) -> DatastarEvent {
+        self.convert_to_datastar_event_inner(self.id.clone())
+    }
+
+    fn convert_to_datastar_event_inner(&self, id: Option<Cow<'static, str>>) -> DatastarEvent {
+        let mut data = DataLines::with_capacity(self.data_capacity());
+        self.write_data_lines(&mut data);
+
+        DatastarEvent {
+            event: Some(consts::EventType::PatchElements),
+            custom_event: None,
+            comments: Vec::new(),
+            fields: Vec::new(),
+            id,
+            retry: self.retry,
+            data,
+        }
+    }
+
+    /// Returns the capacity to reserve for the data lines of this [`PatchElements`].
+    pub(crate) fn data_capacity(&self) -> usize {
+        self.elements.as_deref().map_or(0, str::len) + 64
+    }
+
+    /// Writes the data lines of this [`PatchElements`], which the framework integrations
+    /// write straight into their events.
+    pub(crate) fn write_data_lines(&self, data: &mut impl LineWriter) {
+        #[cfg(all(debug_assertions, feature = "tracing"))]
+        if self.view_transition_script().is_some() {
+            tracing::warn!(
+                "writing a patch as a single event leaves out the script of its view transition"
+            );
+        }
+
+        if let Some(selector) = &self.selector {
+            data.push_literal(consts::SELECTOR_DATALINE_LITERAL, selector);
+        }
+
+        if self.mode != ElementPatchMode::default() {
+            data.push_literal(consts::MODE_DATALINE_LITERAL, self.mode.as_str());
+        }
+
+        if self.use_view_transition != consts::DEFAULT_ELEMENTS_USE_VIEW_TRANSITIONS {
+            data.push_literal(
+                consts::USE_VIEW_TRANSITION_DATALINE_LITERAL,
+                if self.use_view_transition {
+                    "true"
+                } else {
+                    "false"
+                },
+            );
+        }
+
+        let elements = self.elements.as_deref().unwrap_or_default();
+        #[cfg(feature = "ammonia")]
+        let elements = &crate::sanitize::apply_global_policy(elements);
+        if !elements.is_empty() {
+            data.push_literal(consts::ELEMENTS_DATALINE_LITERAL, elements);
+        }
+    }
+}
+
+/// [`ViewTransition`] holds the classes to toggle on the root element of the document around
+/// the view transition of a [`PatchElements`] event, so named transitions can be styled with
+/// selectors such as `.slide-left::view-transition-old(page)`.
+///
+/// The classes are added by a companion [`ExecuteScript`] before the patch starts its view
+/// transition, and removed once its [`duration`](ViewTransition::duration) has elapsed.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{patch_elements::ViewTransition, prelude::PatchElements};
+/// use std::time::Duration;
+///
+/// let patch = PatchElements::new("<main id='page'>Step 2</main>").view_transition(
+///     ViewTransition::new()
+///         .class("slide-left")
+///         .duration(Duration::from_millis(300)),
+/// );
+///
+/// assert_eq!(
+///     patch.view_transition_script().unwrap().script,
+///     r#"{const r=document.documentElement,c=["slide-left"];r.classList.add(...c);setTimeout(()=>r.classList.remove(...c),300)}"#,
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ViewTransition {
+    /// The classes to add to the root element for the duration of the view transition.
+    pub classes: Vec<Cow<'static, str>>,
+    /// How long the classes are kept, which should cover the longest animation of the view
+    /// transition. Defaults to [`DEFAULT_VIEW_TRANSITION_DURATION`].
+    pub duration: Duration,
+}
+
+/// The default [`duration`](ViewTransition::duration) of a [`ViewTransition`].
+pub const DEFAULT_VIEW_TRANSITION_DURATION: Duration = Duration::from_secs(1);
+
+impl Default for ViewTransition {
+    fn default() -> Self {
+        Self {
+            classes: Vec::new(),
+            duration: DEFAULT_VIEW_TRANSITION_DURATION,
+        }
+    }
+}
+
+impl ViewTransition {
+    /// Creates a new [`ViewTransition`] without any class.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a class to the [`ViewTransition`].
+    pub fn class(mut self, class: impl Into<Cow<'static, str>>) -> Self {
+        self.classes.push(class.into());
+        self
+    }
+
+    /// Sets the `duration` of the [`ViewTransition`].
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Returns the script toggling the classes, or `None` if there are none.
+    fn script(&self) -> Option<ExecuteScript> {
+        if self.classes.is_empty() {
+            return None;
+        }
+
+        let mut classes = String::from("[");
+        for (i, class) in self.classes.iter().enumerate() {
+            if i > 0 {
+                classes.push(',');
+            }
+            write_json_string(&mut classes, class);
+        }
+        classes.push(']');
+
+        Some(ExecuteScript::new(format!(
+            "{{const r=document.documentElement,c={classes};r.classList.add(...c);setTimeout(()=>r.classList.remove(...c),{})}}",
+            self.duration.as_millis(),
+        )))
+    }
+}
+
+/// [`Elements`] are the HTML elements of a [`PatchElements`] event.
+///
+/// Strings convert into trusted [`Elements`], which are sent as is, while
+/// [`Elements::text`] escapes its text, so user input can't inject elements or scripts
+/// into the page.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::patch_elements::Elements;
+///
+/// let comment = "<img src=x onerror=alert(1)>";
+///
+/// assert_eq!(
+///     Elements::text(comment).as_str(),
+///     "&lt;img src=x onerror=alert(1)&gt;",
+/// );
+/// assert_eq!(Elements::from("<b>Bold</b>").as_str(), "<b>Bold</b>");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Elements(Cow<'static, str>);
+
+impl Elements {
+    /// Creates [`Elements`] from HTML, which is trusted not to contain user input
+    /// that wasn't escaped.
+    pub fn trusted(html: impl Into<Cow<'static, str>>) -> Self {
+        Self(html.into())
+    }
+
+    /// Creates [`Elements`] from plain text, escaping `&`, `<`, `>`, `"` and `'`.
+    pub fn text(text: impl AsRef<str>) -> Self {
+        let text = text.as_ref();
+        let mut escaped = String::with_capacity(text.len());
+        // Writing into a `String` doesn't fail.
+        let _ = html::escape(&mut escaped, text);
+        Self(Cow::Owned(escaped))
+    }
+
+    /// Returns the HTML of the elements.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the HTML of the elements as a [`Cow`].
+    pub fn into_inner(self) -> Cow<'static, str> {
+        self.0
+    }
+}
+
+impl Display for Elements {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Elements {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&'static str> for Elements {
+    fn from(html: &'static str) -> Self {
+        Self(Cow::Borrowed(html))
+    }
+}
+
+impl From<String> for Elements {
+    fn from(html: String) -> Self {
+        Self(Cow::Owned(html))
+    }
+}
+
+impl From<Cow<'static, str>> for Elements {
+    fn from(html: Cow<'static, str>) -> Self {
+        Self(html)
+    }
+}
+
+impl From<Elements> for Cow<'static, str> {
+    fn from(elements: Elements) -> Self {
+        elements.0
+    }
+}
+
+impl From<Elements> for String {
+    fn from(elements: Elements) -> Self {
+        elements.0.into_owned()
+    }
+}
+
+/// The rendered [`maud::Markup`] is escaped HTML already.
+#[cfg(feature = "maud")]
+impl From<maud::Markup> for Elements {
+    fn from(markup: maud::Markup) -> Self {
+        Self(Cow::Owned(markup.into_string()))
+    }
+}
+
+/// Patches the rendered [`maud::Markup`], which is escaped HTML already.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::prelude::PatchElements;
+/// use maud::html;
+///
+/// let name = "<Ada>";
+/// let patch = PatchElements::from(html! { div #greeting { "Hello, " (name) "!" } });
+///
+/// assert_eq!(
+///     patch.elements.as_deref(),
+///     Some(r#"<div id="greeting">Hello, &lt;Ada&gt;!</div>"#),
+/// );
+/// ```
+#[cfg(feature = "maud")]
+impl From<maud::Markup> for PatchElements {
+    fn from(markup: maud::Markup) -> Self {
+        Self::new(markup)
+    }
+}
+
+/// Patches several rendered [`maud::Markup`] fragments at once, like [`PatchElements::new_many`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::prelude::PatchElements;
+/// use maud::html;
+///
+/// let patch: PatchElements = ["a", "b"]
+///     .into_iter()
+///     .map(|id| html! { div #(id) { (id) } })
+///     .collect();
+///
+/// assert_eq!(
+///     patch.elements.as_deref(),
+///     Some("<div id=\"a\">a</div>\n<div id=\"b\">b</div>"),
+/// );
+/// ```
+#[cfg(feature = "maud")]
+impl FromIterator<maud::Markup> for PatchElements {
+    fn from_iter<I: IntoIterator<Item = maud::Markup>>(markups: I) -> Self {
+        Self::new_many(markups)
+    }
+}
+
+#[cfg(feature = "leptos")]
+impl PatchElements {
+    /// Creates a new [`PatchElements`] event with a Leptos view, rendered to HTML on the server.
+    ///
+    /// The view is rendered synchronously, with
+    /// [`RenderHtml::to_html`](leptos::tachys::view::RenderHtml::to_html), so a `Suspense`
+    /// renders its fallback.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::PatchElements;
+    /// use leptos::prelude::*;
+    ///
+    /// #[component]
+    /// fn Greeting() -> impl IntoView {
+    ///     view! { <div id="greeting">"Hello, Ada!"</div> }
+    /// }
+    ///
+    /// let patch = PatchElements::from_view(view! { <Greeting/> });
+    ///
+    /// assert_eq!(
+    ///     patch.elements.as_deref(),
+    ///     Some(r#"<div id="greeting">Hello, Ada!</div>"#),
+    /// );
+    /// ```
+    pub fn from_view(view: impl leptos::IntoView) -> Self {
+        Self::new(view.to_html())
+    }
+}
+
+impl From<&PatchElements> for DatastarEvent {
+    #[inline]
+    fn from(val: &PatchElements) -> Self {
+        val.as_datastar_event()
+    }
+}
+
+impl From<PatchElements> for DatastarEvent {
+    #[inline]
+    fn from(val: PatchElements) -> Self {
+        val.into_datastar_event()
+    }
+}
+
+impl IntoDatastarEvent for PatchElements {
+    fn event_type(&self) -> Option<EventType> {
+        Some(EventType::PatchElements)
+    }
+}
+
+impl IntoDatastarEvent for &PatchElements {
+    fn event_type(&self) -> Option<EventType> {
+        Some(EventType::PatchElements)
+    }
+}
+
+impl TryFrom<DatastarEvent> for PatchElements {
+    type Error = ParseError;
+
+    /// Decodes a `datastar-patch-elements` event, such as one parsed by [`DatastarEvent::from_sse_str`].
+    fn try_from(event: DatastarEvent) -> Result<Self, Self::Error> {
+        parse::expect_type(&event, EventType::PatchElements)?;
+
+        let mut patch = Self::new("");
+        patch.id = event.id;
+        patch.retry = event.retry;
+        let mut elements = Vec::new();
+        for line in &event.data {
+            match parse::data_line(line) {
+                (consts::SELECTOR_DATALINE_LITERAL, selector) => {
+                    patch.selector = Some(selector.to_owned().into());
+                }
+                (consts::MODE_DATALINE_LITERAL, mode) => patch.mode = parse::data_mode(line, mode)?,
+                (consts::USE_VIEW_TRANSITION_DATALINE_LITERAL, value) => {
+                    patch.use_view_transition = parse::data_bool(line, value)?;
+                }
+                (consts::ELEMENTS_DATALINE_LITERAL, element) => elements.push(element),
+                _ => return Err(ParseError::InvalidDataLine(line.to_owned())),
+            }
+        }
+        // Removals carry no elements, as created by `PatchElements::new_remove`.
+        patch.elements = (!elements.is_empty() || patch.mode != ElementPatchMode::Remove)
+            .then(|| elements.join("\n").into());
+        Ok(patch)
+    }
+}
+
+/// Checks the selector, elements and mode of a patch, for [`PatchElements::validate`] and
+/// [`FragmentResponse::validate`](crate::fragment::FragmentResponse::validate).
+pub(crate) fn validate(
+    selector: Option<&str>,
+    elements: Option<&str>,
+    mode: ElementPatchMode,
+) -> Result<(), PatchElementsError> {
+    if selector.is_some_and(|selector| selector.trim().is_empty()) {
+        return Err(PatchElementsError::BlankSelector);
+    }
+
+    let has_elements = elements.is_some_and(|elements| !elements.trim().is_empty());
+
+    match mode {
+        // Elements are removed either by the selector or by the ids of the given elements.
+        ElementPatchMode::Remove if selector.is_none() && !has_elements => {
+            Err(PatchElementsError::MissingSelector)
+        }
+        ElementPatchMode::Remove => Ok(()),
+        _ if !has_elements => Err(PatchElementsError::EmptyElements),
+        _ => Ok(()),
+    }
+}
+
+/// Error returned by [`PatchElements::validate`] for events the Datastar client can't apply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PatchElementsError {
+    /// [`ElementPatchMode::Remove`] is used without a selector or elements to remove.
+    MissingSelector,
+    /// The elements are missing or empty, while the mode isn't [`ElementPatchMode::Remove`].
+    EmptyElements,
+    /// The selector is empty or consists of whitespace only.
+    BlankSelector,
+    /// The selector of a [`FragmentResponse`](crate::fragment::FragmentResponse) contains a
+    /// line break or another control character, which can't be sent in a header.
+    InvalidSelector,
+}
+
+impl fmt::Display for PatchElementsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSelector => {
+                f.write_str("removing elements requires a selector or elements with an id")
+            }
+            Self::EmptyElements => f.write_str("patching elements requires non-empty elements"),
+            Self::BlankSelector => f.write_str("the selector must not be blank"),
+            Self::InvalidSelector => {
+                f.write_str("the selector must not contain line breaks or control characters")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatchElementsError {}
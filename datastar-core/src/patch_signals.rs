@@ -0,0 +1,565 @@
+//! [`PatchSignals`] patches signals into the signal store.
+
+use {
+    crate::{
+        DatastarEvent, IntoDatastarEvent,
+        consts::{self, EventType},
+        data_lines::{DataLines, LineWriter},
+        parse::{self, ParseError},
+    },
+    core::{fmt::Write, time::Duration},
+    std::{borrow::Cow, collections::BTreeMap},
+};
+
+#[cfg(feature = "serde")]
+use serde_json::value::RawValue;
+
+/// [`PatchSignals`] patches signals into the signal store.
+///
+/// It is constructed with [`PatchSignals::new`] or [`Default`] and configured with the builder
+/// methods, as fields are added in minor releases, see
+/// [Constructing events](crate#constructing-events).
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::prelude::PatchSignals;
+///
+/// let patch = PatchSignals::new("{count: 0}").only_if_missing(true);
+///
+/// assert!(patch.only_if_missing);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct PatchSignals {
+    /// `id` can be used by the backend to replay events.
+    /// This is part of the SSE spec and is used to tell the browser how to handle the event.
+    /// For more details see <https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#id>
+    pub id: Option<Cow<'static, str>>,
+    /// `retry` is part of the SSE spec and is used to tell the browser how long to wait before reconnecting if the connection is lost.
+    /// When `None`, no `retry` field is sent and the browser keeps its current reconnection delay.
+    /// For more details see <https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#retry>
+    pub retry: Option<Duration>,
+    /// `signals` is a JavaScript object or JSON string that will be sent to the browser to update signals in the signals.
+    /// The data ***must*** evaluate to a valid JavaScript. It will be converted to signals by the Datastar client side.
+    pub signals: Cow<'static, str>,
+    /// Whether to patch the signal only if it does not already exist.
+    /// If not provided, the Datastar client side will default to false, which will cause the data to be patched into the signals.
+    pub only_if_missing: bool,
+}
+
+impl Default for PatchSignals {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl PatchSignals {
+    /// Creates a new [`PatchSignals`] event with the given signals.
+    pub fn new(signals: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            id: None,
+            retry: None,
+            signals: signals.into(),
+            only_if_missing: consts::DEFAULT_PATCH_SIGNALS_ONLY_IF_MISSING,
+        }
+    }
+
+    /// Creates a new [`PatchSignals`] event with static signals, in a `const` or a `static`.
+    ///
+    /// An event which is sent again and again can also be [leaked](crate::DatastarEvent::leak),
+    /// to be written only once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::PatchSignals;
+    ///
+    /// const GENERATING: PatchSignals = PatchSignals::new_static(r#"{"generating": true}"#);
+    ///
+    /// assert_eq!(GENERATING, PatchSignals::new(r#"{"generating": true}"#));
+    /// ```
+    pub const fn new_static(signals: &'static str) -> Self {
+        Self {
+            id: None,
+            retry: None,
+            signals: Cow::Borrowed(signals),
+            only_if_missing: consts::DEFAULT_PATCH_SIGNALS_ONLY_IF_MISSING,
+        }
+    }
+
+    /// Creates a new [`PatchSignals`] event which removes the signals at the given paths,
+    /// by patching them to `null` as specified by Datastar.
+    ///
+    /// Paths are split on `.` into nested signals. Use [`SignalPath`](crate::signal_path::SignalPath)s
+    /// to have them checked at compile time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::PatchSignals;
+    ///
+    /// let patch = PatchSignals::remove(["tmp", "user.name", "user.email"]);
+    ///
+    /// assert_eq!(patch.signals, r#"{"tmp":null,"user":{"email":null,"name":null}}"#);
+    /// ```
+    pub fn remove(paths: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let mut removals = BTreeMap::new();
+        for path in paths {
+            let path = path.into();
+            let mut segments = path.split('.').peekable();
+            let mut nested = &mut removals;
+            while let Some(segment) = segments.next() {
+                if segments.peek().is_none() {
+                    nested.insert(segment.to_owned(), Removal::Signal);
+                    break;
+                }
+                match nested
+                    .entry(segment.to_owned())
+                    .or_insert_with(|| Removal::Nested(BTreeMap::new()))
+                {
+                    Removal::Nested(children) => nested = children,
+                    // The parent is removed already, along with all of its nested signals.
+                    Removal::Signal => break,
+                }
+            }
+        }
+
+        let mut signals = String::new();
+        Removal::Nested(removals).write_json(&mut signals);
+        Self::new(signals)
+    }
+
+    /// Creates a new [`PatchSignals`] event by serializing the given signals to JSON.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::PatchSignals;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Progress {
+    ///     generating: bool,
+    ///     total: u64,
+    /// }
+    ///
+    /// let patch = PatchSignals::from_serialize(&Progress { generating: true, total: 3 })?;
+    ///
+    /// assert_eq!(patch.signals, r#"{"generating":true,"total":3}"#);
+    /// # Ok::<(), datastar_core::Error>(())
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_serialize(signals: &impl serde::Serialize) -> Result<Self, crate::Error> {
+        Ok(Self::new(serde_json::to_string(signals)?))
+    }
+
+    /// Alias of [`PatchSignals::from_serialize`].
+    #[cfg(feature = "serde")]
+    pub fn try_new(signals: &impl serde::Serialize) -> Result<Self, crate::Error> {
+        Self::from_serialize(signals)
+    }
+
+    /// Creates a new [`PatchSignals`] event from already serialized JSON,
+    /// which takes over the buffer of the [`RawValue`] without copying it.
+    ///
+    /// Unlike [`PatchSignals::new`], the signals are known to be valid JSON.
+    /// A `&RawValue` or a [`serde_json::Value`] converts with [`From`] as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::PatchSignals;
+    /// use serde_json::value::RawValue;
+    ///
+    /// let signals = RawValue::from_string(r#"{"count":1}"#.to_owned())?;
+    /// let patch = PatchSignals::from_raw_value(signals);
+    ///
+    /// assert_eq!(patch.signals, r#"{"count":1}"#);
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_raw_value(signals: Box<RawValue>) -> Self {
+        Self::new(String::from(Box::<str>::from(signals)))
+    }
+
+    /// Creates a [`PatchSignalsBuilder`] to assemble the signals one path at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::PatchSignals;
+    ///
+    /// let patch = PatchSignals::builder()
+    ///     .set("total", 5)
+    ///     .set("user.name", "bob")
+    ///     .remove("tmp")
+    ///     .build();
+    ///
+    /// assert_eq!(patch.signals, r#"{"tmp":null,"total":5,"user":{"name":"bob"}}"#);
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn builder() -> PatchSignalsBuilder {
+        PatchSignalsBuilder::default()
+    }
+
+    /// Sets the `id` of the [`PatchSignals`] event.
+    pub fn id(mut self, id: impl Into<Cow<'static, str>>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets the `retry` of the [`PatchSignals`] event.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Sets the `signals` of the [`PatchSignals`] event.
+    pub fn signals(mut self, signals: impl Into<Cow<'static, str>>) -> Self {
+        self.signals = signals.into();
+        self
+    }
+
+    /// Sets the `only_if_missing` of the [`PatchSignals`] event.
+    pub fn only_if_missing(mut self, only_if_missing: bool) -> Self {
+        self.only_if_missing = only_if_missing;
+        self
+    }
+
+    /// Converts this [`PatchSignals`] into a [`DatastarEvent`].
+    #[inline]
+    pub fn into_datastar_event(mut self) -> DatastarEvent {
+        let id = self.id.take();
+        self.convert_to_datastar_event_inner(id)
+    }
+
+    /// Copy this [`PatchSignals`] as a [`DatastarEvent`].
+    #[inline]
+    pub fn as_datastar_event(&self) -> DatastarEvent {
+        self.convert_to_datastar_event_inner(self.id.clone())
+    }
+
+    fn convert_to_datastar_event_inner(&self, id: Option<Cow<'static, str>>) -> DatastarEvent {
+        let mut data = DataLines::with_capacity(self.data_capacity());
+        self.write_data_lines(&mut data);
+
+        DatastarEvent {
+            event: Some(consts::EventType::PatchSignals),
+            custom_event: None,
+            comments: Vec::new(),
+            fields: Vec::new(),
+            id,
+            retry: self.retry,
+            data,
+        }
+    }
+
+    /// Returns the capacity to reserve for the data lines of this [`PatchSignals`].
+    pub(crate) fn data_capacity(&self) -> usize {
+        self.signals.len() + 32
+    }
+
+    /// Writes the data lines of this [`PatchSignals`], which the framework integrations
+    /// write straight into their events.
+    pub(crate) fn write_data_lines(&self, data: &mut impl LineWriter) {
+        if self.only_if_missing != consts::DEFAULT_PATCH_SIGNALS_ONLY_IF_MISSING {
+            data.push_literal(
+                consts::ONLY_IF_MISSING_DATALINE_LITERAL,
+                if self.only_if_missing {
+                    "true"
+                } else {
+                    "false"
+                },
+            );
+        }
+
+        if !self.signals.is_empty() {
+            data.push_literal(consts::SIGNALS_DATALINE_LITERAL, &self.signals);
+        }
+    }
+}
+
+/// The signals removed by [`PatchSignals::remove`].
+enum Removal {
+    /// The signal is removed by patching it to `null`.
+    Signal,
+    /// Some nested signals are removed.
+    Nested(BTreeMap<String, Removal>),
+}
+
+impl Removal {
+    fn write_json(&self, out: &mut String) {
+        let Self::Nested(children) = self else {
+            out.push_str("null");
+            return;
+        };
+        out.push('{');
+        for (index, (key, child)) in children.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            write_json_string(out, key);
+            out.push(':');
+            child.write_json(out);
+        }
+        out.push('}');
+    }
+}
+
+/// Patches the boolean signal at the dot separated `path`.
+pub(crate) fn flag(path: &str, value: bool) -> PatchSignals {
+    at_path(path, if value { "true" } else { "false" })
+}
+
+/// Patches the signal at the dot separated `path` with the JSON `value`.
+///
+/// An empty `path` patches the signals with `value` directly, which has to be an object then.
+pub(crate) fn at_path(path: &str, value: &str) -> PatchSignals {
+    if path.is_empty() {
+        return PatchSignals::new(value.to_owned());
+    }
+    let mut signals = String::new();
+    let mut depth = 0;
+    for segment in path.split('.') {
+        signals.push('{');
+        write_json_string(&mut signals, segment);
+        signals.push(':');
+        depth += 1;
+    }
+    signals.push_str(value);
+    signals.extend(core::iter::repeat_n('}', depth));
+    PatchSignals::new(signals)
+}
+
+pub(crate) fn write_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                // Assumption: std::fmt::write does not fail ever for [`String`].
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl From<&PatchSignals> for DatastarEvent {
+    #[inline]
+    fn from(val: &PatchSignals) -> Self {
+        val.as_datastar_event()
+    }
+}
+
+impl From<PatchSignals> for DatastarEvent {
+    #[inline]
+    fn from(val: PatchSignals) -> Self {
+        val.into_datastar_event()
+    }
+}
+
+impl IntoDatastarEvent for PatchSignals {
+    fn event_type(&self) -> Option<EventType> {
+        Some(EventType::PatchSignals)
+    }
+}
+
+impl IntoDatastarEvent for &PatchSignals {
+    fn event_type(&self) -> Option<EventType> {
+        Some(EventType::PatchSignals)
+    }
+}
+
+impl TryFrom<DatastarEvent> for PatchSignals {
+    type Error = ParseError;
+
+    /// Decodes a `datastar-patch-signals` event, such as one parsed by [`DatastarEvent::from_sse_str`].
+    fn try_from(event: DatastarEvent) -> Result<Self, Self::Error> {
+        parse::expect_type(&event, EventType::PatchSignals)?;
+
+        let mut patch = Self::new("");
+        patch.id = event.id;
+        patch.retry = event.retry;
+        let mut signals = Vec::new();
+        for line in &event.data {
+            match parse::data_line(line) {
+                (consts::ONLY_IF_MISSING_DATALINE_LITERAL, value) => {
+                    patch.only_if_missing = parse::data_bool(line, value)?;
+                }
+                (consts::SIGNALS_DATALINE_LITERAL, line) => signals.push(line),
+                _ => return Err(ParseError::InvalidDataLine(line.to_owned())),
+            }
+        }
+        patch.signals = signals.join("\n").into();
+        Ok(patch)
+    }
+}
+
+/// [`PatchSignalsBuilder`] assembles the signals of a [`PatchSignals`] event,
+/// created by [`PatchSignals::builder`].
+///
+/// Paths are split on `.` into nested signals, following the Datastar signal model,
+/// so `user.name` sets the `name` signal of the `user` object.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Default)]
+pub struct PatchSignalsBuilder {
+    signals: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(feature = "serde")]
+impl PatchSignalsBuilder {
+    /// Sets the signal at `path` to the given value.
+    ///
+    /// Use a [`SignalPath`](crate::signal_path::SignalPath) to have the path checked at compile time.
+    pub fn set(mut self, path: impl AsRef<str>, value: impl Into<serde_json::Value>) -> Self {
+        let mut segments = path.as_ref().split('.');
+        // Assumption: split always yields at least one segment.
+        let mut key = segments.next().unwrap_or_default();
+        let mut signals = &mut self.signals;
+        for segment in segments {
+            let entry = signals.entry(key).or_insert(serde_json::Value::Null);
+            if !entry.is_object() {
+                *entry = serde_json::Value::Object(Default::default());
+            }
+            signals = entry.as_object_mut().expect("entry was made an object");
+            key = segment;
+        }
+        signals.insert(key.to_owned(), value.into());
+        self
+    }
+
+    /// Removes the signal at `path`, by patching it to `null`.
+    pub fn remove(self, path: impl AsRef<str>) -> Self {
+        self.set(path, serde_json::Value::Null)
+    }
+
+    /// Serializes the signals into a [`PatchSignals`] event.
+    pub fn build(self) -> PatchSignals {
+        serde_json::Value::Object(self.signals).into()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Box<RawValue>> for PatchSignals {
+    fn from(signals: Box<RawValue>) -> Self {
+        Self::from_raw_value(signals)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<&RawValue> for PatchSignals {
+    fn from(signals: &RawValue) -> Self {
+        Self::new(signals.get().to_owned())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Value> for PatchSignals {
+    fn from(signals: serde_json::Value) -> Self {
+        Self::new(signals.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<PatchSignalsBuilder> for PatchSignals {
+    fn from(builder: PatchSignalsBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Creates a [`PatchSignals`] event from JSON-like signals, serializing every value with
+/// `serde_json` where the macro is used.
+///
+/// Names are identifiers or string literals, values are expressions which implement
+/// `serde::Serialize`, and `{ ... }` nests signals. A name on its own takes the value
+/// of the variable of the same name.
+///
+/// # Panics
+///
+/// Panics if a value fails to serialize, such as a map with keys which aren't strings.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::signals;
+///
+/// let (total, done) = (12, 3);
+/// let patch = signals! {
+///     total: total,
+///     done,
+///     "is-loading": false,
+///     nested: { flag: true, tags: ["a", "b"] },
+/// };
+///
+/// assert_eq!(
+///     patch.signals,
+///     r#"{"done":3,"is-loading":false,"nested":{"flag":true,"tags":["a","b"]},"total":12}"#,
+/// );
+/// ```
+#[cfg(feature = "serde")]
+#[macro_export]
+macro_rules! signals {
+    ($($signals:tt)*) => {
+        $crate::prelude::PatchSignals::from($crate::__signals_value!({ $($signals)* }))
+    };
+}
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __signals_value {
+    ({ $($signals:tt)* }) => {{
+        #[allow(unused_mut)]
+        let mut object = $crate::patch_signals::__private::Map::new();
+        $crate::__signals_object!(object; $($signals)*);
+        $crate::patch_signals::__private::Value::Object(object)
+    }};
+    ($value:expr) => {
+        $crate::patch_signals::__private::to_value(&$value)
+            .expect("signals must serialize to JSON")
+    };
+}
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __signals_object {
+    ($object:ident;) => {};
+    ($object:ident; $name:ident : { $($nested:tt)* } $(, $($rest:tt)*)?) => {
+        $object.insert(
+            ::core::stringify!($name).into(),
+            $crate::__signals_value!({ $($nested)* }),
+        );
+        $crate::__signals_object!($object; $($($rest)*)?);
+    };
+    ($object:ident; $name:literal : { $($nested:tt)* } $(, $($rest:tt)*)?) => {
+        $object.insert($name.into(), $crate::__signals_value!({ $($nested)* }));
+        $crate::__signals_object!($object; $($($rest)*)?);
+    };
+    ($object:ident; $name:ident : $value:expr $(, $($rest:tt)*)?) => {
+        $object.insert(::core::stringify!($name).into(), $crate::__signals_value!($value));
+        $crate::__signals_object!($object; $($($rest)*)?);
+    };
+    ($object:ident; $name:literal : $value:expr $(, $($rest:tt)*)?) => {
+        $object.insert($name.into(), $crate::__signals_value!($value));
+        $crate::__signals_object!($object; $($($rest)*)?);
+    };
+    ($object:ident; $name:ident $(, $($rest:tt)*)?) => {
+        $object.insert(::core::stringify!($name).into(), $crate::__signals_value!($name));
+        $crate::__signals_object!($object; $($($rest)*)?);
+    };
+}
+
+/// The items used by [`signals!`](crate::signals!), which can't name `serde_json` directly.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json::{Map, Value, to_value};
+}
@@ -0,0 +1,174 @@
+//! Pooled buffers to frame events into, with the `bytes` feature.
+//!
+//! Every [`DatastarEvent::to_bytes`] allocates the chunk it returns, which is fine for most
+//! streams but churns the allocator for those sending thousands of events per second, such
+//! as telemetry dashboards or game state. An [`EventBufferPool`] frames the events into large
+//! [`BytesMut`] buffers instead, each chunk taking the next slice of a buffer: a buffer is
+//! reused once every chunk framed into it has been written and dropped, so a steady stream
+//! doesn't allocate at all.
+//!
+//! The chunks are reference counted and the pool is locked twice per event, so framing an event
+//! into a pool takes about as long as allocating its chunk with a fast allocator: the pool pays
+//! off when the allocator is contended by many threads, or fragmented by long-running streams.
+//!
+//! A pool is shared by cloning it, or [`EventBufferPool::global`] can be used. A pool can also
+//! be kept per thread, in which case locking it is never contended:
+//!
+//! ```
+//! use datastar_core::{pool::EventBufferPool, prelude::{DatastarEvent, PatchSignals}};
+//!
+//! thread_local! {
+//!     static POOL: EventBufferPool = EventBufferPool::new();
+//! }
+//!
+//! let event = DatastarEvent::from(PatchSignals::new("{fps: 60}"));
+//! let chunk = POOL.with(|pool| pool.to_bytes(&event));
+//!
+//! assert_eq!(chunk, event.to_bytes());
+//! ```
+
+use {
+    crate::{DatastarEvent, batch::DatastarEventBatch},
+    bytes::{Bytes, BytesMut},
+    std::sync::{Arc, LazyLock, Mutex, PoisonError},
+};
+
+/// The default capacity of the buffers of an [`EventBufferPool`], in bytes.
+pub const DEFAULT_BUFFER_CAPACITY: usize = 16 * 1024;
+
+/// The default number of idle buffers an [`EventBufferPool`] keeps.
+pub const DEFAULT_MAX_IDLE: usize = 16;
+
+static GLOBAL: LazyLock<EventBufferPool> = LazyLock::new(EventBufferPool::new);
+
+/// [`EventBufferPool`] lends buffers to frame events into, so framing many events doesn't
+/// allocate a chunk for each of them.
+///
+/// Cloning an [`EventBufferPool`] returns a handle to the same pool.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{pool::EventBufferPool, prelude::{DatastarEvent, PatchSignals}};
+///
+/// let pool = EventBufferPool::new().buffer_capacity(64 * 1024);
+///
+/// for frame in 0..1000 {
+///     let event = DatastarEvent::from(PatchSignals::new(format!("{{frame: {frame}}}")));
+///     let chunk = pool.to_bytes(&event);
+///     assert_eq!(chunk, event.to_bytes());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventBufferPool {
+    buffers: Arc<Mutex<Vec<BytesMut>>>,
+    buffer_capacity: usize,
+    max_idle: usize,
+}
+
+impl Default for EventBufferPool {
+    fn default() -> Self {
+        Self {
+            buffers: Arc::default(),
+            buffer_capacity: DEFAULT_BUFFER_CAPACITY,
+            max_idle: DEFAULT_MAX_IDLE,
+        }
+    }
+}
+
+impl EventBufferPool {
+    /// Creates a new [`EventBufferPool`] without any buffer, which allocates buffers of
+    /// [`DEFAULT_BUFFER_CAPACITY`] bytes as they are needed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the [`EventBufferPool`] shared by the whole process.
+    pub fn global() -> &'static Self {
+        &GLOBAL
+    }
+
+    /// Sets the capacity of the buffers the [`EventBufferPool`] allocates.
+    ///
+    /// A larger capacity allocates less often, but a buffer is only reused once every chunk
+    /// framed into it has been dropped, so a slow client can hold on to a whole buffer.
+    pub fn buffer_capacity(mut self, buffer_capacity: usize) -> Self {
+        self.buffer_capacity = buffer_capacity;
+        self
+    }
+
+    /// Sets the number of idle buffers the [`EventBufferPool`] keeps, the others being freed
+    /// when they are returned.
+    ///
+    /// A buffer is borrowed by a single thread at once, so this should be at least the number
+    /// of threads framing events at the same time.
+    pub fn max_idle(mut self, max_idle: usize) -> Self {
+        self.max_idle = max_idle;
+        self
+    }
+
+    /// Returns the SSE framing of the [`DatastarEvent`] as [`Bytes`], framed into a pooled
+    /// buffer.
+    ///
+    /// The chunks of [leaked](DatastarEvent::leak) events and keep-alives are static, as with
+    /// [`DatastarEvent::to_bytes`].
+    pub fn to_bytes(&self, event: &DatastarEvent) -> Bytes {
+        if let Some(bytes) = event.static_bytes() {
+            return bytes;
+        }
+        self.frame(event.framed_len(), |buffer| event.write_fmt_to(buffer))
+    }
+
+    /// Returns the SSE framing of every event of the [`DatastarEventBatch`] as one [`Bytes`]
+    /// chunk, framed into a pooled buffer.
+    pub fn batch_to_bytes(&self, batch: &DatastarEventBatch) -> Bytes {
+        match batch.events() {
+            [event] => self.to_bytes(event),
+            events => {
+                let len = events.iter().map(DatastarEvent::framed_len).sum();
+                self.frame(len, |buffer| batch.write_fmt_to(buffer))
+            }
+        }
+    }
+
+    /// Writes the SSE framing of every event of the [`DatastarEventBatch`] into `out` with a
+    /// single call to [`write_all`](std::io::Write::write_all), framing it into a pooled
+    /// buffer rather than a new [`String`].
+    pub fn write_batch_to(
+        &self,
+        batch: &DatastarEventBatch,
+        out: &mut impl std::io::Write,
+    ) -> std::io::Result<()> {
+        out.write_all(&self.batch_to_bytes(batch))
+    }
+
+    /// Frames `len` bytes with `write` into a pooled buffer, returning them.
+    fn frame(&self, len: usize, write: impl FnOnce(&mut BytesMut) -> core::fmt::Result) -> Bytes {
+        let mut buffer = self.take();
+        if buffer.capacity() < len {
+            // Reclaims the buffer if every chunk framed into it was dropped, or else allocates
+            // a new one.
+            buffer.reserve(len.max(self.buffer_capacity));
+        }
+        // Writing to a `BytesMut` never fails.
+        let _ = write(&mut buffer);
+        let bytes = buffer.split().freeze();
+        self.put(buffer);
+        bytes
+    }
+
+    fn take(&self) -> BytesMut {
+        self.buffers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop()
+            .unwrap_or_default()
+    }
+
+    fn put(&self, buffer: BytesMut) {
+        let mut buffers = self.buffers.lock().unwrap_or_else(PoisonError::into_inner);
+        if buffers.len() < self.max_idle {
+            buffers.push(buffer);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Carries signals through a full-page redirect in the query string of the target URL.
+//!
+//! Signals are encoded the same way the Datastar client sends them with `GET` requests:
+//! as JSON in the `datastar` query parameter. The handler of the target page can therefore
+//! read them with the signal extractors of the framework integrations, or with [`from_query`].
+//!
+//! # Examples
+//!
+//! ```
+//! use datastar_core::{query, scripts};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Checkout {
+//!     order: u64,
+//!     coupon: String,
+//! }
+//!
+//! let signals = Checkout { order: 7, coupon: "SPRING 25%".into() };
+//!
+//! let url = query::with_signals("/checkout/done?step=2", &signals)?;
+//! assert_eq!(
+//!     url,
+//!     "/checkout/done?step=2&datastar=%7B%22order%22%3A7%2C%22coupon%22%3A%22SPRING%2025%25%22%7D",
+//! );
+//! let redirect = scripts::redirect(&url);
+//! # drop(redirect);
+//!
+//! // In the handler of `/checkout/done`.
+//! let query = url.split_once('?').map_or("", |(_, query)| query);
+//! assert_eq!(query::from_query::<Checkout>(query)?, signals);
+//! # Ok::<(), datastar_core::Error>(())
+//! ```
+
+use {
+    crate::{Error, json},
+    core::fmt::{self, Display, Write},
+    serde::{Serialize, de::DeserializeOwned},
+};
+
+/// The query parameter the Datastar client sends the signals in.
+const PARAMETER: &str = "datastar";
+
+/// Serializes `signals` into a `datastar=<JSON>` query parameter, percent-encoded.
+pub fn to_query(signals: &impl Serialize) -> Result<String, Error> {
+    let json = serde_json::to_string(signals)?;
+    let mut query = String::with_capacity(PARAMETER.len() + 1 + json.len() * 3 / 2);
+    query.push_str(PARAMETER);
+    query.push('=');
+    // Writing to a `String` never fails.
+    let _ = percent_encode(&mut query, &json);
+    Ok(query)
+}
+
+/// Appends `signals` to the query string of `url`, as returned by [`to_query`].
+///
+/// A fragment of `url` is kept at the end.
+pub fn with_signals(url: &str, signals: &impl Serialize) -> Result<String, Error> {
+    let query = to_query(signals)?;
+    let (url, fragment) = match url.find('#') {
+        Some(index) => url.split_at(index),
+        None => (url, ""),
+    };
+    let separator = match url.find('?') {
+        None => "?",
+        Some(index) if index + 1 == url.len() || url.ends_with('&') => "",
+        Some(_) => "&",
+    };
+    Ok(format!("{url}{separator}{query}{fragment}"))
+}
+
+/// Deserializes the signals from the `datastar` parameter of a query string, without the leading `?`.
+///
+/// Returns [`Error::MissingSignals`] if the parameter is missing, [`Error::InvalidQuery`]
+/// if it is not validly percent-encoded UTF-8, and [`Error::InvalidSignals`] if the signals
+/// don't deserialize into `T`.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{Error, query};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     tags: Vec<String>,
+/// }
+///
+/// // {"tags":["rust",1]}
+/// let err = query::from_query::<Signals>("datastar=%7B%22tags%22%3A%5B%22rust%22%2C1%5D%7D")
+///     .err()
+///     .unwrap();
+///
+/// assert!(matches!(err, Error::InvalidSignals { path, .. } if path == "tags[1]"));
+/// ```
+pub fn from_query<T: DeserializeOwned>(query: &str) -> Result<T, Error> {
+    let value = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == PARAMETER)
+        .map(|(_, value)| value)
+        .ok_or(Error::MissingSignals)?;
+    let signals = percent_decode(value).map_err(|err| Error::InvalidQuery(Box::new(err)))?;
+    json::signals_from_slice(signals.as_bytes())
+}
+
+fn percent_encode(out: &mut String, value: &str) -> fmt::Result {
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.write_char(char::from(byte))?;
+        } else {
+            write!(out, "%{byte:02X}")?;
+        }
+    }
+    Ok(())
+}
+
+fn percent_decode(value: &str) -> Result<String, DecodeError> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' => {
+                let (high, low) = match bytes.get(index + 1..index + 3) {
+                    Some(&[high, low]) => (hex_value(high)?, hex_value(low)?),
+                    _ => return Err(DecodeError),
+                };
+                decoded.push(high << 4 | low);
+                index += 3;
+                continue;
+            }
+            // Forms encode spaces as `+`.
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8(decoded).map_err(|_| DecodeError)
+}
+
+fn hex_value(digit: u8) -> Result<u8, DecodeError> {
+    char::from(digit)
+        .to_digit(16)
+        .map(|value| value as u8)
+        .ok_or(DecodeError)
+}
+
+#[derive(Debug)]
+struct DecodeError;
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the datastar parameter is not validly percent-encoded UTF-8")
+    }
+}
+
+impl std::error::Error for DecodeError {}
@@ -0,0 +1,369 @@
+//! Recording a stream of events to NDJSON and replaying it, with the `serde` feature.
+//!
+//! [`RecordExt::record`] tees every event of a stream into a writer, such as a file, as one
+//! JSON object per line with the time elapsed since the recording started. A [`Recording`] read
+//! back from it is replayed by a [`Player`], with the original timing or scaled by
+//! [`Player::speed`], as the response of a handler. This reproduces what the browser received
+//! when it hit a bug, or demos a flow without its backend.
+//!
+//! ```text
+//! {"elapsedMs":0,"event":{"event":"datastar-patch-elements","data":["elements <div id='status'>Loading</div>"]}}
+//! {"elapsedMs":1250,"event":{"event":"datastar-patch-signals","data":["signals {\"done\":true}"]}}
+//! ```
+
+#[cfg(feature = "tokio")]
+use crate::clock::TokioClock;
+use {
+    crate::{DatastarEvent, clock::Clock, response::IntoDatastarResponse},
+    core::{
+        fmt,
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+        time::Duration,
+    },
+    futures_core::Stream,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::VecDeque,
+        io::{self, BufRead, Write},
+    },
+};
+
+/// [`RecordedEvent`] is an event of a [`Recording`], with when it was sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// The time elapsed since the recording started, written in milliseconds as `elapsedMs`.
+    #[serde(rename = "elapsedMs", with = "millis")]
+    pub elapsed: Duration,
+    /// The event.
+    pub event: DatastarEvent,
+}
+
+/// A [`RecordedEvent`] which borrows its event, as it's written while the event is sent.
+#[derive(Serialize)]
+struct RecordedEventRef<'a> {
+    #[serde(rename = "elapsedMs", with = "millis")]
+    elapsed: Duration,
+    event: &'a DatastarEvent,
+}
+
+/// [`RecordExt`] adds the [`record`](RecordExt::record) combinator to every [`Stream`] of
+/// [`DatastarEvent`]s.
+pub trait RecordExt: Stream<Item = DatastarEvent> + Sized {
+    /// Writes every event to `writer` as a line of NDJSON, as it's sent.
+    ///
+    /// The writer is written to and flushed on the task polling the stream, so a file should
+    /// be on a local disk. Recording stops at the first error, without ending the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{prelude::PatchSignals, recording::RecordExt, sender};
+    ///
+    /// let (sender, receiver) = sender::channel();
+    /// let file = std::fs::File::create(std::env::temp_dir().join("signals.ndjson"))?;
+    ///
+    /// // Return this from a handler instead of `receiver`.
+    /// let response = receiver.record(file);
+    /// sender.send(PatchSignals::new("{done: true}")).ok();
+    /// # drop(response);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(feature = "tokio")]
+    fn record<W: Write>(self, writer: W) -> Recorder<Self, W, TokioClock> {
+        self.record_with_clock(writer, TokioClock)
+    }
+
+    /// Writes every event to `writer` as a line of NDJSON, as it's sent, with the time as
+    /// measured by the given [`Clock`].
+    fn record_with_clock<W: Write, C: Clock>(self, writer: W, clock: C) -> Recorder<Self, W, C> {
+        Recorder {
+            stream: Box::pin(self),
+            writer: Some(writer),
+            start: clock.now(),
+            clock,
+        }
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>> RecordExt for S {}
+
+/// [`Recorder`] is the [`Stream`] returned by [`RecordExt::record`].
+///
+/// It yields the events of the inner stream unchanged, and ends when it ends.
+pub struct Recorder<S, W, C> {
+    stream: Pin<Box<S>>,
+    writer: Option<W>,
+    start: Duration,
+    clock: C,
+}
+
+// Nothing is pinned structurally, as the stream is boxed.
+impl<S, W, C> Unpin for Recorder<S, W, C> {}
+
+impl<S, W, C> fmt::Debug for Recorder<S, W, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("recording", &self.writer.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent>, W: Write, C: Clock> Stream for Recorder<S, W, C> {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let event = futures_core::ready!(this.stream.as_mut().poll_next(cx));
+        let Some(writer) = &mut this.writer else {
+            return Poll::Ready(event);
+        };
+
+        let written = match &event {
+            Some(event) => {
+                let recorded = RecordedEventRef {
+                    elapsed: this.clock.now().saturating_sub(this.start),
+                    event,
+                };
+                write_line(writer, &recorded)
+            }
+            None => writer.flush(),
+        };
+        if let Err(_err) = written {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(error = %_err, "stopped recording the events");
+            this.writer = None;
+        }
+        Poll::Ready(event)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+impl<S, W, C> IntoDatastarResponse for Recorder<S, W, C>
+where
+    S: Stream<Item = DatastarEvent> + Send + 'static,
+    W: Write + Send + 'static,
+    C: Clock,
+{
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
+
+/// [`Recording`] is a list of [`RecordedEvent`]s, read from or written to NDJSON.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+/// use datastar_core::{DatastarEvent, prelude::PatchSignals, recording::{RecordedEvent, Recording}};
+///
+/// let ndjson = r#"{"elapsedMs":1250,"event":{"event":"datastar-patch-signals","data":["signals {done: true}"]}}"#;
+/// let recording = Recording::read(ndjson.as_bytes())?;
+///
+/// assert_eq!(
+///     recording.events,
+///     [RecordedEvent {
+///         elapsed: Duration::from_millis(1250),
+///         event: DatastarEvent::from(PatchSignals::new("{done: true}")),
+///     }],
+/// );
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+    /// The events, in the order they were sent.
+    pub events: Vec<RecordedEvent>,
+}
+
+impl Recording {
+    /// Reads a [`Recording`] from NDJSON, skipping blank lines.
+    ///
+    /// Invalid JSON is returned as an [`io::Error`] of kind [`InvalidData`](io::ErrorKind::InvalidData).
+    pub fn read(reader: impl BufRead) -> io::Result<Self> {
+        let mut events = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                events.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Self { events })
+    }
+
+    /// Writes the [`Recording`] as NDJSON.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        for recorded in &self.events {
+            write_line(&mut writer, recorded)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a [`Player`] replaying the events with their original timing.
+    #[cfg(feature = "tokio")]
+    pub fn play(self) -> Player<TokioClock> {
+        self.play_with_clock(TokioClock)
+    }
+
+    /// Returns a [`Player`] replaying the events with their original timing, as measured by
+    /// the given [`Clock`].
+    pub fn play_with_clock<C: Clock>(self, clock: C) -> Player<C> {
+        Player {
+            events: self.events.into(),
+            speed: 1.0,
+            start: None,
+            sleep: None,
+            clock,
+        }
+    }
+}
+
+impl FromIterator<RecordedEvent> for Recording {
+    fn from_iter<I: IntoIterator<Item = RecordedEvent>>(iter: I) -> Self {
+        Self {
+            events: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// [`Player`] is the [`Stream`] returned by [`Recording::play`], which yields every event
+/// of the recording when it's due.
+///
+/// The time is measured from when the player is first polled, and it ends after the last event.
+///
+/// # Examples
+///
+/// ```
+/// use core::{
+///     pin::Pin,
+///     task::{Context, Poll, Waker},
+///     time::Duration,
+/// };
+/// use datastar_core::{
+///     clock::MockClock,
+///     prelude::PatchSignals,
+///     recording::{RecordedEvent, Recording},
+/// };
+/// use futures_core::Stream;
+///
+/// let recording = Recording::from_iter([
+///     RecordedEvent { elapsed: Duration::ZERO, event: PatchSignals::new("{step: 1}").into() },
+///     RecordedEvent { elapsed: Duration::from_secs(4), event: PatchSignals::new("{step: 2}").into() },
+/// ]);
+///
+/// let clock = MockClock::new();
+/// let mut player = recording.play_with_clock(clock.clone()).speed(2.0);
+/// let mut cx = Context::from_waker(Waker::noop());
+///
+/// assert!(matches!(Pin::new(&mut player).poll_next(&mut cx), Poll::Ready(Some(_))));
+/// assert!(Pin::new(&mut player).poll_next(&mut cx).is_pending());
+///
+/// clock.advance(Duration::from_secs(2));
+/// assert!(matches!(Pin::new(&mut player).poll_next(&mut cx), Poll::Ready(Some(_))));
+/// assert!(matches!(Pin::new(&mut player).poll_next(&mut cx), Poll::Ready(None)));
+/// ```
+pub struct Player<C: Clock> {
+    events: VecDeque<RecordedEvent>,
+    speed: f64,
+    start: Option<Duration>,
+    sleep: Option<Pin<Box<C::Sleep>>>,
+    clock: C,
+}
+
+impl<C: Clock> Player<C> {
+    /// Scales the timing of the recording by `speed`, so `2.0` replays it twice as fast and
+    /// [`f64::INFINITY`] replays it without waiting.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` isn't positive.
+    pub fn speed(mut self, speed: f64) -> Self {
+        assert!(speed > 0.0, "the speed must be positive");
+        self.speed = speed;
+        self
+    }
+}
+
+// Nothing is pinned structurally, as the sleep is boxed.
+impl<C: Clock> Unpin for Player<C> {}
+
+impl<C: Clock> fmt::Debug for Player<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Player")
+            .field("remaining", &self.events.len())
+            .field("speed", &self.speed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: Clock> Stream for Player<C> {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let Some(next) = this.events.front() else {
+            return Poll::Ready(None);
+        };
+
+        let now = this.clock.now();
+        let start = *this.start.get_or_insert(now);
+        let due = next.elapsed.div_f64(this.speed);
+        let elapsed = now.saturating_sub(start);
+        if elapsed < due {
+            let clock = &this.clock;
+            let sleep = this
+                .sleep
+                .get_or_insert_with(|| Box::pin(clock.sleep(due - elapsed)));
+            futures_core::ready!(sleep.as_mut().poll(cx));
+        }
+
+        this.sleep = None;
+        Poll::Ready(this.events.pop_front().map(|recorded| recorded.event))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.events.len(), Some(self.events.len()))
+    }
+}
+
+impl<C: Clock> IntoDatastarResponse for Player<C> {
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
+
+/// Writes `value` as a line of JSON and flushes `writer`.
+fn write_line(writer: &mut impl Write, value: &impl Serialize) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+/// (De)serializes a [`Duration`] as whole milliseconds.
+mod millis {
+    use {
+        core::time::Duration,
+        serde::{Deserialize, Deserializer, Serializer},
+    };
+
+    pub(super) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(u64::try_from(duration.as_millis()).unwrap_or(u64::MAX))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
@@ -0,0 +1,276 @@
+//! [`RedisHub`] fans out events through Redis, so every instance of a load-balanced
+//! deployment can push to its own clients, and clients can resume on any instance.
+//!
+//! Live events are sent with Redis pub/sub. When replay is enabled, they are also appended
+//! to a capped Redis stream per topic, which is used to replay the events a client missed.
+//! Events are stored in their SSE representation.
+
+use {
+    crate::{
+        DatastarEvent, migrations::migrate_sse, replay::Journal, response::IntoDatastarResponse,
+    },
+    ::redis::{AsyncCommands, Client, RedisResult, aio::MultiplexedConnection, aio::PubSubStream},
+    core::{
+        fmt,
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    futures_core::Stream,
+    std::collections::VecDeque,
+};
+
+/// The default prefix of the Redis keys used by a [`RedisHub`].
+pub const DEFAULT_PREFIX: &str = "datastar";
+
+/// [`RedisHub`] fans out [`DatastarEvent`]s to all subscribers of a topic, across processes.
+///
+/// It is the multi-instance counterpart of the in-memory `broadcast::Hub`.
+/// Cloning a [`RedisHub`] is cheap, and all clones share the same connection.
+///
+/// # Examples
+///
+/// ```no_run
+/// use datastar_core::{prelude::PatchElements, redis::RedisHub};
+///
+/// # async fn run() -> redis::RedisResult<()> {
+/// let hub = RedisHub::new(redis::Client::open("redis://127.0.0.1/")?)
+///     .await?
+///     .replay(1000);
+///
+/// let subscription = hub.subscribe_from("chat", None).await?;
+/// hub.publish("chat", PatchElements::new("<li>Hello!</li>").selector("#messages").id("1"))
+///     .await?;
+/// # drop(subscription);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RedisHub {
+    client: Client,
+    connection: MultiplexedConnection,
+    prefix: String,
+    replay_len: usize,
+}
+
+impl fmt::Debug for RedisHub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisHub")
+            .field("client", &self.client)
+            .field("prefix", &self.prefix)
+            .field("replay_len", &self.replay_len)
+            .finish()
+    }
+}
+
+impl RedisHub {
+    /// Creates a new [`RedisHub`] using the given Redis client.
+    pub async fn new(client: Client) -> RedisResult<Self> {
+        let connection = client.get_multiplexed_async_connection().await?;
+        Ok(Self {
+            client,
+            connection,
+            prefix: DEFAULT_PREFIX.to_owned(),
+            replay_len: 0,
+        })
+    }
+
+    /// Sets the prefix of the Redis keys, defaults to [`DEFAULT_PREFIX`].
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Keeps approximately the last `len` events of every topic in a Redis stream,
+    /// so clients reconnecting with a `Last-Event-ID` can resume using [`RedisHub::subscribe_from`].
+    pub fn replay(mut self, len: usize) -> Self {
+        self.replay_len = len;
+        self
+    }
+
+    fn channel_key(&self, topic: &str) -> String {
+        format!("{}:{topic}", self.prefix)
+    }
+
+    fn stream_key(&self, topic: &str) -> String {
+        format!("{}:{topic}:replay", self.prefix)
+    }
+
+    /// Publishes an event to all subscribers of `topic`,
+    /// returning the number of subscribers it was sent to.
+    pub async fn publish(
+        &self,
+        topic: &str,
+        event: impl Into<DatastarEvent>,
+    ) -> RedisResult<usize> {
+        let payload = event.into().to_string();
+        let mut connection = self.connection.clone();
+
+        if self.replay_len == 0 {
+            return connection.publish(self.channel_key(topic), payload).await;
+        }
+
+        let (count,): (usize,) = ::redis::pipe()
+            .atomic()
+            .cmd("XADD")
+            .arg(self.stream_key(topic))
+            .arg("MAXLEN")
+            .arg("~")
+            .arg(self.replay_len)
+            .arg("*")
+            .arg("event")
+            .arg(&payload)
+            .ignore()
+            .cmd("PUBLISH")
+            .arg(self.channel_key(topic))
+            .arg(&payload)
+            .query_async(&mut connection)
+            .await?;
+        Ok(count)
+    }
+
+    /// Subscribes to all events published to `topic` after the event with `last_event_id`.
+    ///
+    /// The missed events are only available if replay is enabled with [`RedisHub::replay`].
+    /// As the subscription starts before the missed events are read, an event published in
+    /// between can be received twice.
+    pub async fn subscribe_from(
+        &self,
+        topic: &str,
+        last_event_id: Option<&str>,
+    ) -> RedisResult<RedisSubscription> {
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(self.channel_key(topic)).await?;
+
+        let missed = match last_event_id {
+            Some(last_event_id) if self.replay_len > 0 => {
+                self.events_after(topic, last_event_id).await?.into()
+            }
+            _ => VecDeque::new(),
+        };
+
+        Ok(RedisSubscription {
+            pending: missed,
+            stream: pubsub.into_on_message(),
+        })
+    }
+
+    /// Returns the recorded events of `topic` after the event with `last_event_id`.
+    ///
+    /// An empty list is returned when nothing was missed or the id is unknown.
+    pub async fn events_after(
+        &self,
+        topic: &str,
+        last_event_id: &str,
+    ) -> RedisResult<Vec<DatastarEvent>> {
+        let entries: Vec<(String, Vec<(String, String)>)> = ::redis::cmd("XRANGE")
+            .arg(self.stream_key(topic))
+            .arg("-")
+            .arg("+")
+            .query_async(&mut self.connection.clone())
+            .await?;
+
+        let events: Vec<DatastarEvent> = entries
+            .into_iter()
+            .flat_map(|(_, fields)| fields)
+            .filter(|(field, _)| field == "event")
+            .filter_map(|(_, payload)| migrate_sse(&payload).ok())
+            .flatten()
+            .collect();
+
+        Ok(
+            match events
+                .iter()
+                .rposition(|event| event.id.as_deref() == Some(last_event_id))
+            {
+                Some(position) => events.into_iter().skip(position + 1).collect(),
+                None => Vec::new(),
+            },
+        )
+    }
+
+    /// Returns a [`Journal`] over the recorded events of `topic`,
+    /// to be used with the framework replay middleware.
+    pub fn journal(&self, topic: impl Into<String>) -> RedisJournal {
+        RedisJournal {
+            hub: self.clone(),
+            topic: topic.into(),
+        }
+    }
+}
+
+/// [`RedisJournal`] is a [`Journal`] over the recorded events of a topic of a [`RedisHub`].
+#[derive(Debug, Clone)]
+pub struct RedisJournal {
+    hub: RedisHub,
+    topic: String,
+}
+
+impl Journal for RedisJournal {
+    fn events_after(&self, last_event_id: &str) -> impl Future<Output = Vec<DatastarEvent>> + Send {
+        let last_event_id = last_event_id.to_owned();
+        async move {
+            self.hub
+                .events_after(&self.topic, &last_event_id)
+                .await
+                .unwrap_or_else(
+                    #[cfg_attr(not(feature = "tracing"), expect(unused_variables))]
+                    |err| {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(%err, "failed to read missed events from redis");
+
+                        Vec::new()
+                    },
+                )
+        }
+    }
+}
+
+/// [`RedisSubscription`] is a [`Stream`] of the events published to a topic of a [`RedisHub`].
+///
+/// It can be returned from a handler as is, using the framework integrations.
+pub struct RedisSubscription {
+    pending: VecDeque<DatastarEvent>,
+    stream: PubSubStream,
+}
+
+impl fmt::Debug for RedisSubscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisSubscription")
+            .field("pending", &self.pending)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Stream for RedisSubscription {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            let Some(msg) = futures_core::ready!(Pin::new(&mut self.stream).poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            let events = msg
+                .get_payload::<String>()
+                .ok()
+                .and_then(|payload| migrate_sse(&payload).ok());
+            if let Some(events) = events {
+                self.pending.extend(events);
+            } else {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(channel = msg.get_channel_name(), "ignoring invalid event");
+            }
+        }
+    }
+}
+
+impl IntoDatastarResponse for RedisSubscription {
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
@@ -0,0 +1,358 @@
+//! Replay of missed events for clients resuming a stream with `Last-Event-ID`.
+//!
+//! When an SSE connection drops, the browser reconnects and sends the `id` of the last
+//! event it received in the `Last-Event-ID` header. A [`Journal`] knows which events were
+//! sent after that id, so that the framework integrations can transparently prepend them
+//! to the stream returned by the handler.
+
+use {
+    crate::DatastarEvent,
+    core::{fmt::Display, future::Future, ops::Deref},
+    std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex, PoisonError},
+    },
+};
+
+/// The name of the header a browser uses to resume an SSE stream.
+#[doc(hidden)]
+pub const LAST_EVENT_ID_HEADER_STR: &str = "last-event-id";
+
+/// [`LastEventId`] is the `id` of the last event a client received before its connection dropped,
+/// as sent in the `Last-Event-ID` header when it reconnects.
+///
+/// The browser only remembers ids of events that were sent with one, see [`DatastarEvent::id`]
+/// and the `id` builder methods of the event types. Events sent without an id keep the
+/// previously received id, so a stream should assign ids to every event that can be resumed from.
+///
+/// Every framework integration provides an extractor for it, which can be used to resume a stream
+/// without a [`Journal`]:
+///
+/// - Axum: `LastEventId` implements `FromRequestParts` and `OptionalFromRequestParts`;
+/// - Rocket: `LastEventId` is a request guard, which forwards if the header is missing;
+/// - Warp: the `warp::last_event_id` filter extracts an `Option<LastEventId>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LastEventId(pub String);
+
+impl LastEventId {
+    /// Returns the id as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for LastEventId {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for LastEventId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// [`Journal`] gives access to previously sent events so they can be replayed to a client
+/// that reconnects with a `Last-Event-ID` header.
+///
+/// Only events which were sent with an `id` can be resumed from.
+pub trait Journal: Send + Sync + 'static {
+    /// Returns all events recorded after the event with the given `last_event_id`,
+    /// in the order they were originally sent.
+    ///
+    /// An empty list is returned when nothing was missed or the id is unknown.
+    fn events_after(&self, last_event_id: &str) -> impl Future<Output = Vec<DatastarEvent>> + Send;
+}
+
+impl<J: Journal> Journal for Arc<J> {
+    fn events_after(&self, last_event_id: &str) -> impl Future<Output = Vec<DatastarEvent>> + Send {
+        (**self).events_after(last_event_id)
+    }
+}
+
+/// [`ReplayBuffer`] records sent events, so they can be replayed to a client that reconnects
+/// with a `Last-Event-ID` header.
+///
+/// Unlike a [`Journal`], which can be backed by any (asynchronous) storage, a [`ReplayBuffer`]
+/// is written to for every sent event and therefore has to be cheap and synchronous.
+/// It is used by the [`DatastarSender`](crate::sender::DatastarSender) and the broadcast `Hub`.
+pub trait ReplayBuffer: Send + Sync + 'static {
+    /// Records an event that is sent to the client.
+    fn record(&self, event: &DatastarEvent);
+
+    /// Returns all recorded events after the event with the given `last_event_id`,
+    /// in the order they were recorded.
+    ///
+    /// An empty list is returned when nothing was missed or the id is unknown.
+    fn replay(&self, last_event_id: &str) -> Vec<DatastarEvent>;
+}
+
+impl<B: ReplayBuffer> ReplayBuffer for Arc<B> {
+    fn record(&self, event: &DatastarEvent) {
+        (**self).record(event)
+    }
+
+    fn replay(&self, last_event_id: &str) -> Vec<DatastarEvent> {
+        (**self).replay(last_event_id)
+    }
+}
+
+/// [`RingBuffer`] is an in-memory [`ReplayBuffer`] which keeps the last `capacity` events.
+///
+/// It also implements [`Journal`], so it can be used with the framework replay middleware.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     prelude::PatchSignals,
+///     replay::{ReplayBuffer, RingBuffer},
+/// };
+///
+/// let buffer = RingBuffer::new(16);
+///
+/// for n in 1..=3 {
+///     buffer.record(&PatchSignals::new(format!("{{count: {n}}}")).id(n.to_string()).into());
+/// }
+///
+/// let missed = buffer.replay("1");
+/// assert_eq!(missed.len(), 2);
+/// assert_eq!(missed[0].id.as_deref(), Some("2"));
+/// ```
+#[derive(Debug)]
+pub struct RingBuffer {
+    events: Mutex<VecDeque<DatastarEvent>>,
+    capacity: usize,
+}
+
+impl RingBuffer {
+    /// Creates a new [`RingBuffer`] which keeps the last `capacity` events.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Returns the number of events currently kept.
+    pub fn len(&self) -> usize {
+        self.events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Returns `true` if no events are kept.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the event with the given `id` is kept.
+    pub fn contains(&self, id: &str) -> bool {
+        self.events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .any(|event| event.id.as_deref() == Some(id))
+    }
+}
+
+impl ReplayBuffer for RingBuffer {
+    fn record(&self, event: &DatastarEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event.clone());
+    }
+
+    fn replay(&self, last_event_id: &str) -> Vec<DatastarEvent> {
+        let events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        match events
+            .iter()
+            .rposition(|event| event.id.as_deref() == Some(last_event_id))
+        {
+            Some(position) => events.iter().skip(position + 1).cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Journal for RingBuffer {
+    fn events_after(&self, last_event_id: &str) -> impl Future<Output = Vec<DatastarEvent>> + Send {
+        core::future::ready(self.replay(last_event_id))
+    }
+}
+
+/// The default number of events requested from a [`ReplaySource`] at once.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// [`ReplaySource`] reads events from durable storage, such as the database of an audit log,
+/// to backfill events which are no longer kept in memory.
+///
+/// It is used by [`Backfill`], which pages through the source using the id of the last
+/// returned event as the cursor.
+pub trait ReplaySource: Send + Sync + 'static {
+    /// Returns at most `limit` events stored after the event with the id `cursor`,
+    /// in the order they were originally sent.
+    ///
+    /// An empty list is returned when there are no more events or the cursor is unknown.
+    fn events_after(
+        &self,
+        cursor: &str,
+        limit: usize,
+    ) -> impl Future<Output = Vec<DatastarEvent>> + Send;
+}
+
+impl<S: ReplaySource> ReplaySource for Arc<S> {
+    fn events_after(
+        &self,
+        cursor: &str,
+        limit: usize,
+    ) -> impl Future<Output = Vec<DatastarEvent>> + Send {
+        (**self).events_after(cursor, limit)
+    }
+}
+
+/// [`Backfill`] is a [`Journal`] which replays from a [`RingBuffer`] when possible, and
+/// falls back to a [`ReplaySource`] for clients which fell behind further than the buffer reaches.
+///
+/// Events are read from the source page by page, until an event kept in the buffer is reached,
+/// after which the remaining events are replayed from the buffer.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     DatastarEvent,
+///     prelude::PatchElements,
+///     replay::{Backfill, Journal, ReplayBuffer, ReplaySource, RingBuffer},
+/// };
+/// use std::sync::Arc;
+///
+/// /// Stands in for the table of an audit log.
+/// struct AuditLog(Vec<DatastarEvent>);
+///
+/// impl ReplaySource for AuditLog {
+///     async fn events_after(&self, cursor: &str, limit: usize) -> Vec<DatastarEvent> {
+///         match self.0.iter().position(|event| event.id.as_deref() == Some(cursor)) {
+///             Some(position) => self.0.iter().skip(position + 1).take(limit).cloned().collect(),
+///             None => Vec::new(),
+///         }
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let events: Vec<DatastarEvent> = (1..=10)
+///     .map(|n| PatchElements::new(format!("<li>Entry {n}</li>")).id(n.to_string()).into())
+///     .collect();
+///
+/// let buffer = Arc::new(RingBuffer::new(3));
+/// for event in &events {
+///     buffer.record(event);
+/// }
+///
+/// let journal = Backfill::new(buffer, AuditLog(events)).page_size(4);
+///
+/// let missed = journal.events_after("2").await;
+/// assert_eq!(missed.len(), 8);
+/// assert_eq!(missed[0].id.as_deref(), Some("3"));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Backfill<S> {
+    buffer: Arc<RingBuffer>,
+    source: S,
+    page_size: usize,
+    limit: usize,
+}
+
+impl<S: ReplaySource> Backfill<S> {
+    /// Creates a new [`Backfill`] replaying from `buffer`, and from `source` when the
+    /// buffer doesn't reach back far enough.
+    pub fn new(buffer: Arc<RingBuffer>, source: S) -> Self {
+        Self {
+            buffer,
+            source,
+            page_size: DEFAULT_PAGE_SIZE,
+            limit: usize::MAX,
+        }
+    }
+
+    /// Sets the number of events requested from the source at once,
+    /// defaults to [`DEFAULT_PAGE_SIZE`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is zero.
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        assert!(page_size > 0, "the page size must be greater than zero");
+        self.page_size = page_size;
+        self
+    }
+
+    /// Sets the maximum number of events replayed to a single client, which is unlimited by default.
+    ///
+    /// A client that missed more events only receives the oldest `limit` of them.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<S: ReplaySource> Journal for Backfill<S> {
+    fn events_after(&self, last_event_id: &str) -> impl Future<Output = Vec<DatastarEvent>> + Send {
+        let last_event_id = last_event_id.to_owned();
+        async move {
+            if self.buffer.contains(&last_event_id) {
+                let mut events = self.buffer.replay(&last_event_id);
+                events.truncate(self.limit);
+                return events;
+            }
+
+            let mut events = Vec::new();
+            let mut cursor = last_event_id;
+            loop {
+                let page = self.source.events_after(&cursor, self.page_size).await;
+                let is_last_page = page.len() < self.page_size;
+
+                for event in page {
+                    if let Some(id) = event.id.as_deref().filter(|id| self.buffer.contains(id)) {
+                        let id = id.to_owned();
+                        events.push(event);
+                        events.extend(self.buffer.replay(&id));
+                        events.truncate(self.limit);
+                        return events;
+                    }
+                    events.push(event);
+                    if events.len() >= self.limit {
+                        return events;
+                    }
+                }
+
+                match events.last().and_then(|event| event.id.clone()) {
+                    Some(id) if !is_last_page => cursor = id.into_owned(),
+                    _ => return events,
+                }
+            }
+        }
+    }
+}
+
+/// Serializes the given events into a single SSE payload, ready to be prepended to a response body.
+#[doc(hidden)]
+pub fn frame_events(events: &[DatastarEvent]) -> String {
+    let mut framed = String::new();
+    for event in events {
+        // Writing to a `String` never fails.
+        let _ = event.write_fmt_to(&mut framed);
+    }
+    framed
+}
@@ -0,0 +1,249 @@
+//! [`IntoDatastarResponse`] allows returning Datastar events from a handler,
+//! regardless of the framework in use.
+//!
+//! Every framework integration provides a `DatastarResponse` wrapper which turns any
+//! [`IntoDatastarResponse`] into a response of that framework. [`PatchElements`],
+//! [`PatchSignals`], [`ExecuteScript`] and [`DatastarEvent`] can also be returned directly.
+//!
+//! [`PatchElements`]: crate::patch_elements::PatchElements
+//! [`PatchSignals`]: crate::patch_signals::PatchSignals
+//! [`ExecuteScript`]: crate::execute_script::ExecuteScript
+
+use {
+    crate::{
+        DatastarEvent, batch::DatastarEventBatch, execute_script::ExecuteScript,
+        patch_elements::PatchElements, patch_signals::PatchSignals, sender::Transaction,
+    },
+    core::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    futures_core::Stream,
+    std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex, PoisonError},
+    },
+};
+
+/// [`IntoDatastarResponse`] is implemented by everything that can be sent to the browser
+/// as a stream of Datastar events.
+///
+/// This allows helper libraries to be written once, without depending on a specific framework.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     prelude::{PatchElements, PatchSignals},
+///     response::IntoDatastarResponse,
+/// };
+///
+/// fn greet(name: &str) -> impl IntoDatastarResponse {
+///     PatchElements::new(format!("<div id='greeting'>Hello, {name}!</div>"))
+/// }
+///
+/// fn reset() -> impl IntoDatastarResponse {
+///     PatchSignals::new("{count: 0}")
+/// }
+/// ```
+pub trait IntoDatastarResponse {
+    /// The [`Stream`] of events sent to the browser.
+    type Stream: Stream<Item = DatastarEvent> + Send + 'static;
+
+    /// Converts this value into a [`Stream`] of [`DatastarEvent`]s.
+    fn into_datastar_stream(self) -> Self::Stream;
+}
+
+impl IntoDatastarResponse for DatastarEvent {
+    type Stream = Once;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        Once(Some(self))
+    }
+}
+
+/// Sends the script of the [`ViewTransition`](crate::patch_elements::ViewTransition) of the
+/// [`PatchElements`] before it, if it has one.
+impl IntoDatastarResponse for PatchElements {
+    type Stream = Batch;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        Transaction::new().patch_elements(self).into()
+    }
+}
+
+impl IntoDatastarResponse for PatchSignals {
+    type Stream = Once;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        Once(Some(self.into_datastar_event()))
+    }
+}
+
+impl IntoDatastarResponse for ExecuteScript {
+    type Stream = Once;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        Once(Some(self.into_datastar_event()))
+    }
+}
+
+/// A [`Stream`] which yields a single [`DatastarEvent`].
+#[derive(Debug)]
+pub struct Once(Option<DatastarEvent>);
+
+impl Stream for Once {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.0.take())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = usize::from(self.0.is_some());
+        (len, Some(len))
+    }
+}
+
+/// [`DatastarStream`] wraps a [`Stream`] of [`DatastarEvent`]s so it can be returned as a response.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     DatastarEvent,
+///     prelude::PatchElements,
+///     response::{DatastarStream, IntoDatastarResponse},
+/// };
+/// use tokio_stream::{self as stream, StreamExt};
+///
+/// fn countdown() -> impl IntoDatastarResponse {
+///     DatastarStream(
+///         stream::iter((0..3).rev())
+///             .map(|n| PatchElements::new(format!("<div id='count'>{n}</div>")).into()),
+///     )
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DatastarStream<S>(pub S);
+
+impl<S> IntoDatastarResponse for DatastarStream<S>
+where
+    S: Stream<Item = DatastarEvent> + Send + 'static,
+{
+    type Stream = S;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self.0
+    }
+}
+
+/// Runs `f` with a [`Collector`] and returns the events it sent as a finite [`Batch`].
+///
+/// This is the simplest way to respond to an action, such as `data-on:click="@post('/save')"`,
+/// without setting up a stream. The [`Batch`] can be returned from a handler of every framework
+/// integration.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     prelude::{PatchElements, PatchSignals},
+///     response::{Batch, respond},
+/// };
+///
+/// async fn save() -> Batch {
+///     respond(|sse| async move {
+///         sse.patch_signals(PatchSignals::new(r#"{"saving": false}"#)).await;
+///         sse.patch_elements(PatchElements::new("<div id='status'>Saved</div>")).await;
+///     })
+///     .await
+/// }
+/// ```
+pub async fn respond<F, Fut>(f: F) -> Batch
+where
+    F: FnOnce(Collector) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let collector = Collector::default();
+    f(collector.clone()).await;
+    Batch(collector.take().into())
+}
+
+/// [`Collector`] collects the events sent within [`respond`].
+#[derive(Debug, Clone, Default)]
+pub struct Collector(Arc<Mutex<Vec<DatastarEvent>>>);
+
+impl Collector {
+    fn take(&self) -> Vec<DatastarEvent> {
+        core::mem::take(&mut self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Adds any event to the response.
+    pub async fn send(&self, event: impl Into<DatastarEvent>) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(event.into());
+    }
+
+    /// Adds a [`PatchElements`] event to the response, after the script of its
+    /// [`ViewTransition`](crate::patch_elements::ViewTransition) if it has one.
+    pub async fn patch_elements(&self, patch_elements: PatchElements) {
+        let events = Transaction::new()
+            .patch_elements(patch_elements)
+            .into_events();
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend(events);
+    }
+
+    /// Adds a [`PatchSignals`] event to the response.
+    pub async fn patch_signals(&self, patch_signals: PatchSignals) {
+        self.send(patch_signals).await;
+    }
+
+    /// Adds an [`ExecuteScript`] event to the response.
+    pub async fn execute_script(&self, execute_script: ExecuteScript) {
+        self.send(execute_script).await;
+    }
+}
+
+/// [`Batch`] is a finite [`Stream`] of the events collected by [`respond`],
+/// or of the events of a [`Transaction`].
+#[derive(Debug)]
+pub struct Batch(VecDeque<DatastarEvent>);
+
+impl Stream for Batch {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.0.pop_front())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len(), Some(self.0.len()))
+    }
+}
+
+impl From<Transaction> for Batch {
+    fn from(transaction: Transaction) -> Self {
+        Self(transaction.into_events().into())
+    }
+}
+
+impl From<DatastarEventBatch> for Batch {
+    fn from(batch: DatastarEventBatch) -> Self {
+        Self(batch.into_events().into())
+    }
+}
+
+impl IntoDatastarResponse for Batch {
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
@@ -0,0 +1,922 @@
+//! Rocket integration for Datastar.
+
+#[cfg(feature = "inspector")]
+use {
+    crate::inspector::{self, Inspection, Inspector},
+    core::{
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    rocket::tokio::io::{AsyncRead, ReadBuf},
+};
+use {
+    crate::{
+        DatastarEvent, Error,
+        accept::accepts_event_stream,
+        batch::{self, BatchExt, DatastarEventBatch},
+        compression,
+        consts::DATASTAR_REQ_HEADER_STR,
+        fragment::FragmentResponse,
+        heartbeat::HeartbeatExt,
+        json,
+        prelude::{ExecuteScript, PatchElements, PatchSignals},
+        query,
+        replay::{self, Journal, LastEventId},
+        response::{Batch, IntoDatastarResponse},
+        sender::DatastarReceiver,
+        signals::{self, InvalidSignal},
+        sse_payload::{SsePayload, ToSsePayload},
+    },
+    core::time::Duration,
+    rocket::{
+        Catcher, Request, Response,
+        catcher::BoxFuture,
+        data::{ByteUnit, Data, FromData, Limits},
+        fairing::{Fairing, Info, Kind},
+        futures::StreamExt,
+        http::{ContentType, Method, Status},
+        request::{FromRequest, Outcome},
+        response::{
+            self, Responder,
+            stream::{Event, ReaderStream},
+        },
+        tokio::io::AsyncReadExt,
+    },
+    serde::de::DeserializeOwned,
+    std::io::Cursor,
+};
+
+/// Datastar's headers, re-exported from [`consts::headers`](crate::consts::headers).
+pub mod header {
+    pub use crate::consts::headers::*;
+}
+
+/// The interval of the keep-alive comments sent by [`DatastarResponse`],
+/// matching the default of a Rocket [`EventStream`](rocket::response::stream::EventStream).
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+impl PatchElements {
+    /// Write this [`PatchElements`] into a Rocket SSE [`Event`].
+    pub fn write_as_rocket_sse_event(&self) -> Event {
+        sse_event(self.sse_payload())
+    }
+}
+
+impl From<PatchElements> for Event {
+    fn from(value: PatchElements) -> Self {
+        value.write_as_rocket_sse_event()
+    }
+}
+
+impl From<&PatchElements> for Event {
+    fn from(value: &PatchElements) -> Self {
+        value.write_as_rocket_sse_event()
+    }
+}
+
+impl PatchSignals {
+    /// Write this [`PatchSignals`] into a Rocket SSE [`Event`].
+    pub fn write_as_rocket_sse_event(&self) -> Event {
+        sse_event(self.sse_payload())
+    }
+}
+
+impl From<PatchSignals> for Event {
+    fn from(value: PatchSignals) -> Self {
+        value.write_as_rocket_sse_event()
+    }
+}
+
+impl From<&PatchSignals> for Event {
+    fn from(value: &PatchSignals) -> Self {
+        value.write_as_rocket_sse_event()
+    }
+}
+
+impl ExecuteScript {
+    /// Write this [`ExecuteScript`] into a Rocket SSE [`Event`].
+    pub fn write_as_rocket_sse_event(&self) -> Event {
+        sse_event(self.sse_payload())
+    }
+}
+
+impl From<ExecuteScript> for Event {
+    fn from(value: ExecuteScript) -> Self {
+        value.write_as_rocket_sse_event()
+    }
+}
+
+impl From<&ExecuteScript> for Event {
+    fn from(value: &ExecuteScript) -> Self {
+        value.write_as_rocket_sse_event()
+    }
+}
+
+impl DatastarEvent {
+    /// Turn this [`DatastarEvent`] into a Rocket SSE [`Event`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event has custom [`fields`](DatastarEvent::fields), which a Rocket
+    /// [`Event`] can't hold, rather than dropping them. [`DatastarResponse`] writes them.
+    pub fn write_as_rocket_sse_event(&self) -> Event {
+        let payload = self.sse_payload();
+        payload.assert_fits("Rocket", usize::MAX);
+        sse_event(payload)
+    }
+}
+
+/// Builds the Rocket [`Event`] of an [`SsePayload`], which has no custom fields.
+///
+/// A Rocket [`Event`] holds a single comment, which it writes as a comment line for each of
+/// its lines, so the comments are joined into one.
+fn sse_event(payload: SsePayload<'_>) -> Event {
+    let mut event = match payload.data {
+        Some(data) => Event::data(data),
+        None => Event::empty(),
+    };
+
+    if let Some(retry) = payload.retry {
+        event = event.with_retry(retry);
+    }
+    // The standard event types are static, so only custom ones are copied.
+    event = match (payload.custom_event, payload.event_type) {
+        (Some(custom_event), _) => event.event(custom_event.to_owned()),
+        (None, Some(event_type)) => event.event(event_type.as_static_str()),
+        (None, None) => event,
+    };
+    if !payload.comments.is_empty() {
+        event = event.with_comment(payload.comments.join("\n"));
+    }
+
+    match payload.id {
+        Some(id) => event.id(id.clone()),
+        None => event,
+    }
+}
+
+impl From<DatastarEvent> for Event {
+    fn from(value: DatastarEvent) -> Self {
+        value.write_as_rocket_sse_event()
+    }
+}
+
+impl From<&DatastarEvent> for Event {
+    fn from(value: &DatastarEvent) -> Self {
+        value.write_as_rocket_sse_event()
+    }
+}
+
+/// [`DatastarResponse`] turns any [`IntoDatastarResponse`] into a Rocket SSE response.
+///
+/// The events which are ready together, such as those of a [`Transaction`](crate::sender::Transaction),
+/// are written as one chunk with [`BatchExt::ready_batches`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     prelude::PatchElements, response::IntoDatastarResponse, rocket::DatastarResponse,
+/// };
+/// use rocket::get;
+///
+/// fn greet(name: &str) -> impl IntoDatastarResponse {
+///     PatchElements::new(format!("<div id='greeting'>Hello, {name}!</div>"))
+/// }
+///
+/// #[get("/greet")]
+/// fn handler() -> DatastarResponse<impl IntoDatastarResponse> {
+///     DatastarResponse(greet("world"))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DatastarResponse<T>(pub T);
+
+impl<'r, T: IntoDatastarResponse> Responder<'r, 'r> for DatastarResponse<T> {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'r> {
+        let stream = self
+            .0
+            .into_datastar_stream()
+            .with_heartbeat(HEARTBEAT_INTERVAL)
+            .ready_batches()
+            .map(|batch| Cursor::new(batch.to_bytes()));
+        Response::build()
+            .header(ContentType::EventStream)
+            .raw_header("Cache-Control", "no-cache")
+            .raw_header("Expires", "0")
+            .streamed_body(ReaderStream::from(stream))
+            .ok()
+    }
+}
+
+impl<'r> Responder<'r, 'r> for PatchElements {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+impl<'r> Responder<'r, 'r> for PatchSignals {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+impl<'r> Responder<'r, 'r> for ExecuteScript {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+impl<'r> Responder<'r, 'r> for DatastarEvent {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+impl<'r> Responder<'r, 'r> for Batch {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+/// Responds with the events as a single `text/event-stream` body rather than a stream,
+/// closing the connection once it's sent.
+impl<'r> Responder<'r, 'r> for DatastarEventBatch {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'r> {
+        let body = self.to_bytes();
+        let mut response = Response::build();
+        for (name, value) in batch::RESPONSE_HEADERS {
+            response.raw_header(name, value);
+        }
+        response.sized_body(body.len(), Cursor::new(body)).ok()
+    }
+}
+
+impl<'r> Responder<'r, 'r> for DatastarReceiver {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+impl<'r, S> Responder<'r, 'r> for crate::indicator::Indicator<S>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+{
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+impl<'r, S, G> Responder<'r, 'r> for crate::event_id::WithEventIds<S, G>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    G: crate::event_id::EventIdGenerator,
+{
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+impl<'r, S, C> Responder<'r, 'r> for crate::stall::Stall<S, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+#[cfg(feature = "broadcast")]
+impl<'r> Responder<'r, 'r> for crate::broadcast::Subscription {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+impl<'r, S, C> Responder<'r, 'r> for crate::heartbeat::Heartbeat<S, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'r, S, C> Responder<'r, 'r> for crate::coalesce::CoalesceSignals<S, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'r, S, W, C> Responder<'r, 'r> for crate::recording::Recorder<S, W, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    W: std::io::Write + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'r, C: crate::clock::Clock> Responder<'r, 'r> for crate::recording::Player<C> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+#[cfg(feature = "broadcast")]
+impl<'r> Responder<'r, 'r> for crate::broadcast::HubMetrics {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'r> {
+        let body = self.to_string();
+        Response::build()
+            .raw_header("Content-Type", crate::broadcast::PROMETHEUS_CONTENT_TYPE)
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
+
+#[cfg(feature = "inspector")]
+impl<'r> Responder<'r, 'r> for crate::inspector::InspectorPanel {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+#[cfg(feature = "inspector")]
+impl<'r, S> Responder<'r, 'r> for crate::inspector::Inspected<S>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+{
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+#[cfg(feature = "redis")]
+impl<'r> Responder<'r, 'r> for crate::redis::RedisSubscription {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        DatastarResponse(self).respond_to(req)
+    }
+}
+
+/// [`ReplayFairing`] prepends the events a client missed to every SSE response,
+/// whenever the request carries a `Last-Event-ID` header.
+///
+/// Handlers don't need to be aware of the replay at all, which makes any existing
+/// [`EventStream`](rocket::response::stream::EventStream) resumable by attaching the fairing.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{DatastarEvent, replay::Journal, rocket::ReplayFairing};
+///
+/// struct NoJournal;
+///
+/// impl Journal for NoJournal {
+///     async fn events_after(&self, _last_event_id: &str) -> Vec<DatastarEvent> {
+///         Vec::new()
+///     }
+/// }
+///
+/// let rocket = rocket::build().attach(ReplayFairing::new(NoJournal));
+/// ```
+#[derive(Debug)]
+pub struct ReplayFairing<J> {
+    journal: J,
+}
+
+impl<J> ReplayFairing<J> {
+    /// Creates a new [`ReplayFairing`] which replays events from the given [`Journal`].
+    pub fn new(journal: J) -> Self {
+        Self { journal }
+    }
+}
+
+#[rocket::async_trait]
+impl<J: Journal> Fairing for ReplayFairing<J> {
+    fn info(&self) -> Info {
+        Info {
+            name: "Datastar Last-Event-ID replay",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Some(last_event_id) = req.headers().get_one(replay::LAST_EVENT_ID_HEADER_STR) else {
+            return;
+        };
+
+        if res.content_type() != Some(ContentType::EventStream) {
+            return;
+        }
+
+        let missed = self.journal.events_after(last_event_id).await;
+        if missed.is_empty() {
+            return;
+        }
+
+        let prefix = Cursor::new(replay::frame_events(&missed));
+        let body = res.body_mut().take();
+        res.set_streamed_body(prefix.chain(body));
+    }
+}
+
+/// [`InspectorFairing`] logs every event of the SSE responses with `tracing`, and mirrors them
+/// to the live panels of its [`Inspector`].
+///
+/// The responses of [`INSPECTOR_PATH`](inspector::INSPECTOR_PATH) aren't inspected.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     inspector::{Inspector, InspectorPanel},
+///     rocket::InspectorFairing,
+/// };
+/// use rocket::{State, get, routes};
+///
+/// #[get("/_datastar/inspector")]
+/// fn panel(inspector: &State<Inspector>) -> InspectorPanel {
+///     inspector.panel()
+/// }
+///
+/// let inspector = Inspector::new();
+/// let rocket = rocket::build()
+///     .attach(InspectorFairing::new(inspector.clone()))
+///     .manage(inspector)
+///     .mount("/", routes![panel]);
+/// ```
+#[cfg(feature = "inspector")]
+#[derive(Debug, Default)]
+pub struct InspectorFairing {
+    inspector: Inspector,
+}
+
+#[cfg(feature = "inspector")]
+impl InspectorFairing {
+    /// Creates a new [`InspectorFairing`] which mirrors the events to the panels of `inspector`.
+    pub fn new(inspector: Inspector) -> Self {
+        Self { inspector }
+    }
+}
+
+#[cfg(feature = "inspector")]
+#[rocket::async_trait]
+impl Fairing for InspectorFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Datastar inspector",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let path = req.uri().path();
+        if path == inspector::INSPECTOR_PATH || res.content_type() != Some(ContentType::EventStream)
+        {
+            return;
+        }
+
+        let body = res.body_mut().take();
+        res.set_streamed_body(InspectedBody {
+            body,
+            inspection: self.inspector.inspect(path.as_str()),
+        });
+    }
+}
+
+/// The body of a response inspected by the [`InspectorFairing`].
+#[cfg(feature = "inspector")]
+struct InspectedBody<R> {
+    body: R,
+    inspection: Inspection,
+}
+
+#[cfg(feature = "inspector")]
+impl<R: AsyncRead + Unpin> AsyncRead for InspectedBody<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let filled = buf.filled().len();
+        let this = &mut *self;
+        futures_core::ready!(Pin::new(&mut this.body).poll_read(cx, buf))?;
+        this.inspection.observe(&buf.filled()[filled..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// [`AcceptsEventStream`] is a request guard which only succeeds if the request accepts
+/// `text/event-stream`.
+///
+/// Otherwise the request is forwarded with `406 Not Acceptable`, which allows a route with
+/// a higher rank to serve a non-streaming snapshot instead.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{prelude::PatchElements, rocket::AcceptsEventStream};
+/// use rocket::{get, response::content::RawHtml};
+///
+/// #[get("/counter")]
+/// fn counter(_accepts: AcceptsEventStream) -> PatchElements {
+///     PatchElements::new("<div id='counter'>0</div>")
+/// }
+///
+/// #[get("/counter", rank = 2)]
+/// fn counter_snapshot() -> RawHtml<&'static str> {
+///     RawHtml("<div id='counter'>0</div>")
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct AcceptsEventStream;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptsEventStream {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        if req.headers().get("accept").any(accepts_event_stream) {
+            Outcome::Success(Self)
+        } else {
+            Outcome::Forward(Status::NotAcceptable)
+        }
+    }
+}
+
+/// Extracts the [`LastEventId`] from the `Last-Event-ID` header.
+///
+/// Forwards with `400 Bad Request` if the header is missing,
+/// use `Option<LastEventId>` for streams which can also be started from scratch.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::replay::LastEventId;
+/// use rocket::get;
+///
+/// #[get("/feed")]
+/// fn feed(last_event_id: Option<LastEventId>) -> String {
+///     match last_event_id {
+///         Some(id) => format!("resuming after {id}"),
+///         None => "starting a new stream".to_owned(),
+///     }
+/// }
+/// ```
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match req.headers().get_one(replay::LAST_EVENT_ID_HEADER_STR) {
+            Some(value) => Outcome::Success(Self(value.to_owned())),
+            None => Outcome::Forward(Status::BadRequest),
+        }
+    }
+}
+
+/// Responds with the status code of [`Error::status`] and the error message as plain text.
+impl<'r> Responder<'r, 'static> for Error {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let status = Status::from_code(self.status()).unwrap_or(Status::InternalServerError);
+        (status, self.to_string()).respond_to(req)
+    }
+}
+
+/// Responds with the elements as HTML and the headers telling Datastar how to patch them, or
+/// with the error of an invalid [`FragmentResponse`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{consts::ElementPatchMode, fragment::FragmentResponse};
+/// use rocket::post;
+///
+/// #[post("/like")]
+/// fn like() -> FragmentResponse {
+///     FragmentResponse::new("<span>12 likes</span>")
+///         .selector("#likes")
+///         .mode(ElementPatchMode::Inner)
+/// }
+/// ```
+impl<'r> Responder<'r, 'static> for FragmentResponse {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        if let Err(err) = self.validate() {
+            return err.respond_to(req);
+        }
+
+        let mut response = Response::build();
+        response.header(ContentType::HTML);
+        for (name, value) in self.headers() {
+            response.raw_header(name, value.to_owned());
+        }
+        let elements = self.elements.into_owned();
+        response
+            .sized_body(elements.len(), Cursor::new(elements))
+            .ok()
+    }
+}
+
+/// [`DatastarRequest`] is a data guard that surfaces the Datastar specific metadata
+/// of a request, so handlers can branch on it without parsing the request twice.
+///
+/// The signals are kept as raw JSON and the extraction does not fail for requests
+/// which were not sent by Datastar. The body is limited like that of [`ReadSignals`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::rocket::DatastarRequest;
+/// use rocket::post;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     delay: u64,
+/// }
+///
+/// #[post("/hello", data = "<req>")]
+/// fn hello(req: DatastarRequest) -> String {
+///     if !req.is_datastar {
+///         return "full page".to_owned();
+///     }
+///     match req.signals_as::<Signals>() {
+///         Ok(Some(signals)) => format!("delay: {}", signals.delay),
+///         _ => "fragment".to_owned(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DatastarRequest {
+    /// Whether the request carries the `datastar-request` header.
+    pub is_datastar: bool,
+    /// The HTTP method of the request.
+    pub method: Method,
+    /// The signals sent with the request, if any.
+    ///
+    /// For `GET` requests these are read from the `datastar` query parameter,
+    /// for all other methods from the JSON body.
+    pub signals: Option<serde_json::Value>,
+    /// The `Last-Event-ID` header of the request, if any.
+    pub last_event_id: Option<LastEventId>,
+}
+
+impl DatastarRequest {
+    /// Deserializes the raw [`DatastarRequest::signals`] into `T`.
+    pub fn signals_as<T: DeserializeOwned>(&self) -> Result<Option<T>, Error> {
+        Ok(self.signals.as_ref().map(T::deserialize).transpose()?)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for DatastarRequest {
+    type Error = Error;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> rocket::data::Outcome<'r, Self> {
+        let is_datastar = req.headers().contains(DATASTAR_REQ_HEADER_STR);
+        let last_event_id = req
+            .headers()
+            .get_one(replay::LAST_EVENT_ID_HEADER_STR)
+            .map(|id| LastEventId(id.to_owned()));
+        let method = req.method();
+
+        let signals = match method {
+            Method::Get => match req.query_value::<&str>("datastar") {
+                Some(Ok(signals)) => Some(json::signals_from_slice(signals.as_bytes())),
+                _ => None,
+            },
+            _ => {
+                let body = match read_body(req, data).await {
+                    Ok(body) => body,
+                    Err(err) => return fail(req, err),
+                };
+                let content_type = req.content_type().map(ToString::to_string);
+                (!body.is_empty()).then(|| json::signals_from_body(content_type.as_deref(), &body))
+            }
+        };
+
+        match signals.transpose() {
+            Ok(signals) => rocket::data::Outcome::Success(Self {
+                is_datastar,
+                method,
+                signals,
+                last_event_id,
+            }),
+            Err(err) => fail(req, err),
+        }
+    }
+}
+
+/// [`ReadSignals`] is a data guard that deserializes the signals of a request into `T`.
+///
+/// As a request guard it reads the signals from the `datastar` query parameter, for `GET`
+/// routes. As a data guard it reads them from the query parameter of `GET` requests and
+/// from a JSON or URL encoded form body otherwise. The body is limited by the `json` or `form`
+/// limit of the Rocket configuration, such as `limits.json` in `Rocket.toml`.
+///
+/// A request without signals, or with signals which don't deserialize into `T`, fails with the
+/// status of the [`Error`], which is handled by the catchers of [`signals_catchers`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::rocket::{ReadSignals, signals_catchers};
+/// use rocket::{get, post, routes};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     delay: u64,
+/// }
+///
+/// #[get("/hello")]
+/// fn hello(signals: ReadSignals<Signals>) -> String {
+///     format!("delay: {}", signals.0.delay)
+/// }
+///
+/// #[post("/hello", data = "<signals>")]
+/// fn save(signals: ReadSignals<Signals>) -> String {
+///     format!("delay: {}", signals.0.delay)
+/// }
+///
+/// let rocket = rocket::build()
+///     .mount("/", routes![hello, save])
+///     .register("/", signals_catchers());
+/// ```
+#[derive(Debug)]
+pub struct ReadSignals<T>(pub T);
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromData<'r> for ReadSignals<T> {
+    type Error = Error;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> rocket::data::Outcome<'r, Self> {
+        let limit = body_limit(req);
+        let body = match req.method() {
+            Method::Get => Vec::new(),
+            _ => match read_raw_body(data, limit).await {
+                Ok(body) => body,
+                Err(err) => return fail(req, err),
+            },
+        };
+
+        let headers = ["content-type", "content-encoding"]
+            .into_iter()
+            .flat_map(|name| req.headers().get(name).map(move |value| (name, value)));
+        let query = req.uri().query().map(|query| query.as_str());
+        match signals::from_parts_with_limit(
+            req.method().as_str(),
+            query,
+            headers,
+            &body,
+            decoded_limit(limit),
+        ) {
+            Ok(signals) => rocket::data::Outcome::Success(Self(signals)),
+            Err(err) => fail(req, err),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned> FromRequest<'r> for ReadSignals<T> {
+    type Error = Error;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match query_signals(req) {
+            Ok(signals) => Outcome::Success(Self(signals)),
+            Err(err) => fail(req, err),
+        }
+    }
+}
+
+/// Deserializes the signals of the `datastar` query parameter of `req`.
+fn query_signals<T: DeserializeOwned>(req: &Request<'_>) -> Result<T, Error> {
+    query::from_query(req.uri().query().map_or("", |query| query.as_str()))
+}
+
+/// Reads and decodes the body of `req`, within the `form` limit for URL encoded forms
+/// and the `json` limit otherwise.
+async fn read_body(req: &Request<'_>, data: Data<'_>) -> Result<Vec<u8>, Error> {
+    let limit = body_limit(req);
+    let body = read_raw_body(data, limit).await?;
+    let content_encoding = req.headers().get_one("content-encoding");
+    let decoded = compression::decode_body(content_encoding, &body, decoded_limit(limit))?;
+    Ok(decoded.unwrap_or(body))
+}
+
+/// Returns the `form` limit for URL encoded forms and the `json` limit otherwise.
+fn body_limit(req: &Request<'_>) -> ByteUnit {
+    match req.content_type() {
+        Some(content_type) if content_type.is_form() => {
+            req.limits().get("form").unwrap_or(Limits::FORM)
+        }
+        _ => req.limits().get("json").unwrap_or(Limits::JSON),
+    }
+}
+
+/// Returns the limit on the decoded size of a body, which is the limit on its encoded size.
+fn decoded_limit(limit: ByteUnit) -> usize {
+    usize::try_from(limit.as_u64()).unwrap_or(usize::MAX)
+}
+
+/// Reads the body of a request as it was sent, failing if it exceeds `limit`.
+async fn read_raw_body(data: Data<'_>, limit: ByteUnit) -> Result<Vec<u8>, Error> {
+    match data.open(limit).into_bytes().await {
+        Ok(body) if body.is_complete() => Ok(body.into_inner()),
+        Ok(_) => Err(Error::PayloadTooLarge),
+        Err(err) => Err(Error::Body(Box::new(err))),
+    }
+}
+
+/// Fails a guard with `err`, keeping the [`SignalsFailure`] for the catchers.
+fn fail<T, F>(req: &Request<'_>, err: Error) -> rocket::outcome::Outcome<T, (Status, Error), F> {
+    let failure = SignalsFailure::from(&err);
+    req.local_cache(|| Some(failure));
+    rocket::outcome::Outcome::Error((Status::new(err.status()), err))
+}
+
+/// [`SignalsFailure`] is why a signal guard failed, kept in the request-local cache
+/// so catchers can respond with more than the status.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::rocket::SignalsFailure;
+/// use rocket::{Request, catch, http::Status};
+///
+/// #[catch(422)]
+/// fn invalid(status: Status, req: &Request) -> String {
+///     let Some(failure) = SignalsFailure::of(req) else {
+///         return status.reason_lossy().to_owned();
+///     };
+///     failure
+///         .signals
+///         .iter()
+///         .map(|signal| format!("{}: {}\n", signal.path, signal.message))
+///         .collect()
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignalsFailure {
+    /// The status code of the response, as returned by [`Error::status`].
+    pub status: u16,
+    /// The message of the [`Error`].
+    pub message: String,
+    /// The signals which failed to deserialize or validate, if the error is about signals.
+    pub signals: Vec<InvalidSignal>,
+}
+
+impl SignalsFailure {
+    /// Returns the [`SignalsFailure`] of the request, if a signal guard failed.
+    pub fn of<'r>(req: &'r Request<'_>) -> Option<&'r Self> {
+        req.local_cache(|| None::<Self>).as_ref()
+    }
+}
+
+impl From<&Error> for SignalsFailure {
+    fn from(err: &Error) -> Self {
+        let signals = match err {
+            Error::InvalidSignals { path, message } => vec![InvalidSignal {
+                path: path.clone(),
+                message: message.clone(),
+            }],
+            Error::Validation(errors) => errors.errors().to_vec(),
+            _ => Vec::new(),
+        };
+        Self {
+            status: err.status(),
+            message: err.to_string(),
+            signals,
+        }
+    }
+}
+
+/// Returns catchers for the client errors of the signal guards, which respond with the
+/// message of the [`SignalsFailure`] as plain text, like the [`Error`] responder.
+///
+/// Requests which failed for another reason get the reason of the status.
+pub fn signals_catchers() -> Vec<Catcher> {
+    [400, 413, 415, 422]
+        .into_iter()
+        .map(|code| Catcher::new(code, catch_signals))
+        .collect()
+}
+
+fn catch_signals<'r>(status: Status, req: &'r Request<'_>) -> BoxFuture<'r> {
+    let message = match SignalsFailure::of(req) {
+        Some(failure) => failure.message.clone(),
+        None => status.reason_lossy().to_owned(),
+    };
+    Box::pin(async move { (status, message).respond_to(req) })
+}
@@ -0,0 +1,86 @@
+//! Sanitization of [`PatchElements`] with an [`ammonia`] allow-list policy, with the
+//! `ammonia` feature.
+//!
+//! Elements which include user-generated HTML, such as the comments or chat messages of an
+//! activity feed, are sanitized one by one with [`PatchElements::sanitized`]. An application
+//! can also opt into sanitizing every outgoing [`PatchElements`] event with
+//! [`set_global_policy`], which runs the policy when the event is written.
+//!
+//! The default policy of [`ammonia::Builder`] removes the `id` and the `data-*` attributes
+//! Datastar relies on, so they have to be allowed when the elements are morphed by id or
+//! carry Datastar attributes.
+
+use {
+    crate::patch_elements::{Elements, PatchElements},
+    ammonia::Builder,
+    std::{borrow::Cow, sync::OnceLock},
+};
+
+static GLOBAL_POLICY: OnceLock<Builder<'static>> = OnceLock::new();
+
+/// Sets the policy which sanitizes the elements of every [`PatchElements`] event when it
+/// is written, for the rest of the process.
+///
+/// The policy can only be set once, so this returns `false` and drops `policy` if it was
+/// already set.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{DatastarEvent, prelude::PatchElements, sanitize};
+///
+/// let mut policy = ammonia::Builder::default();
+/// policy.add_generic_attributes(["id"]);
+/// assert!(sanitize::set_global_policy(policy));
+///
+/// let event = DatastarEvent::from(PatchElements::new(
+///     "<li id='comment-1'>Nice!<img src=x onerror=alert(1)></li>",
+/// ));
+///
+/// assert_eq!(event.data, [r#"elements <li id="comment-1">Nice!<img src="x"></li>"#]);
+/// ```
+pub fn set_global_policy(policy: Builder<'static>) -> bool {
+    GLOBAL_POLICY.set(policy).is_ok()
+}
+
+/// Returns the policy set with [`set_global_policy`], if any.
+pub fn global_policy() -> Option<&'static Builder<'static>> {
+    GLOBAL_POLICY.get()
+}
+
+/// Sanitizes `html` with the global policy, if one was set.
+pub(crate) fn apply_global_policy(html: &str) -> Cow<'_, str> {
+    match global_policy() {
+        Some(policy) if !html.is_empty() => Cow::Owned(policy.clean(html).to_string()),
+        _ => Cow::Borrowed(html),
+    }
+}
+
+impl Elements {
+    /// Creates [`Elements`] from untrusted HTML, removing the elements and attributes
+    /// which aren't allowed by `policy`.
+    pub fn sanitized(html: impl AsRef<str>, policy: &Builder<'_>) -> Self {
+        Self::from(policy.clean(html.as_ref()).to_string())
+    }
+}
+
+impl PatchElements {
+    /// Creates a new [`PatchElements`] event with untrusted HTML, removing the elements
+    /// and attributes which aren't allowed by `policy`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::prelude::PatchElements;
+    ///
+    /// let comment = "<p onclick='steal()'>Great <b>post</b>!<script>steal()</script></p>";
+    /// let patch = PatchElements::sanitized(comment, &ammonia::Builder::default())
+    ///     .selector("#comments")
+    ///     .mode(datastar_core::consts::ElementPatchMode::Append);
+    ///
+    /// assert_eq!(patch.elements.as_deref(), Some("<p>Great <b>post</b>!</p>"));
+    /// ```
+    pub fn sanitized(html: impl AsRef<str>, policy: &Builder<'_>) -> Self {
+        Self::new(Elements::sanitized(html, policy))
+    }
+}
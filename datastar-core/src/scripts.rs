@@ -0,0 +1,102 @@
+//! Prebuilt [`ExecuteScript`] events for common browser operations.
+//!
+//! Every argument is embedded as a JSON string literal, so it can't break out of the script.
+//!
+//! # Examples
+//!
+//! ```
+//! use datastar_core::scripts;
+//!
+//! let script = scripts::set_title(r#"3 new "messages""#);
+//!
+//! assert_eq!(script.script, r#"document.title = "3 new \"messages\"""#);
+//! ```
+
+use crate::{execute_script::ExecuteScript, patch_signals::write_json_string};
+
+/// Navigates the browser to `url`.
+///
+/// The navigation is deferred with `setTimeout`, so the events sent before it are applied first.
+pub fn redirect(url: &str) -> ExecuteScript {
+    ExecuteScript::new(format!(
+        "setTimeout(() => window.location.href = {})",
+        json_string(url)
+    ))
+}
+
+/// Reloads the current page.
+pub fn reload() -> ExecuteScript {
+    ExecuteScript::new("window.location.reload()")
+}
+
+/// Logs `message` to the browser console with `console.log`.
+pub fn console_log(message: &str) -> ExecuteScript {
+    console("log", message)
+}
+
+/// Logs `message` to the browser console with `console.warn`.
+pub fn console_warn(message: &str) -> ExecuteScript {
+    console("warn", message)
+}
+
+/// Logs `message` to the browser console with `console.error`.
+pub fn console_error(message: &str) -> ExecuteScript {
+    console("error", message)
+}
+
+fn console(level: &str, message: &str) -> ExecuteScript {
+    ExecuteScript::new(format!("console.{level}({})", json_string(message)))
+}
+
+/// Dispatches a bubbling [`CustomEvent`](https://developer.mozilla.org/en-US/docs/Web/API/CustomEvent)
+/// named `name` on the `document`, with `detail` serialized to JSON.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::scripts;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Saved {
+///     id: u64,
+/// }
+///
+/// let script = scripts::dispatch_event("saved", &Saved { id: 7 })?;
+///
+/// assert_eq!(
+///     script.script,
+///     r#"document.dispatchEvent(new CustomEvent("saved", {bubbles: true, cancelable: true, composed: true, detail: {"id":7}}))"#,
+/// );
+/// # Ok::<(), datastar_core::Error>(())
+/// ```
+#[cfg(feature = "serde")]
+pub fn dispatch_event(
+    name: &str,
+    detail: &impl serde::Serialize,
+) -> Result<ExecuteScript, crate::Error> {
+    Ok(ExecuteScript::new(format!(
+        "document.dispatchEvent(new CustomEvent({}, {{bubbles: true, cancelable: true, composed: true, detail: {}}}))",
+        json_string(name),
+        serde_json::to_string(detail)?,
+    )))
+}
+
+/// Pushes `url` onto the browser history, without navigating to it.
+pub fn push_history(url: &str) -> ExecuteScript {
+    ExecuteScript::new(format!(
+        "window.history.pushState({{}}, \"\", {})",
+        json_string(url)
+    ))
+}
+
+/// Sets the title of the document.
+pub fn set_title(title: &str) -> ExecuteScript {
+    ExecuteScript::new(format!("document.title = {}", json_string(title)))
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    write_json_string(&mut json, value);
+    json
+}
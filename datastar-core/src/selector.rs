@@ -0,0 +1,443 @@
+//! Compile-time checks for the selectors and ids targeted by [`PatchElements`](crate::patch_elements::PatchElements).
+//!
+//! The browser silently ignores patches whose selector doesn't match anything, so a typo
+//! in a selector only shows up as a page that doesn't update. The [`selector!`](crate::selector!)
+//! and [`id!`](crate::id!) macros check the syntax in a const context, failing the build instead.
+//! Both can also cross-check against HTML templates which are included with `include_str!`,
+//! catching ids which don't exist in any of them.
+//!
+//! The syntax check is deliberately shallow: it catches empty selectors, dangling combinators,
+//! unbalanced brackets and quotes and `#` or `.` without a name, not every invalid selector.
+
+use {
+    core::fmt::{self, Display},
+    std::borrow::Cow,
+};
+
+/// Checks the syntax of a CSS selector and returns it as a [`Selector`].
+///
+/// The selector has to be a constant expression. When templates are given after `in`,
+/// every `#id` of the selector has to be present in at least one of them.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{prelude::PatchElements, selector};
+///
+/// const PAGE: &str = r#"<ul id="feed"></ul>"#;
+///
+/// let patch = PatchElements::new("<li>Done</li>").selector(selector!("#feed > li:last-child"));
+/// let checked = selector!("#feed", in PAGE);
+/// # assert_eq!(checked, "#feed");
+/// # drop(patch);
+/// ```
+///
+/// Typos fail to compile:
+///
+/// ```compile_fail
+/// let selector = datastar_core::selector!("#feed >");
+/// ```
+#[macro_export]
+macro_rules! selector {
+    ($selector:expr $(, in $($template:expr),+)? $(,)?) => {{
+        const SELECTOR: $crate::selector::Selector = $crate::selector::Selector::new($selector);
+        $(
+            const _: () = assert!(
+                $crate::selector::has_selector_ids(&[$($template),+], SELECTOR.as_str()),
+                "the selector targets an id which is missing from the templates",
+            );
+        )?
+        SELECTOR
+    }};
+}
+
+/// [`Selector`] is a CSS selector whose syntax was checked, which is accepted by
+/// [`PatchElements::selector`](crate::patch_elements::PatchElements::selector).
+///
+/// Create it with [`selector!`](crate::selector!), or with [`Selector::new`] in a `const`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Selector(&'static str);
+
+impl Selector {
+    /// Creates a [`Selector`] from `selector`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `selector` doesn't pass the syntax check described in the [module docs](self),
+    /// which fails the build in a `const`.
+    pub const fn new(selector: &'static str) -> Self {
+        assert!(is_valid_selector(selector), "invalid CSS selector");
+        Self(selector)
+    }
+
+    /// Returns the selector as a `&'static str`.
+    pub const fn as_str(self) -> &'static str {
+        self.0
+    }
+}
+
+impl Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl AsRef<str> for Selector {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl PartialEq<str> for Selector {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Selector {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<Selector> for &'static str {
+    fn from(selector: Selector) -> Self {
+        selector.0
+    }
+}
+
+impl From<Selector> for String {
+    fn from(selector: Selector) -> Self {
+        selector.0.to_owned()
+    }
+}
+
+impl From<Selector> for Cow<'static, str> {
+    fn from(selector: Selector) -> Self {
+        Cow::Borrowed(selector.0)
+    }
+}
+
+/// Checks that an element id is valid and returns it as a `&'static str`.
+///
+/// The id has to be a constant expression. When templates are given after `in`,
+/// it has to be present in at least one of them.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{html::el, id};
+///
+/// const PAGE: &str = r#"<div id="message"></div>"#;
+///
+/// let element = el("div").id(id!("message", in PAGE)).text("Hello!");
+/// # assert_eq!(element.to_string(), r#"<div id="message">Hello!</div>"#);
+/// ```
+///
+/// Ids that are missing from the templates fail to compile:
+///
+/// ```compile_fail
+/// const PAGE: &str = r#"<div id="message"></div>"#;
+///
+/// let id = datastar_core::id!("mesage", in PAGE);
+/// ```
+#[macro_export]
+macro_rules! id {
+    ($id:expr $(, in $($template:expr),+)? $(,)?) => {{
+        const ID: &str = $id;
+        const _: () = assert!(
+            $crate::selector::is_valid_id(ID),
+            "ids must not be empty or contain whitespace",
+        );
+        $(
+            const _: () = assert!(
+                $crate::selector::has_id(&[$($template),+], ID),
+                "the id is missing from the templates",
+            );
+        )?
+        ID
+    }};
+}
+
+/// Returns whether `id` is a valid element id, which is not empty and contains no whitespace.
+pub const fn is_valid_id(id: &str) -> bool {
+    let bytes = id.as_bytes();
+    if bytes.is_empty() {
+        return false;
+    }
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index].is_ascii_whitespace() {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+/// Returns the `id` of the first top-level element of the HTML `elements`, skipping the
+/// whitespace and comments before it.
+///
+/// Returns `None` if the elements start with text, or the first element has no valid `id`.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::selector::first_element_id;
+///
+/// assert_eq!(first_element_id(r#"<!-- feed --> <li id="entry-1">Done</li>"#), Some("entry-1"));
+/// assert_eq!(first_element_id("<li>Done</li>"), None);
+/// ```
+pub fn first_element_id(elements: &str) -> Option<&str> {
+    let mut rest = elements.trim_start();
+    while rest.starts_with("<!") {
+        let end = match rest.strip_prefix("<!--") {
+            Some(comment) => 4 + comment.find("-->")? + 3,
+            None => rest.find('>')? + 1,
+        };
+        rest = rest[end..].trim_start();
+    }
+
+    let tag = rest
+        .strip_prefix('<')
+        .filter(|tag| tag.starts_with(|c: char| c.is_ascii_alphabetic()))?;
+    let mut attributes =
+        &tag[tag.find(|c: char| c.is_ascii_whitespace() || matches!(c, '>' | '/'))?..];
+    loop {
+        attributes = attributes.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+        if attributes.is_empty() || attributes.starts_with('>') {
+            return None;
+        }
+
+        let name_end = attributes
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))
+            .unwrap_or(attributes.len());
+        let name = &attributes[..name_end];
+        attributes = attributes[name_end..].trim_start();
+        let Some(value) = attributes.strip_prefix('=') else {
+            continue;
+        };
+
+        let value = value.trim_start();
+        let (value, rest) = match value.as_bytes().first() {
+            Some(&quote @ (b'"' | b'\'')) => {
+                let len = value[1..].find(char::from(quote))?;
+                (&value[1..1 + len], &value[len + 2..])
+            }
+            _ => {
+                let len = value
+                    .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                    .unwrap_or(value.len());
+                value.split_at(len)
+            }
+        };
+        if name.eq_ignore_ascii_case("id") {
+            return Some(value).filter(|id| is_valid_id(id));
+        }
+        attributes = rest;
+    }
+}
+
+/// Returns a CSS selector for the element with the given `id`, which is `#id` when the id
+/// is a plain CSS identifier, or an attribute selector otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::selector::id_selector;
+///
+/// assert_eq!(id_selector("entry-1"), "#entry-1");
+/// assert_eq!(id_selector("1st"), r#"[id="1st"]"#);
+/// ```
+pub fn id_selector(id: &str) -> String {
+    let is_identifier = id
+        .bytes()
+        .next()
+        .is_some_and(|byte| byte.is_ascii_alphabetic() || byte == b'_')
+        && id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_'));
+    if is_identifier {
+        return format!("#{id}");
+    }
+
+    let mut selector = String::with_capacity(id.len() + 7);
+    selector.push_str("[id=\"");
+    for c in id.chars() {
+        if matches!(c, '"' | '\\') {
+            selector.push('\\');
+        }
+        selector.push(c);
+    }
+    selector.push_str("\"]");
+    selector
+}
+
+/// Returns whether `selector` passes the syntax check described in the [module docs](self).
+pub const fn is_valid_selector(selector: &str) -> bool {
+    let bytes = selector.as_bytes();
+    let (mut brackets, mut parens, mut quote) = (0usize, 0usize, 0u8);
+    // An empty selector, or one starting or ending with a combinator, is invalid.
+    let mut expects_compound = true;
+
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = bytes[index];
+        if quote != 0 {
+            if byte == b'\\' {
+                index += 1;
+            } else if byte == quote {
+                quote = 0;
+            }
+            index += 1;
+            continue;
+        }
+
+        match byte {
+            b'\\' => {
+                index += 1;
+                expects_compound = false;
+            }
+            b'"' | b'\'' if brackets > 0 || parens > 0 => quote = byte,
+            b'"' | b'\'' => return false,
+            b'[' => {
+                brackets += 1;
+                expects_compound = false;
+            }
+            b']' if brackets == 0 => return false,
+            b']' => brackets -= 1,
+            b'(' => parens += 1,
+            b')' if parens == 0 => return false,
+            b')' => parens -= 1,
+            b'#' | b'.' if brackets == 0 => {
+                if index + 1 >= bytes.len() || !is_name_start(bytes[index + 1]) {
+                    return false;
+                }
+                expects_compound = false;
+            }
+            b'>' | b'+' | b'~' | b',' if brackets == 0 && parens == 0 => {
+                if expects_compound {
+                    return false;
+                }
+                expects_compound = true;
+            }
+            _ if byte.is_ascii_whitespace() => {}
+            _ => expects_compound = false,
+        }
+        index += 1;
+    }
+
+    brackets == 0 && parens == 0 && quote == 0 && !expects_compound
+}
+
+/// Returns whether any of the HTML `templates` contains an element with the given `id`.
+pub const fn has_id(templates: &[&str], id: &str) -> bool {
+    let id = id.as_bytes();
+    templates_have_id(templates, id, 0, id.len())
+}
+
+/// Returns whether every `#id` of `selector` is present in at least one of the HTML `templates`.
+///
+/// Ids containing CSS escapes are not checked.
+pub const fn has_selector_ids(templates: &[&str], selector: &str) -> bool {
+    let bytes = selector.as_bytes();
+    let (mut brackets, mut quote) = (0usize, 0u8);
+
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = bytes[index];
+        if quote != 0 {
+            if byte == b'\\' {
+                index += 1;
+            } else if byte == quote {
+                quote = 0;
+            }
+        } else if byte == b'"' || byte == b'\'' {
+            quote = byte;
+        } else if byte == b'[' {
+            brackets += 1;
+        } else if byte == b']' {
+            brackets = brackets.saturating_sub(1);
+        } else if byte == b'\\' {
+            index += 1;
+        } else if byte == b'#' && brackets == 0 {
+            let start = index + 1;
+            let mut end = start;
+            while end < bytes.len() && is_name(bytes[end]) {
+                end += 1;
+            }
+            let escaped = end < bytes.len() && bytes[end] == b'\\';
+            if !escaped && !templates_have_id(templates, bytes, start, end) {
+                return false;
+            }
+            index = end;
+            continue;
+        }
+        index += 1;
+    }
+    true
+}
+
+const fn is_name_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || matches!(byte, b'-' | b'_' | b'\\') || byte >= 0x80
+}
+
+const fn is_name(byte: u8) -> bool {
+    is_name_start(byte) || byte.is_ascii_digit()
+}
+
+const fn templates_have_id(templates: &[&str], id: &[u8], start: usize, end: usize) -> bool {
+    let mut index = 0;
+    while index < templates.len() {
+        if template_has_id(templates[index].as_bytes(), id, start, end) {
+            return true;
+        }
+        index += 1;
+    }
+    false
+}
+
+/// Looks for an `id` attribute with the value `id[start..end]`, quoted or not.
+const fn template_has_id(html: &[u8], id: &[u8], start: usize, end: usize) -> bool {
+    let len = end - start;
+    let mut index = 1;
+    while index + 3 <= html.len() {
+        let is_attribute = html[index - 1].is_ascii_whitespace()
+            && html[index] == b'i'
+            && html[index + 1] == b'd'
+            && html[index + 2] == b'=';
+        if is_attribute {
+            let mut value = index + 3;
+            let quote = if value < html.len() && (html[value] == b'"' || html[value] == b'\'') {
+                value += 1;
+                html[value - 1]
+            } else {
+                0
+            };
+            if value + len < html.len() && bytes_eq(html, value, id, start, len) {
+                let after = html[value + len];
+                let closed = if quote == 0 {
+                    after.is_ascii_whitespace() || after == b'>' || after == b'/'
+                } else {
+                    after == quote
+                };
+                if closed {
+                    return true;
+                }
+            }
+        }
+        index += 1;
+    }
+    false
+}
+
+const fn bytes_eq(a: &[u8], a_start: usize, b: &[u8], b_start: usize, len: usize) -> bool {
+    let mut index = 0;
+    while index < len {
+        if a[a_start + index] != b[b_start + index] {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
@@ -0,0 +1,391 @@
+//! [`DatastarSender`] pushes events to a client from anywhere, such as background tasks.
+//!
+//! This is the equivalent of the `ServerSentEventGenerator` found in other Datastar SDKs.
+//! The [`DatastarReceiver`] end of the [`channel`] is returned from the handler, which turns
+//! it into an SSE response using the framework integration.
+
+use {
+    crate::{
+        DatastarEvent,
+        event_id::{self, EventIdGenerator},
+        execute_script::ExecuteScript,
+        patch_elements::PatchElements,
+        patch_signals::PatchSignals,
+        replay::ReplayBuffer,
+        response::IntoDatastarResponse,
+    },
+    core::{
+        fmt::{self, Display},
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    futures_channel::mpsc,
+    futures_core::Stream,
+    std::{collections::VecDeque, sync::Arc},
+};
+
+/// Creates a new [`DatastarSender`] and [`DatastarReceiver`] pair.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{prelude::PatchElements, sender};
+///
+/// let (sender, receiver) = sender::channel();
+///
+/// std::thread::spawn(move || {
+///     sender
+///         .patch_elements(PatchElements::new("<div id='status'>Done</div>"))
+///         .ok();
+///     sender.close();
+/// });
+///
+/// // Return `receiver` from a handler to stream the events to the client.
+/// # drop(receiver);
+/// ```
+pub fn channel() -> (DatastarSender, DatastarReceiver) {
+    let (tx, rx) = mpsc::unbounded();
+    (
+        DatastarSender {
+            tx,
+            replay: None,
+            ids: None,
+        },
+        DatastarReceiver::new(rx),
+    )
+}
+
+/// Creates a new [`DatastarSender`] and [`DatastarReceiver`] pair which records every sent
+/// event in the given [`ReplayBuffer`].
+///
+/// If the client reconnected with a `Last-Event-ID`, the events it missed are replayed first.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     prelude::PatchSignals,
+///     replay::{LastEventId, RingBuffer},
+///     sender,
+/// };
+/// use std::sync::Arc;
+///
+/// let buffer = Arc::new(RingBuffer::new(64));
+///
+/// fn feed(buffer: &Arc<RingBuffer>, last_event_id: Option<LastEventId>) -> sender::DatastarReceiver {
+///     let (sender, receiver) = sender::channel_with_replay(buffer.clone(), last_event_id.as_deref());
+///     sender.patch_signals(PatchSignals::new("{online: true}").id("1")).ok();
+///     receiver
+/// }
+/// # drop(feed(&buffer, None));
+/// ```
+pub fn channel_with_replay<B: ReplayBuffer>(
+    buffer: B,
+    last_event_id: Option<&str>,
+) -> (DatastarSender, DatastarReceiver) {
+    let (tx, rx) = mpsc::unbounded();
+    if let Some(last_event_id) = last_event_id {
+        for event in buffer.replay(last_event_id) {
+            // Assumption: sending never fails, as the receiver is still alive.
+            let _ = tx.unbounded_send(Message::Event(event));
+        }
+    }
+    let sender = DatastarSender {
+        tx,
+        replay: Some(Arc::new(buffer)),
+        ids: None,
+    };
+    (sender, DatastarReceiver::new(rx))
+}
+
+/// The messages of a [`channel`], where a transaction is sent as a single message
+/// so that no events of other senders can end up in between.
+#[derive(Debug)]
+enum Message {
+    Event(DatastarEvent),
+    Transaction(Vec<DatastarEvent>),
+}
+
+/// [`DatastarSender`] is a handle to push events to a client, created by [`channel`].
+///
+/// It can be cloned to send events from multiple tasks.
+#[derive(Clone)]
+pub struct DatastarSender {
+    tx: mpsc::UnboundedSender<Message>,
+    replay: Option<Arc<dyn ReplayBuffer>>,
+    ids: Option<Arc<dyn EventIdGenerator>>,
+}
+
+impl fmt::Debug for DatastarSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatastarSender")
+            .field("tx", &self.tx)
+            .field("replay", &self.replay.is_some())
+            .field("ids", &self.ids.is_some())
+            .finish()
+    }
+}
+
+impl DatastarSender {
+    /// Gives every event sent without an `id` the next id of `ids`, before it is recorded
+    /// for replay. Clones of the returned sender share the generator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{
+    ///     event_id::Counter,
+    ///     prelude::PatchSignals,
+    ///     replay::RingBuffer,
+    ///     sender,
+    /// };
+    /// use std::sync::Arc;
+    ///
+    /// let buffer = Arc::new(RingBuffer::new(64));
+    /// let (sender, receiver) = sender::channel_with_replay(buffer.clone(), None);
+    /// let sender = sender.with_event_ids(Counter::new());
+    ///
+    /// sender.patch_signals(PatchSignals::new("{online: true}"))?;
+    ///
+    /// assert!(buffer.contains("1"));
+    /// # drop(receiver);
+    /// # Ok::<(), sender::SendError>(())
+    /// ```
+    pub fn with_event_ids(mut self, ids: impl EventIdGenerator) -> Self {
+        self.ids = Some(Arc::new(ids));
+        self
+    }
+
+    /// Sends any event to the client.
+    ///
+    /// Fails if the client disconnected or the channel was closed.
+    pub fn send(&self, event: impl Into<DatastarEvent>) -> Result<(), SendError> {
+        let mut event = event.into();
+        if let Some(ids) = &self.ids {
+            event_id::assign(&**ids, &mut event);
+        }
+        if let Some(replay) = &self.replay {
+            replay.record(&event);
+        }
+        self.tx
+            .unbounded_send(Message::Event(event))
+            .map_err(|err| match err.into_inner() {
+                Message::Event(event) => SendError(Box::new(event)),
+                Message::Transaction(_) => unreachable!("an event was sent"),
+            })
+    }
+
+    /// Sends all events of the [`Transaction`] to the client back to back,
+    /// without any events of other senders in between.
+    ///
+    /// Either all events are sent or, if the client disconnected or the channel was closed, none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{
+    ///     prelude::{ExecuteScript, PatchElements},
+    ///     sender::{self, Transaction},
+    /// };
+    ///
+    /// let (sender, receiver) = sender::channel();
+    ///
+    /// // The script always runs right after the element it depends on was patched.
+    /// sender.commit(
+    ///     Transaction::new()
+    ///         .patch_elements(PatchElements::new("<canvas id='chart'></canvas>"))
+    ///         .execute_script(ExecuteScript::new("drawChart('chart')")),
+    /// )?;
+    /// # drop(receiver);
+    /// # Ok::<(), sender::SendError<Transaction>>(())
+    /// ```
+    pub fn commit(&self, mut transaction: Transaction) -> Result<(), SendError<Transaction>> {
+        if self.tx.is_closed() {
+            return Err(SendError(Box::new(transaction)));
+        }
+        if let Some(ids) = &self.ids {
+            for event in &mut transaction.events {
+                event_id::assign(&**ids, event);
+            }
+        }
+        if let Some(replay) = &self.replay {
+            for event in &transaction.events {
+                replay.record(event);
+            }
+        }
+        self.tx
+            .unbounded_send(Message::Transaction(transaction.events))
+            .map_err(|err| match err.into_inner() {
+                Message::Transaction(events) => SendError(Box::new(Transaction { events })),
+                Message::Event(_) => unreachable!("a transaction was sent"),
+            })
+    }
+
+    /// Sends a [`PatchElements`] event to the client, after the script of its
+    /// [`ViewTransition`](crate::patch_elements::ViewTransition) if it has one.
+    ///
+    /// The script and the patch are committed as a [`Transaction`], so no event sent
+    /// concurrently lands between them. The error holds the patch, without its script.
+    pub fn patch_elements(&self, patch_elements: PatchElements) -> Result<(), SendError> {
+        if patch_elements.view_transition_script().is_none() {
+            return self.send(patch_elements);
+        }
+        self.commit(Transaction::new().patch_elements(patch_elements))
+            .map_err(|err| {
+                let patch = err.into_inner().events.pop();
+                SendError(Box::new(patch.expect("the transaction holds the patch")))
+            })
+    }
+
+    /// Sends a [`PatchSignals`] event to the client.
+    pub fn patch_signals(&self, patch_signals: PatchSignals) -> Result<(), SendError> {
+        self.send(patch_signals)
+    }
+
+    /// Sends an [`ExecuteScript`] event to the client.
+    pub fn execute_script(&self, execute_script: ExecuteScript) -> Result<(), SendError> {
+        self.send(execute_script)
+    }
+
+    /// Closes the channel for all senders, which ends the response once the
+    /// events sent so far are delivered.
+    pub fn close(&self) {
+        self.tx.close_channel();
+    }
+
+    /// Returns `true` if the client disconnected or the channel was closed.
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+/// [`DatastarReceiver`] is the receiving end of a [`channel`], to be returned as a response.
+///
+/// It is a [`Stream`] of the events sent by the [`DatastarSender`]s, which ends when
+/// all senders are dropped or [`DatastarSender::close`] is called.
+#[derive(Debug)]
+pub struct DatastarReceiver {
+    rx: mpsc::UnboundedReceiver<Message>,
+    /// The remaining events of the transaction which is being received.
+    pending: VecDeque<DatastarEvent>,
+}
+
+impl DatastarReceiver {
+    fn new(rx: mpsc::UnboundedReceiver<Message>) -> Self {
+        Self {
+            rx,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl Stream for DatastarReceiver {
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            match futures_core::ready!(Pin::new(&mut self.rx).poll_next(cx)) {
+                Some(Message::Event(event)) => return Poll::Ready(Some(event)),
+                Some(Message::Transaction(events)) => self.pending.extend(events),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, _) = self.rx.size_hint();
+        (lower.saturating_add(self.pending.len()), None)
+    }
+}
+
+impl IntoDatastarResponse for DatastarReceiver {
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
+
+/// Error returned by [`DatastarSender`] when the event or [`Transaction`] could not be delivered,
+/// because the client disconnected or the channel was closed.
+#[derive(Debug)]
+pub struct SendError<T = DatastarEvent>(Box<T>);
+
+impl<T> SendError<T> {
+    /// Returns the event or [`Transaction`] which could not be sent.
+    pub fn into_inner(self) -> T {
+        *self.0
+    }
+}
+
+impl<T> Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the Datastar channel is closed")
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for SendError<T> {}
+
+/// [`Transaction`] is a group of events which is sent to the client back to back, or not at all.
+///
+/// This keeps the events of other tasks from landing in between events which depend on each other,
+/// such as a [`PatchElements`] and an [`ExecuteScript`] using the patched element.
+/// It is sent using [`DatastarSender::commit`], or published using
+/// `Hub::commit` with the `broadcast` feature.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transaction {
+    events: Vec<DatastarEvent>,
+}
+
+impl Transaction {
+    /// Creates a new, empty [`Transaction`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds any event to the [`Transaction`].
+    pub fn event(mut self, event: impl Into<DatastarEvent>) -> Self {
+        self.events.push(event.into());
+        self
+    }
+
+    /// Adds a [`PatchElements`] event to the [`Transaction`], after the script of its
+    /// [`ViewTransition`](crate::patch_elements::ViewTransition) if it has one.
+    pub fn patch_elements(mut self, mut patch_elements: PatchElements) -> Self {
+        if let Some(script) = patch_elements.take_view_transition_script() {
+            self = self.event(script);
+        }
+        self.event(patch_elements)
+    }
+
+    /// Adds a [`PatchSignals`] event to the [`Transaction`].
+    pub fn patch_signals(self, patch_signals: PatchSignals) -> Self {
+        self.event(patch_signals)
+    }
+
+    /// Adds an [`ExecuteScript`] event to the [`Transaction`].
+    pub fn execute_script(self, execute_script: ExecuteScript) -> Self {
+        self.event(execute_script)
+    }
+
+    /// Returns the events of the [`Transaction`].
+    pub fn events(&self) -> &[DatastarEvent] {
+        &self.events
+    }
+
+    /// Returns the events of the [`Transaction`], consuming it.
+    pub fn into_events(self) -> Vec<DatastarEvent> {
+        self.events
+    }
+}
+
+impl<E: Into<DatastarEvent>> FromIterator<E> for Transaction {
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        Self {
+            events: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! [`SerializeOptions`] control how an event is written, for conformance tests and proxies
+//! which compare the output of different SDKs byte for byte.
+//!
+//! [`Display`](core::fmt::Display) and the framework integrations write events with `\n`
+//! line endings and the data lines the event has. [`SerializeOptions::strict`] writes what the
+//! reference SDK writes, leaving out the data lines and `retry` which are at their defaults.
+
+use {
+    crate::{
+        DatastarEvent,
+        batch::DatastarEventBatch,
+        consts::{self, EventType},
+        parse,
+    },
+    core::{fmt, time::Duration},
+};
+
+/// The data lines of a patch of elements in the order of the reference SDK.
+const ELEMENTS_ORDER: &[&str] = &[
+    consts::SELECTOR_DATALINE_LITERAL,
+    consts::MODE_DATALINE_LITERAL,
+    consts::USE_VIEW_TRANSITION_DATALINE_LITERAL,
+    consts::ELEMENTS_DATALINE_LITERAL,
+];
+
+/// The data lines of a patch of signals in the order of the reference SDK.
+const SIGNALS_ORDER: &[&str] = &[
+    consts::ONLY_IF_MISSING_DATALINE_LITERAL,
+    consts::SIGNALS_DATALINE_LITERAL,
+];
+
+/// The optional data lines of a patch of elements at their defaults.
+const ELEMENTS_DEFAULTS: &[&str] = &["mode outer", "useViewTransition false"];
+
+/// The optional data lines of a patch of signals at their defaults.
+const SIGNALS_DEFAULTS: &[&str] = &["onlyIfMissing false"];
+
+/// [`LineEnding`] is the end of every line of an event, including the blank line ending it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// `\n`, as written by the reference SDK.
+    #[default]
+    Lf,
+    /// `\r\n`, which the SSE specification allows as well.
+    CrLf,
+}
+
+impl LineEnding {
+    /// Returns the [`LineEnding`] as a [`&'static str`].
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
+
+/// [`Defaults`] decides what happens to the optional data lines and `retry` which have their
+/// default values, such as `mode outer` or `onlyIfMissing false`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Defaults {
+    /// Writes the data lines and `retry` the event has.
+    #[default]
+    AsIs,
+    /// Leaves them out, as the reference SDK does.
+    Omit,
+    /// Writes them even when the event doesn't have them.
+    Emit,
+}
+
+/// [`SerializeOptions`] configure how [`DatastarEvent::write_fmt_with`] writes an event.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     DatastarEvent,
+///     prelude::PatchElements,
+///     serialize::{Defaults, LineEnding, SerializeOptions},
+/// };
+///
+/// let event = DatastarEvent::from(PatchElements::new("<div id='status'>Done</div>"));
+///
+/// let options = SerializeOptions::new()
+///     .line_ending(LineEnding::CrLf)
+///     .defaults(Defaults::Emit);
+///
+/// assert_eq!(
+///     event.to_string_with(options),
+///     "event: datastar-patch-elements\r\nretry: 1000\r\ndata: mode outer\r\n\
+///      data: useViewTransition false\r\ndata: elements <div id='status'>Done</div>\r\n\r\n",
+/// );
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SerializeOptions {
+    line_ending: LineEnding,
+    defaults: Defaults,
+}
+
+impl SerializeOptions {
+    /// Creates [`SerializeOptions`] which write events like their [`Display`](core::fmt::Display)
+    /// implementation does.
+    pub const fn new() -> Self {
+        Self {
+            line_ending: LineEnding::Lf,
+            defaults: Defaults::AsIs,
+        }
+    }
+
+    /// Creates [`SerializeOptions`] which write events byte for byte like the reference SDK,
+    /// with `\n` line endings and without the data lines and `retry` at their defaults.
+    pub const fn strict() -> Self {
+        Self {
+            line_ending: LineEnding::Lf,
+            defaults: Defaults::Omit,
+        }
+    }
+
+    /// Sets the [`LineEnding`].
+    pub const fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Sets what happens to the data lines and `retry` at their [`Defaults`].
+    pub const fn defaults(mut self, defaults: Defaults) -> Self {
+        self.defaults = defaults;
+        self
+    }
+}
+
+impl DatastarEvent {
+    /// Writes the SSE framing of the [`DatastarEvent`] into `out` as configured by `options`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{DatastarEvent, consts::EventType, serialize::SerializeOptions};
+    ///
+    /// let event = DatastarEvent::new(EventType::PatchSignals, ["onlyIfMissing false", "signals {count: 1}"]);
+    ///
+    /// assert_eq!(
+    ///     event.to_string_with(SerializeOptions::strict()),
+    ///     "event: datastar-patch-signals\ndata: signals {count: 1}\n\n",
+    /// );
+    /// ```
+    pub fn write_fmt_with(
+        &self,
+        out: &mut impl fmt::Write,
+        options: SerializeOptions,
+    ) -> fmt::Result {
+        let end = options.line_ending.as_str();
+
+        for comment in &self.comments {
+            if comment.is_empty() {
+                write_pieces(out, [":", end])?;
+            } else {
+                write_pieces(out, [": ", comment, end])?;
+            }
+        }
+
+        if let Some(event) = self.event_name() {
+            write_pieces(out, ["event: ", event, end])?;
+        }
+
+        if let Some(id) = &self.id {
+            write_pieces(out, ["id: ", id, end])?;
+        }
+
+        let default_retry = Duration::from_millis(consts::DEFAULT_SSE_RETRY_DURATION);
+        let retry = match options.defaults {
+            Defaults::AsIs => self.retry,
+            Defaults::Omit => self.retry.filter(|&retry| retry != default_retry),
+            Defaults::Emit => Some(self.retry.unwrap_or(default_retry)),
+        };
+        if let Some(retry) = retry {
+            let mut millis = itoa::Buffer::new();
+            write_pieces(out, ["retry: ", millis.format(retry.as_millis()), end])?;
+        }
+
+        for (name, value) in &self.fields {
+            write_pieces(out, [name, ": ", value, end])?;
+        }
+
+        match (options.defaults, self.known_data_lines()) {
+            (Defaults::Emit, Some(_)) => {
+                for line in self.data_lines_with_defaults() {
+                    write_pieces(out, ["data: ", line, end])?;
+                }
+            }
+            (Defaults::Omit, Some((_, optional))) => {
+                for line in self.data.iter().filter(|line| !optional.contains(line)) {
+                    write_pieces(out, ["data: ", line, end])?;
+                }
+            }
+            _ => {
+                for line in self.data.iter() {
+                    write_pieces(out, ["data: ", line, end])?;
+                }
+            }
+        }
+
+        out.write_str(end)
+    }
+
+    /// Returns the SSE framing of the [`DatastarEvent`] as configured by `options`.
+    pub fn to_string_with(&self, options: SerializeOptions) -> String {
+        let mut out = String::with_capacity(self.data.framed().0.len() + 64);
+        // Writing to a `String` never fails.
+        let _ = self.write_fmt_with(&mut out, options);
+        out
+    }
+
+    /// Returns the data lines, with those at their defaults added where the event doesn't
+    /// have them.
+    fn data_lines_with_defaults(&self) -> Vec<&str> {
+        let mut lines: Vec<&str> = self.data.iter().collect();
+        let Some((order, optional)) = self.known_data_lines() else {
+            return lines;
+        };
+
+        for default in optional {
+            let literal = parse::data_line(default).0;
+            if lines.iter().any(|line| parse::data_line(line).0 == literal) {
+                continue;
+            }
+            let at = lines
+                .iter()
+                .position(|line| rank(order, line) > rank(order, default))
+                .unwrap_or(lines.len());
+            lines.insert(at, default);
+        }
+        lines
+    }
+
+    /// Returns the data lines with those at their defaults added, sorted in the order of the
+    /// reference SDK.
+    pub(crate) fn canonical_data_lines(&self) -> Vec<&str> {
+        let mut lines = self.data_lines_with_defaults();
+        if let Some((order, _)) = self.known_data_lines() {
+            lines.sort_by_key(|line| rank(order, line));
+        }
+        lines
+    }
+
+    /// Returns the order of the data lines of a standard event, and those at their defaults.
+    fn known_data_lines(&self) -> Option<(&'static [&'static str], &'static [&'static str])> {
+        match (self.custom_event.is_some(), self.event) {
+            (false, Some(EventType::PatchElements)) => Some((ELEMENTS_ORDER, ELEMENTS_DEFAULTS)),
+            (false, Some(EventType::PatchSignals)) => Some((SIGNALS_ORDER, SIGNALS_DEFAULTS)),
+            _ => None,
+        }
+    }
+}
+
+/// Writes `pieces` into `out` one after the other, without formatting them first.
+fn write_pieces<const N: usize>(out: &mut impl fmt::Write, pieces: [&str; N]) -> fmt::Result {
+    pieces
+        .into_iter()
+        .try_for_each(|piece| out.write_str(piece))
+}
+
+/// Returns the position of the literal of `line` in `order`, after all of them if it's unknown.
+fn rank(order: &[&str], line: &str) -> usize {
+    let literal = parse::data_line(line).0;
+    order
+        .iter()
+        .position(|known| *known == literal)
+        .unwrap_or(order.len())
+}
+
+impl DatastarEventBatch {
+    /// Writes the SSE framing of every event into `out` as configured by `options`.
+    pub fn write_fmt_with(
+        &self,
+        out: &mut impl fmt::Write,
+        options: SerializeOptions,
+    ) -> fmt::Result {
+        self.events()
+            .iter()
+            .try_for_each(|event| event.write_fmt_with(out, options))
+    }
+
+    /// Returns the SSE framing of every event as configured by `options`.
+    pub fn to_string_with(&self, options: SerializeOptions) -> String {
+        let capacity = self
+            .events()
+            .iter()
+            .map(|event| event.data.framed().0.len() + 64)
+            .sum();
+        let mut out = String::with_capacity(capacity);
+        // Writing to a `String` never fails.
+        let _ = self.write_fmt_with(&mut out, options);
+        out
+    }
+}
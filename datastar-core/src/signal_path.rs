@@ -0,0 +1,146 @@
+//! Compile-time checked paths of signals, for the [`PatchSignals`](crate::prelude::PatchSignals)
+//! builder and [`PatchSignals::remove`](crate::prelude::PatchSignals::remove).
+//!
+//! A patch creates every signal it names, so a typo in a dotted path such as `user.setings.theme`
+//! doesn't fail, it creates a new signal the page never reads. The [`signal_path!`](crate::signal_path!)
+//! macro checks the syntax of a path in a const context, and [`signal_paths!`](crate::signal_paths!)
+//! declares the paths of an application as constants, so they are spelled out only once.
+
+use core::fmt::{self, Display};
+
+/// Checks the syntax of a dotted signal path and returns it as a [`SignalPath`].
+///
+/// The path has to be a constant expression, of names separated by `.`, where every name
+/// starts with an ASCII letter, `_` or `$`, followed by ASCII letters, digits, `_` or `$`.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{prelude::PatchSignals, signal_path};
+///
+/// let patch = PatchSignals::remove([signal_path!("user.settings.theme")]);
+///
+/// assert_eq!(patch.signals, r#"{"user":{"settings":{"theme":null}}}"#);
+/// ```
+///
+/// Typos fail to compile:
+///
+/// ```compile_fail
+/// let path = datastar_core::signal_path!("user..theme");
+/// ```
+#[macro_export]
+macro_rules! signal_path {
+    ($path:expr $(,)?) => {{
+        const PATH: $crate::signal_path::SignalPath = $crate::signal_path::SignalPath::new($path);
+        PATH
+    }};
+}
+
+/// Declares checked [`SignalPath`] constants, one for every signal an application patches.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{prelude::PatchSignals, signal_paths};
+///
+/// signal_paths! {
+///     pub THEME = "user.settings.theme";
+///     pub DRAFT = "editor.draft";
+/// }
+///
+/// let patch = PatchSignals::remove([DRAFT]);
+/// assert_eq!(patch.signals, r#"{"editor":{"draft":null}}"#);
+/// assert_eq!(THEME.as_str(), "user.settings.theme");
+/// ```
+#[macro_export]
+macro_rules! signal_paths {
+    ($($(#[$meta:meta])* $vis:vis $name:ident = $path:expr;)*) => {
+        $(
+            $(#[$meta])*
+            $vis const $name: $crate::signal_path::SignalPath =
+                $crate::signal_path::SignalPath::new($path);
+        )*
+    };
+}
+
+/// [`SignalPath`] is the dotted path of a signal, such as `user.settings.theme`,
+/// whose syntax was checked.
+///
+/// Create it with [`signal_path!`](crate::signal_path!) or [`signal_paths!`](crate::signal_paths!),
+/// or with [`SignalPath::new`] in a `const`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SignalPath(&'static str);
+
+impl SignalPath {
+    /// Creates a [`SignalPath`] from `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` isn't a valid signal path, which fails the build in a `const`.
+    pub const fn new(path: &'static str) -> Self {
+        assert!(is_valid_signal_path(path), "invalid signal path");
+        Self(path)
+    }
+
+    /// Returns the path as a `&'static str`.
+    pub const fn as_str(self) -> &'static str {
+        self.0
+    }
+
+    /// Returns the names of the path, from the outermost signal to the signal itself.
+    pub fn segments(self) -> impl Iterator<Item = &'static str> {
+        self.0.split('.')
+    }
+}
+
+impl Display for SignalPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl AsRef<str> for SignalPath {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl From<SignalPath> for &'static str {
+    fn from(path: SignalPath) -> Self {
+        path.0
+    }
+}
+
+impl From<SignalPath> for String {
+    fn from(path: SignalPath) -> Self {
+        path.0.to_owned()
+    }
+}
+
+/// Returns whether `path` passes the syntax check described in [`signal_path!`](crate::signal_path!).
+pub const fn is_valid_signal_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    // An empty path, or one starting or ending with a `.`, is invalid.
+    let mut expects_name = true;
+
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = bytes[index];
+        if byte == b'.' {
+            if expects_name {
+                return false;
+            }
+            expects_name = true;
+        } else if expects_name {
+            if !(byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$') {
+                return false;
+            }
+            expects_name = false;
+        } else if !(byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$') {
+            return false;
+        }
+        index += 1;
+    }
+
+    !expects_name
+}
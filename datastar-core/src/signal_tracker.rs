@@ -0,0 +1,131 @@
+//! [`SignalTracker`] remembers the signals sent over a connection, so only the signals which
+//! changed since are sent, rather than the whole state on every update.
+//!
+//! Signal patches are JSON merge patches, so the diff of two states is a patch with the
+//! changed signals, and `null` for the signals which are gone.
+
+use {
+    crate::patch_signals::PatchSignals,
+    core::fmt,
+    serde::{Serialize, ser},
+    serde_json::{Map, Value},
+};
+
+/// [`SignalTracker`] turns each new state of the signals into a [`PatchSignals`] event
+/// with only the fields which changed since the last state it was given.
+///
+/// A tracker belongs to a single connection, as it assumes the client applied every patch
+/// it returned. Call [`reset`](SignalTracker::reset) when that no longer holds, such as
+/// after the client reconnected, to send the whole state again.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::signal_tracker::SignalTracker;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, PartialEq)]
+/// struct Progress {
+///     label: &'static str,
+///     percent: u8,
+/// }
+///
+/// let mut tracker = SignalTracker::new();
+///
+/// let patch = tracker.update(Progress { label: "Uploading", percent: 0 })?.unwrap();
+/// assert_eq!(patch.signals, r#"{"label":"Uploading","percent":0}"#);
+///
+/// let patch = tracker.update(Progress { label: "Uploading", percent: 40 })?.unwrap();
+/// assert_eq!(patch.signals, r#"{"percent":40}"#);
+///
+/// assert!(tracker.update(Progress { label: "Uploading", percent: 40 })?.is_none());
+/// # Ok::<(), datastar_core::Error>(())
+/// ```
+pub struct SignalTracker<T> {
+    /// The last state, along with its JSON to diff the next state against.
+    last: Option<(T, Map<String, Value>)>,
+}
+
+impl<T: Serialize + PartialEq> SignalTracker<T> {
+    /// Creates a new [`SignalTracker`], which hasn't sent any state yet.
+    pub const fn new() -> Self {
+        Self { last: None }
+    }
+
+    /// Remembers `state` as the state of the client, returning the [`PatchSignals`] event
+    /// which brings the client there, or `None` if nothing changed.
+    ///
+    /// The first state is sent whole. `state` has to serialize to a JSON object,
+    /// as the signals of a patch are always an object.
+    pub fn update(&mut self, state: T) -> Result<Option<PatchSignals>, crate::Error> {
+        if let Some((last, _)) = &self.last {
+            if *last == state {
+                return Ok(None);
+            }
+        }
+
+        let Value::Object(signals) = serde_json::to_value(&state)? else {
+            return Err(crate::Error::Json(ser::Error::custom(
+                "the signals must serialize to a JSON object",
+            )));
+        };
+        let patch = match &self.last {
+            Some((_, last)) => diff(last, &signals),
+            None => signals.clone(),
+        };
+        self.last = Some((state, signals));
+
+        Ok((!patch.is_empty()).then(|| Value::Object(patch).into()))
+    }
+
+    /// Returns the last state given to [`update`](SignalTracker::update).
+    pub fn state(&self) -> Option<&T> {
+        self.last.as_ref().map(|(state, _)| state)
+    }
+
+    /// Forgets the last state, so the next [`update`](SignalTracker::update) sends the whole state.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+impl<T: Serialize + PartialEq> Default for SignalTracker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SignalTracker<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignalTracker")
+            .field("state", &self.last.as_ref().map(|(state, _)| state))
+            .finish()
+    }
+}
+
+/// Returns the merge patch which turns `last` into `next`.
+fn diff(last: &Map<String, Value>, next: &Map<String, Value>) -> Map<String, Value> {
+    let mut patch = Map::new();
+    for (key, value) in next {
+        match (last.get(key), value) {
+            (Some(last), value) if last == value => {}
+            (Some(Value::Object(last)), Value::Object(next)) => {
+                let nested = diff(last, next);
+                if !nested.is_empty() {
+                    patch.insert(key.clone(), Value::Object(nested));
+                }
+            }
+            // A signal which is gone stays gone.
+            (None, Value::Null) => {}
+            (_, value) => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    for (key, value) in last {
+        if !value.is_null() && !next.contains_key(key) {
+            patch.insert(key.clone(), Value::Null);
+        }
+    }
+    patch
+}
@@ -0,0 +1,446 @@
+//! Wrappers which change how the signals of a request are read, by any signal extractor.
+//!
+//! [`from_parts`] reads the signals of a request the way the extractors of the framework
+//! integrations do, for frameworks without an integration.
+//!
+//! [`WithRaw`] reads the signals both as a typed struct and as untyped JSON, for handlers which
+//! forward or audit the signals as they were sent. [`Lenient`] reads the signals which match
+//! the struct and reports the others, for front ends whose signals drift ahead of the backend.
+//!
+//! [`ValidateSignals`] checks the values of the signals once they are read, for the extractors
+//! which reject invalid signals with the [`ValidationErrors`] as [`Error::Validation`].
+
+use {
+    crate::{
+        Error,
+        compression::{self, DEFAULT_DECOMPRESSED_LIMIT},
+        json,
+        patch_signals::PatchSignals,
+        query,
+    },
+    core::fmt::{self, Display, Write},
+    serde::{Deserialize, Deserializer, Serialize, de},
+    serde_json::{Map, Value},
+};
+
+/// Deserializes the signals of a request into `T`, the way the signal extractors of the
+/// framework integrations do.
+///
+/// The signals of `GET` requests are read from the `datastar` parameter of `query`, the query
+/// string without the leading `?`. Those of other requests are read from `body`, decoded as
+/// its `Content-Encoding` header says and read as a URL encoded form or as JSON as its
+/// `Content-Type` header says. The body is expected to be limited by the caller, its decoded
+/// size is limited to [`DEFAULT_DECOMPRESSED_LIMIT`].
+///
+/// The `headers` are pairs of names and values, such as those of a `&http::HeaderMap`.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::signals;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     delay: u64,
+/// }
+///
+/// let headers = [("Content-Type", "application/json")];
+/// let signals: Signals = signals::from_parts("POST", None, headers, br#"{"delay":400}"#)?;
+/// assert_eq!(signals.delay, 400);
+///
+/// // {"delay":0}
+/// let query = Some("datastar=%7B%22delay%22%3A0%7D");
+/// let signals: Signals = signals::from_parts("GET", query, [("accept", "*/*")], b"")?;
+/// assert_eq!(signals.delay, 0);
+/// # Ok::<(), datastar_core::Error>(())
+/// ```
+pub fn from_parts<T, K, V>(
+    method: &str,
+    query: Option<&str>,
+    headers: impl IntoIterator<Item = (K, V)>,
+    body: &[u8],
+) -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+    K: AsRef<str>,
+    V: AsRef<[u8]>,
+{
+    from_parts_with_limit(method, query, headers, body, DEFAULT_DECOMPRESSED_LIMIT)
+}
+
+/// Deserializes the signals of a request like [`from_parts`], limiting the decoded size
+/// of the body to `limit`.
+pub(crate) fn from_parts_with_limit<T, K, V>(
+    method: &str,
+    query: Option<&str>,
+    headers: impl IntoIterator<Item = (K, V)>,
+    body: &[u8],
+    limit: usize,
+) -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+    K: AsRef<str>,
+    V: AsRef<[u8]>,
+{
+    if method == "GET" {
+        return query::from_query(query.unwrap_or_default());
+    }
+
+    let mut content_type = None;
+    let mut content_encoding: Option<String> = None;
+    for (name, value) in headers {
+        let name = name.as_ref();
+        if name.eq_ignore_ascii_case("content-type") {
+            let value = core::str::from_utf8(value.as_ref())
+                .map_err(|_| Error::InvalidHeader("content-type"))?;
+            content_type = Some(value.to_owned());
+        } else if name.eq_ignore_ascii_case("content-encoding") {
+            let value = core::str::from_utf8(value.as_ref())
+                .map_err(|_| Error::InvalidHeader("content-encoding"))?;
+            // Repeated headers list the encodings in the order they were applied.
+            match &mut content_encoding {
+                Some(encodings) => {
+                    encodings.push_str(", ");
+                    encodings.push_str(value);
+                }
+                None => content_encoding = Some(value.to_owned()),
+            }
+        }
+    }
+
+    let decoded = compression::decode_body(content_encoding.as_deref(), body, limit)?;
+    let body = decoded.as_deref().unwrap_or(body);
+    if body.is_empty() {
+        return Err(Error::MissingSignals);
+    }
+    json::signals_from_body(content_type.as_deref(), body)
+}
+
+/// [`WithRaw`] deserializes the signals of a request into `T`, keeping the JSON they were
+/// deserialized from.
+///
+/// The body is parsed once into [`raw`](WithRaw::raw), which `T` is then deserialized from,
+/// so it can be read by any signal extractor, such as `ReadSignals<WithRaw<T>>`.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{query, signals::WithRaw};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Search {
+///     query: String,
+/// }
+///
+/// // {"query":"datastar","page":2}
+/// let WithRaw { signals, raw } = query::from_query::<WithRaw<Search>>(
+///     "datastar=%7B%22query%22%3A%22datastar%22%2C%22page%22%3A2%7D",
+/// )?;
+///
+/// assert_eq!(signals.query, "datastar");
+/// assert_eq!(raw["page"], 2);
+/// # Ok::<(), datastar_core::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithRaw<T> {
+    /// The signals deserialized into `T`.
+    pub signals: T,
+    /// The signals as they were sent, including those `T` doesn't have.
+    pub raw: Value,
+}
+
+impl<'de, T: de::DeserializeOwned> Deserialize<'de> for WithRaw<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Value::deserialize(deserializer)?;
+        let signals = serde_path_to_error::deserialize(&raw).map_err(|err| {
+            let mut message = String::new();
+            // Writing to a `String` never fails.
+            let _ = match err.path().to_string() {
+                path if path == "." => write!(message, "{}", err.inner()),
+                path => write!(message, "{} at `{path}`", err.inner()),
+            };
+            de::Error::custom(message)
+        })?;
+        Ok(Self { signals, raw })
+    }
+}
+
+/// [`Lenient`] deserializes the signals of a request into `T`, taking every signal which
+/// fits `T` and falling back to [`Default`] for the others.
+///
+/// Missing signals keep their default value, signals `T` doesn't have are ignored, and signals
+/// of the wrong type keep their default value and are reported in [`invalid`](Lenient::invalid),
+/// rather than failing the whole request.
+///
+/// The fields of `T` are found by serializing its default value, so a field which is skipped
+/// when serialized is only read if its signal fits.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{query, signals::Lenient};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct Filters {
+///     query: String,
+///     page: u32,
+///     archived: bool,
+/// }
+///
+/// // {"query":"datastar","page":"2","sort":"name"}
+/// let Lenient { signals, invalid } = query::from_query::<Lenient<Filters>>(
+///     "datastar=%7B%22query%22%3A%22datastar%22%2C%22page%22%3A%222%22%2C%22sort%22%3A%22name%22%7D",
+/// )?;
+///
+/// assert_eq!(signals.query, "datastar");
+/// assert_eq!(signals.page, 0);
+/// assert!(!signals.archived);
+/// assert_eq!(invalid.len(), 1);
+/// assert_eq!(invalid[0].path, "page");
+/// # Ok::<(), datastar_core::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lenient<T> {
+    /// The signals which fit `T`, with the default values of the others.
+    pub signals: T,
+    /// The signals which were sent with a value which doesn't fit `T`.
+    pub invalid: Vec<InvalidSignal>,
+}
+
+/// [`InvalidSignal`] is a signal [`Lenient`] left at its default value, as its value didn't fit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidSignal {
+    /// The path of the signal, such as `user.email`.
+    pub path: String,
+    /// Why the value of the signal didn't fit.
+    pub message: String,
+}
+
+impl<'de, T> Deserialize<'de> for Lenient<T>
+where
+    T: de::DeserializeOwned + Serialize + Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Value::deserialize(deserializer)?;
+        if let Ok(signals) = T::deserialize(&raw) {
+            return Ok(Self {
+                signals,
+                invalid: Vec::new(),
+            });
+        }
+
+        let mut merged = serde_json::to_value(T::default()).map_err(de::Error::custom)?;
+        let mut invalid = Vec::new();
+        match raw {
+            Value::Object(raw) if merged.is_object() => {
+                merge::<T>(&mut merged, &mut Vec::new(), raw, &mut invalid);
+            }
+            raw => invalid.push(InvalidSignal {
+                path: ".".to_owned(),
+                message: format!("expected the signals to be an object, found {raw}"),
+            }),
+        }
+
+        #[cfg(feature = "tracing")]
+        for signal in &invalid {
+            tracing::debug!(path = %signal.path, message = %signal.message, "ignoring invalid signal");
+        }
+
+        let signals = T::deserialize(&merged).map_err(de::Error::custom)?;
+        Ok(Self { signals, invalid })
+    }
+}
+
+/// Merges the signals of `raw` into the object at `path` of `merged`, which deserializes
+/// into `T`, keeping every signal with which `merged` still deserializes into `T`.
+fn merge<T: de::DeserializeOwned>(
+    merged: &mut Value,
+    path: &mut Vec<String>,
+    raw: Map<String, Value>,
+    invalid: &mut Vec<InvalidSignal>,
+) {
+    let pointer: String = path
+        .iter()
+        .map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+        .collect();
+
+    for (key, value) in raw {
+        let Some(Value::Object(object)) = merged.pointer_mut(&pointer) else {
+            return;
+        };
+        let previous = match (object.get(&key), value) {
+            (Some(Value::Object(_)), Value::Object(raw)) => {
+                path.push(key);
+                merge::<T>(merged, path, raw, invalid);
+                path.pop();
+                continue;
+            }
+            (_, value) => object.insert(key.clone(), value),
+        };
+
+        let Err(err) = T::deserialize(&*merged) else {
+            continue;
+        };
+        let Some(Value::Object(object)) = merged.pointer_mut(&pointer) else {
+            return;
+        };
+        match previous {
+            Some(previous) => {
+                object.insert(key.clone(), previous);
+                invalid.push(InvalidSignal {
+                    path: path
+                        .iter()
+                        .chain([&key])
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join("."),
+                    message: err.to_string(),
+                });
+            }
+            // A signal `T` doesn't have, or which it skips when serialized.
+            None => {
+                object.remove(&key);
+            }
+        }
+    }
+}
+
+/// [`ValidateSignals`] checks the values of signals which deserialized, such as the length of
+/// a name or the format of an email address.
+///
+/// Signals which implement it can be read with the `ReadValidSignals` extractor of the Axum
+/// integration or the `read_valid_signals` filter of the Warp integration, which reject
+/// invalid signals with [`Error::Validation`], a `422 Unprocessable Entity` response.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::signals::{ValidateSignals, ValidationErrors};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct SignUp {
+///     name: String,
+///     email: String,
+/// }
+///
+/// impl ValidateSignals for SignUp {
+///     fn validate(&self) -> Result<(), ValidationErrors> {
+///         let mut errors = ValidationErrors::new();
+///         if self.name.trim().is_empty() {
+///             errors.push("name", "Enter your name");
+///         }
+///         if !self.email.contains('@') {
+///             errors.push("email", "Enter a valid email address");
+///         }
+///         errors.into_result()
+///     }
+/// }
+///
+/// let sign_up = SignUp { name: "Ada".to_owned(), email: "ada".to_owned() };
+/// let errors = sign_up.validate().unwrap_err();
+///
+/// // Show the errors next to the inputs, with `data-text="$errors.email"`.
+/// assert_eq!(
+///     errors.to_patch_signals("errors").signals,
+///     r#"{"errors":{"email":"Enter a valid email address"}}"#,
+/// );
+/// ```
+pub trait ValidateSignals {
+    /// Returns the invalid signals, if there are any.
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// [`ValidationErrors`] are the signals which [`ValidateSignals::validate`] found invalid.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    errors: Vec<InvalidSignal>,
+}
+
+impl ValidationErrors {
+    /// Creates empty [`ValidationErrors`].
+    pub const fn new() -> Self {
+        Self { errors: Vec::new() }
+    }
+
+    /// Adds the signal at `path`, such as `user.email`, as invalid because of `message`.
+    pub fn push(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(InvalidSignal {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Returns whether no signal is invalid.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the invalid signals.
+    pub fn errors(&self) -> &[InvalidSignal] {
+        &self.errors
+    }
+
+    /// Returns `Ok(())` if no signal is invalid, and the errors otherwise.
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() { Ok(()) } else { Err(self) }
+    }
+
+    /// Returns the [`PatchSignals`] event which sets the message of every invalid signal at its
+    /// path below the signal `name`, so the page can show it next to the input.
+    ///
+    /// The patch only sets the signals which are invalid, so send `{"<name>": null}`
+    /// once the signals are valid to clear the messages.
+    pub fn to_patch_signals(&self, name: &str) -> PatchSignals {
+        let mut messages = Map::new();
+        for error in &self.errors {
+            let mut object = &mut messages;
+            let mut keys = error.path.split('.').peekable();
+            while let Some(key) = keys.next() {
+                if keys.peek().is_none() {
+                    object.insert(key.to_owned(), Value::String(error.message.clone()));
+                    break;
+                }
+                let nested = object
+                    .entry(key)
+                    .and_modify(|value| {
+                        if !value.is_object() {
+                            *value = Value::Object(Map::new());
+                        }
+                    })
+                    .or_insert_with(|| Value::Object(Map::new()));
+                let Value::Object(nested) = nested else {
+                    unreachable!("the value was just made an object");
+                };
+                object = nested;
+            }
+        }
+
+        let mut signals = Map::new();
+        signals.insert(name.to_owned(), Value::Object(messages));
+        PatchSignals::from(Value::Object(signals))
+    }
+}
+
+impl Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, error) in self.errors.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "`{}`: {}", error.path, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl From<ValidationErrors> for Error {
+    fn from(errors: ValidationErrors) -> Self {
+        Self::Validation(errors)
+    }
+}
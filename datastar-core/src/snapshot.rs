@@ -0,0 +1,277 @@
+//! Canonical formatting of events for snapshot tests, such as with `insta`.
+//!
+//! The same event can be written in several ways, as the optional data lines may be left out at
+//! their defaults and written in any order. [`DatastarEvent::canonical_string`] writes every
+//! event the same way, so a snapshot only changes when what the browser does changes:
+//! the data lines and `retry` at their defaults are written, the data lines are sorted in the
+//! order of the reference SDK and the custom fields by name.
+//!
+//! Parts of an event which change on every run, such as generated ids or timestamps, are
+//! replaced with placeholders by [`DatastarEvent::canonical_string_with`] and [`Redactions`].
+
+use {
+    crate::{DatastarEvent, batch::DatastarEventBatch, consts, parse},
+    core::{fmt::Write, time::Duration},
+};
+
+/// [`Redactions`] replace the volatile parts of an event with placeholders in its
+/// [canonical string](DatastarEvent::canonical_string_with).
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{DatastarEvent, prelude::PatchElements, snapshot::Redactions};
+///
+/// let event = DatastarEvent::from(
+///     PatchElements::new("<li id='message-01J9' data-sent='12:03'>Hello</li>").selector("#feed"),
+/// )
+/// .id("01J9");
+///
+/// let redactions = Redactions::new().id().attribute("id").attribute("data-sent");
+///
+/// assert_eq!(
+///     event.canonical_string_with(&redactions),
+///     "event: datastar-patch-elements\nid: [id]\nretry: 1000\ndata: selector #feed\n\
+///      data: mode outer\ndata: useViewTransition false\n\
+///      data: elements <li id='[id]' data-sent='[data-sent]'>Hello</li>\n\n",
+/// );
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Redactions {
+    id: bool,
+    attributes: Vec<String>,
+    #[cfg(feature = "serde")]
+    signals: Vec<String>,
+    replacements: Vec<(String, String)>,
+}
+
+impl Redactions {
+    /// Creates [`Redactions`] which don't redact anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the `id` of the event with `[id]`.
+    pub fn id(mut self) -> Self {
+        self.id = true;
+        self
+    }
+
+    /// Replaces the values of the attribute `name` in the elements with `[name]`.
+    pub fn attribute(mut self, name: impl Into<String>) -> Self {
+        self.attributes.push(name.into());
+        self
+    }
+
+    /// Replaces the value of the signal at the dotted `path` with the string `[path]`, when
+    /// the signals are JSON.
+    ///
+    /// The signals are written again as compact JSON when one of them is redacted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{DatastarEvent, prelude::PatchSignals, snapshot::Redactions};
+    ///
+    /// let event = DatastarEvent::from(PatchSignals::new(r#"{"count": 1, "user": {"seen": 1729000000}}"#));
+    ///
+    /// assert_eq!(
+    ///     event.canonical_string_with(&Redactions::new().signal("user.seen")),
+    ///     "event: datastar-patch-signals\nretry: 1000\ndata: onlyIfMissing false\n\
+    ///      data: signals {\"count\":1,\"user\":{\"seen\":\"[user.seen]\"}}\n\n",
+    /// );
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn signal(mut self, path: impl Into<String>) -> Self {
+        self.signals.push(path.into());
+        self
+    }
+
+    /// Replaces every occurrence of `text` in the event with `placeholder`, such as a
+    /// timestamp the test knows.
+    pub fn replace(mut self, text: impl Into<String>, placeholder: impl Into<String>) -> Self {
+        self.replacements.push((text.into(), placeholder.into()));
+        self
+    }
+}
+
+impl DatastarEvent {
+    /// Returns a deterministic representation of the [`DatastarEvent`] for snapshot tests.
+    ///
+    /// It's the SSE framing of the event with `\n` line endings, with the data lines and
+    /// `retry` at their defaults written, the data lines sorted in the order of the reference
+    /// SDK, and the custom fields sorted by name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{DatastarEvent, consts::EventType};
+    ///
+    /// let event = DatastarEvent::new(
+    ///     EventType::PatchElements,
+    ///     ["elements <div id='status'>Done</div>", "useViewTransition true"],
+    /// );
+    ///
+    /// assert_eq!(
+    ///     event.canonical_string(),
+    ///     "event: datastar-patch-elements\nretry: 1000\ndata: mode outer\n\
+    ///      data: useViewTransition true\ndata: elements <div id='status'>Done</div>\n\n",
+    /// );
+    /// ```
+    pub fn canonical_string(&self) -> String {
+        self.canonical_string_with(&Redactions::new())
+    }
+
+    /// Returns the [canonical string](Self::canonical_string) of the [`DatastarEvent`] with
+    /// the volatile parts replaced as configured by `redactions`.
+    pub fn canonical_string_with(&self, redactions: &Redactions) -> String {
+        let mut out = String::new();
+        // Writing to a `String` never fails.
+        let _ = self.write_canonical(&mut out, redactions);
+        out
+    }
+
+    fn write_canonical(&self, out: &mut String, redactions: &Redactions) -> core::fmt::Result {
+        for comment in &self.comments {
+            if comment.is_empty() {
+                out.push_str(":\n");
+            } else {
+                writeln!(out, ": {comment}")?;
+            }
+        }
+
+        if let Some(event) = self.event_name() {
+            writeln!(out, "event: {event}")?;
+        }
+
+        match &self.id {
+            Some(_) if redactions.id => out.push_str("id: [id]\n"),
+            Some(id) => writeln!(out, "id: {id}")?,
+            None => {}
+        }
+
+        let retry = self
+            .retry
+            .unwrap_or(Duration::from_millis(consts::DEFAULT_SSE_RETRY_DURATION));
+        writeln!(out, "retry: {}", retry.as_millis())?;
+
+        let mut fields: Vec<_> = self.fields.iter().collect();
+        fields.sort_by_key(|(name, _)| name);
+        for (name, value) in fields {
+            writeln!(out, "{name}: {value}")?;
+        }
+
+        let mut lines: Vec<String> = self
+            .canonical_data_lines()
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        #[cfg(feature = "serde")]
+        redact_signals(&mut lines, &redactions.signals);
+        for line in &mut lines {
+            for name in &redactions.attributes {
+                if parse::data_line(line).0 == consts::ELEMENTS_DATALINE_LITERAL {
+                    *line = redact_attribute(line, name);
+                }
+            }
+            writeln!(out, "data: {line}")?;
+        }
+        out.push('\n');
+
+        for (text, placeholder) in &redactions.replacements {
+            if !text.is_empty() {
+                *out = out.replace(text.as_str(), placeholder);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DatastarEventBatch {
+    /// Returns the [canonical string](DatastarEvent::canonical_string) of every event.
+    pub fn canonical_string(&self) -> String {
+        self.canonical_string_with(&Redactions::new())
+    }
+
+    /// Returns the [canonical string](DatastarEvent::canonical_string_with) of every event,
+    /// with the volatile parts replaced as configured by `redactions`.
+    pub fn canonical_string_with(&self, redactions: &Redactions) -> String {
+        self.events()
+            .iter()
+            .map(|event| event.canonical_string_with(redactions))
+            .collect()
+    }
+}
+
+/// Replaces the values of the attribute `name` in `html` with `[name]`.
+fn redact_attribute(html: &str, name: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(at) = rest.find(name) {
+        let after = &rest[at + name.len()..];
+        let is_attribute = rest[..at].ends_with(|c: char| c.is_ascii_whitespace());
+        let quote = after
+            .strip_prefix('=')
+            .and_then(|value| value.chars().next())
+            .filter(|quote| matches!(quote, '"' | '\''));
+        if let (true, Some(quote)) = (is_attribute, quote) {
+            let value = &after[2..];
+            if let Some(end) = value.find(quote) {
+                out.push_str(&rest[..at]);
+                let _ = write!(out, "{name}={quote}[{name}]{quote}");
+                rest = &value[end + 1..];
+                continue;
+            }
+        }
+        out.push_str(&rest[..at + name.len()]);
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Replaces the values of the signals at `paths` with `[path]`, writing the signals on one line
+/// if any of them is redacted.
+#[cfg(feature = "serde")]
+fn redact_signals(lines: &mut Vec<String>, paths: &[String]) {
+    use serde_json::Value;
+
+    let is_signals = |line: &String| parse::data_line(line).0 == consts::SIGNALS_DATALINE_LITERAL;
+    let Some(first) = lines
+        .iter()
+        .position(is_signals)
+        .filter(|_| !paths.is_empty())
+    else {
+        return;
+    };
+
+    let json = lines
+        .iter()
+        .filter(|line| is_signals(line))
+        .map(|line| parse::data_line(line).1)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let Ok(mut signals) = serde_json::from_str::<Value>(&json) else {
+        return;
+    };
+
+    let mut redacted = false;
+    for path in paths {
+        let signal = path
+            .split('.')
+            .try_fold(&mut signals, |value, segment| value.get_mut(segment));
+        if let Some(signal) = signal {
+            *signal = Value::String(format!("[{path}]"));
+            redacted = true;
+        }
+    }
+
+    if redacted {
+        lines[first] = format!("{} {signals}", consts::SIGNALS_DATALINE_LITERAL);
+        let mut index = 0;
+        lines.retain(|line| {
+            index += 1;
+            index - 1 == first || !is_signals(line)
+        });
+    }
+}
@@ -0,0 +1,177 @@
+//! [`SsePayload`] is what the framework integrations build their SSE events from, so the
+//! events are framed the same way whichever framework writes them.
+//!
+//! The `write_as_*_sse_event` methods of the integrations only move the parts of an
+//! [`SsePayload`] into the event type of their framework, and an integration with another
+//! framework can do the same with [`ToSsePayload::sse_payload`].
+//!
+//! The event types of the frameworks can't hold every part of an [`SsePayload`]: none of them
+//! has custom [`fields`](SsePayload::fields), and the one of Warp holds a single comment. The
+//! `write_as_*_sse_event` methods of a [`DatastarEvent`] panic on such an event rather than
+//! sending it truncated.
+//!
+//! The `DatastarResponse` of Axum and Rocket writes the events with
+//! [`DatastarEvent::write_fmt_to`] instead, which keeps every part of them. Warp can only stream
+//! its own SSE events, so its `DatastarResponse` ends the response with a
+//! [`FieldError::Unsupported`](crate::FieldError::Unsupported) on such an event.
+
+use {
+    crate::{
+        DatastarEvent, consts::EventType, data_lines::TextLines, execute_script::ExecuteScript,
+        patch_elements::PatchElements, patch_signals::PatchSignals,
+    },
+    core::time::Duration,
+    std::borrow::Cow,
+};
+
+/// [`SsePayload`] holds the parts of an SSE event, borrowed from the event or builder it
+/// was made from, with its data lines joined by `\n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsePayload<'a> {
+    /// `event_type` is the type of the event, unless it has a
+    /// [`custom_event`](SsePayload::custom_event) or carries only comments.
+    pub event_type: Option<EventType>,
+    /// `custom_event` is the name of a non-standard event type, written instead of
+    /// [`event_type`](SsePayload::event_type).
+    pub custom_event: Option<&'a str>,
+    /// `comments` are the SSE comments of the event.
+    pub comments: &'a [String],
+    /// `fields` are the custom SSE fields of the event, as names and values.
+    pub fields: &'a [(String, String)],
+    /// `id` is the `id` field of the event.
+    pub id: Option<&'a Cow<'static, str>>,
+    /// `retry` is the `retry` field of the event.
+    pub retry: Option<Duration>,
+    /// `data` is the data lines of the event joined by `\n`, or `None` if it has none.
+    pub data: Option<String>,
+}
+
+impl SsePayload<'_> {
+    /// Returns the name of the event type, custom or not.
+    pub fn event_name(&self) -> Option<&str> {
+        self.custom_event
+            .or_else(|| self.event_type.map(|event_type| event_type.as_static_str()))
+    }
+
+    /// Returns an error if the SSE event type of `framework`, which has no custom fields and
+    /// holds at most `max_comments` comments, can't hold every part of the payload.
+    #[cfg(any(feature = "axum", feature = "rocket", feature = "warp"))]
+    pub(crate) fn check_fits(
+        &self,
+        framework: &'static str,
+        max_comments: usize,
+    ) -> Result<(), crate::FieldError> {
+        let part = if !self.fields.is_empty() {
+            "custom fields"
+        } else if self.comments.len() > max_comments {
+            "several comments"
+        } else {
+            return Ok(());
+        };
+        Err(crate::FieldError::Unsupported { framework, part })
+    }
+
+    /// Panics if the SSE event type of `framework` can't hold every part of the payload, as
+    /// checked by [`check_fits`](Self::check_fits).
+    #[cfg(any(feature = "axum", feature = "rocket", feature = "warp"))]
+    #[track_caller]
+    pub(crate) fn assert_fits(&self, framework: &'static str, max_comments: usize) {
+        if let Err(err) = self.check_fits(framework, max_comments) {
+            panic!("{err}");
+        }
+    }
+}
+
+/// [`ToSsePayload`] is implemented by the events and builders the framework integrations
+/// can write as SSE events.
+pub trait ToSsePayload {
+    /// Returns the [`SsePayload`] of the event.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{prelude::PatchSignals, sse_payload::ToSsePayload};
+    ///
+    /// let patch = PatchSignals::new("{count: 1}").id("counter-1");
+    /// let payload = patch.sse_payload();
+    ///
+    /// assert_eq!(payload.event_name(), Some("datastar-patch-signals"));
+    /// assert_eq!(payload.id.map(|id| id.as_ref()), Some("counter-1"));
+    /// assert_eq!(payload.data.as_deref(), Some("signals {count: 1}"));
+    /// ```
+    fn sse_payload(&self) -> SsePayload<'_>;
+}
+
+impl ToSsePayload for DatastarEvent {
+    fn sse_payload(&self) -> SsePayload<'_> {
+        SsePayload {
+            event_type: self.event,
+            custom_event: self.custom_event.as_deref(),
+            comments: &self.comments,
+            fields: &self.fields,
+            id: self.id.as_ref(),
+            retry: self.retry,
+            data: (!self.data.is_empty()).then(|| self.data.to_text()),
+        }
+    }
+}
+
+impl ToSsePayload for PatchElements {
+    fn sse_payload(&self) -> SsePayload<'_> {
+        builder_payload(
+            EventType::PatchElements,
+            self.id.as_ref(),
+            self.retry,
+            self.data_capacity(),
+            |data| self.write_data_lines(data),
+        )
+    }
+}
+
+impl ToSsePayload for PatchSignals {
+    fn sse_payload(&self) -> SsePayload<'_> {
+        builder_payload(
+            EventType::PatchSignals,
+            self.id.as_ref(),
+            self.retry,
+            self.data_capacity(),
+            |data| self.write_data_lines(data),
+        )
+    }
+}
+
+impl ToSsePayload for ExecuteScript {
+    fn sse_payload(&self) -> SsePayload<'_> {
+        builder_payload(
+            EventType::PatchElements,
+            self.id.as_ref(),
+            self.retry,
+            self.data_capacity(),
+            |data| self.write_data_lines(data),
+        )
+    }
+}
+
+/// Builds the [`SsePayload`] of a builder, whose data lines are written straight into its
+/// data rather than through a [`DatastarEvent`].
+fn builder_payload<'a>(
+    event_type: EventType,
+    id: Option<&'a Cow<'static, str>>,
+    retry: Option<Duration>,
+    capacity: usize,
+    write_data: impl FnOnce(&mut TextLines<String>),
+) -> SsePayload<'a> {
+    let mut data = TextLines::new(String::with_capacity(capacity));
+    write_data(&mut data);
+    let data = data.into_inner();
+
+    SsePayload {
+        event_type: Some(event_type),
+        custom_event: None,
+        comments: &[],
+        fields: &[],
+        id,
+        retry,
+        data: (!data.is_empty()).then_some(data),
+    }
+}
@@ -0,0 +1,211 @@
+//! [`Stall`] ends streams whose client stopped reading, such as half-dead mobile clients.
+//!
+//! The server only asks a response for its next event once the previous one was written,
+//! so once the TCP buffers of a client which stopped acknowledging data are full, the stream
+//! waits forever, pinning everything it holds, such as a subscription to a topic.
+//! [`Stall`] notices when the next event isn't asked for within a timeout, and drops the
+//! inner stream, which frees its resources. The stream ends as soon as it is polled again.
+
+use {
+    crate::{
+        DatastarEvent,
+        clock::{Clock, TokioClock},
+        response::IntoDatastarResponse,
+    },
+    core::{
+        fmt,
+        pin::Pin,
+        task::{Context, Poll},
+        time::Duration,
+    },
+    futures_core::Stream,
+    std::sync::{Arc, Mutex, PoisonError},
+    tokio::task::JoinHandle,
+};
+
+/// [`StallExt`] adds the [`with_stall_timeout`](StallExt::with_stall_timeout) combinator
+/// to every [`Stream`] of [`DatastarEvent`]s.
+pub trait StallExt: Stream<Item = DatastarEvent> + Send + Sized + 'static {
+    /// Drops the stream when the next event isn't asked for within `timeout` after an event was sent.
+    ///
+    /// The stream has to be polled within a tokio runtime, which runs the stall detection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::time::Duration;
+    /// use datastar_core::{sender, stall::StallExt};
+    ///
+    /// let (sender, receiver) = sender::channel();
+    ///
+    /// // Return this from a handler instead of `receiver`.
+    /// let response = receiver
+    ///     .with_stall_timeout(Duration::from_secs(30))
+    ///     .on_stall(|| eprintln!("dropped a stalled connection"));
+    /// # drop((sender, response));
+    /// ```
+    fn with_stall_timeout(self, timeout: Duration) -> Stall<Self, TokioClock> {
+        self.with_stall_timeout_clock(timeout, TokioClock)
+    }
+
+    /// Drops the stream when the next event isn't asked for within `timeout` after an event was sent,
+    /// as measured by the given [`Clock`].
+    fn with_stall_timeout_clock<C: Clock>(self, timeout: Duration, clock: C) -> Stall<Self, C> {
+        Stall {
+            state: Arc::new(Mutex::new(State {
+                stream: Some(Box::pin(self)),
+                sent_at: None,
+            })),
+            clock: Arc::new(clock),
+            timeout,
+            on_stall: None,
+            watchdog: None,
+        }
+    }
+}
+
+impl<S: Stream<Item = DatastarEvent> + Send + 'static> StallExt for S {}
+
+type Hook = Arc<dyn Fn() + Send + Sync>;
+
+struct State<S> {
+    /// The inner stream, which is dropped once it ends or stalls.
+    stream: Option<Pin<Box<S>>>,
+    /// When the last event was handed out, as long as the next one wasn't asked for.
+    sent_at: Option<Duration>,
+}
+
+/// [`Stall`] is the [`Stream`] returned by [`StallExt::with_stall_timeout`].
+///
+/// It ends when the inner stream ends or stalls.
+pub struct Stall<S, C: Clock> {
+    state: Arc<Mutex<State<S>>>,
+    clock: Arc<C>,
+    timeout: Duration,
+    on_stall: Option<Hook>,
+    watchdog: Option<JoinHandle<()>>,
+}
+
+// Nothing is pinned structurally, as the stream is boxed.
+impl<S, C: Clock> Unpin for Stall<S, C> {}
+
+impl<S, C: Clock> Stall<S, C> {
+    /// Sets a hook which is called once the stream stalled, after the inner stream was dropped.
+    ///
+    /// With the `metrics` feature, stalls are counted by `datastar_stalled_connections_total` as well.
+    pub fn on_stall(mut self, hook: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_stall = Some(Arc::new(hook));
+        self
+    }
+}
+
+impl<S, C: Clock> fmt::Debug for Stall<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stall")
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, C: Clock> Drop for Stall<S, C> {
+    fn drop(&mut self) {
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.abort();
+        }
+    }
+}
+
+impl<S, C> Stream for Stall<S, C>
+where
+    S: Stream<Item = DatastarEvent> + Send + 'static,
+    C: Clock,
+{
+    type Item = DatastarEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.watchdog.is_none() {
+            let watchdog = watch(
+                self.state.clone(),
+                self.clock.clone(),
+                self.timeout,
+                self.on_stall.clone(),
+            );
+            self.watchdog = Some(tokio::spawn(watchdog));
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.sent_at = None;
+        let Some(stream) = state.stream.as_mut() else {
+            return Poll::Ready(None);
+        };
+
+        match stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(event)) => {
+                state.sent_at = Some(self.clock.now());
+                Poll::Ready(Some(event))
+            }
+            Poll::Ready(None) => {
+                state.stream = None;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Waits until the last event was sent `timeout` ago without the next one being asked for.
+async fn watch<S, C: Clock>(
+    state: Arc<Mutex<State<S>>>,
+    clock: Arc<C>,
+    timeout: Duration,
+    on_stall: Option<Hook>,
+) {
+    loop {
+        let wait = {
+            let state = state.lock().unwrap_or_else(PoisonError::into_inner);
+            if state.stream.is_none() {
+                return;
+            }
+            match state.sent_at {
+                Some(sent_at) => (sent_at + timeout).saturating_sub(clock.now()),
+                None => timeout,
+            }
+        };
+        clock.sleep(wait).await;
+
+        let stalled = {
+            let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+            match state.sent_at {
+                Some(sent_at) if clock.now().saturating_sub(sent_at) >= timeout => {
+                    state.stream.take()
+                }
+                _ => continue,
+            }
+        };
+        // Dropped outside of the lock, as dropping the stream can take a while.
+        drop(stalled);
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?timeout, "dropped a stalled stream");
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!("datastar_stalled_connections_total").increment(1);
+
+        if let Some(on_stall) = on_stall {
+            on_stall();
+        }
+        return;
+    }
+}
+
+impl<S, C> IntoDatastarResponse for Stall<S, C>
+where
+    S: Stream<Item = DatastarEvent> + Send + 'static,
+    C: Clock,
+{
+    type Stream = Self;
+
+    fn into_datastar_stream(self) -> Self::Stream {
+        self
+    }
+}
@@ -0,0 +1,105 @@
+//! [`DatastarStreamExt`] converts a plain [`Stream`] of events into the SSE response of
+//! a framework at the end of a handler, rather than writing every event as an SSE event
+//! of the framework where it is yielded.
+
+use {
+    crate::{DatastarEvent, response::DatastarStream},
+    futures_core::Stream,
+};
+
+/// [`DatastarStreamExt`] adds conversions into the SSE responses of the enabled frameworks
+/// to every [`Stream`] of values which convert into a [`DatastarEvent`].
+///
+/// The conversions return the `DatastarResponse` of the framework, so the events are written
+/// like it writes them, custom SSE fields included.
+pub trait DatastarStreamExt: Stream + Sized {
+    /// Converts the stream into an Axum [`DatastarResponse`](crate::axum::DatastarResponse).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use axum::response::IntoResponse;
+    /// use datastar_core::{prelude::PatchElements, stream::DatastarStreamExt};
+    /// use tokio_stream::{self as stream, StreamExt};
+    ///
+    /// async fn countdown() -> impl IntoResponse {
+    ///     stream::iter((0..3).rev())
+    ///         .map(|n| PatchElements::new(format!("<div id='count'>{n}</div>")))
+    ///         .into_axum_sse()
+    /// }
+    /// ```
+    #[cfg(feature = "axum")]
+    fn into_axum_sse(
+        self,
+    ) -> crate::axum::DatastarResponse<DatastarStream<impl Stream<Item = DatastarEvent> + Send>>
+    where
+        Self: Send + 'static,
+        Self::Item: Into<DatastarEvent>,
+    {
+        use futures_util::StreamExt;
+
+        crate::axum::DatastarResponse(DatastarStream(self.map(Into::into)))
+    }
+
+    /// Converts the stream into a Warp [`DatastarResponse`](crate::warp::DatastarResponse).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{prelude::PatchElements, stream::DatastarStreamExt};
+    /// use tokio_stream::{self as stream, StreamExt};
+    /// use warp::Filter;
+    ///
+    /// let route = warp::path("countdown").map(|| {
+    ///     stream::iter((0..3).rev())
+    ///         .map(|n| PatchElements::new(format!("<div id='count'>{n}</div>")))
+    ///         .into_warp_sse()
+    /// });
+    /// ```
+    #[cfg(feature = "warp")]
+    fn into_warp_sse(
+        self,
+    ) -> crate::warp::DatastarResponse<DatastarStream<impl Stream<Item = DatastarEvent> + Send>>
+    where
+        Self: Send + 'static,
+        Self::Item: Into<DatastarEvent>,
+    {
+        use futures_util::StreamExt;
+
+        crate::warp::DatastarResponse(DatastarStream(self.map(Into::into)))
+    }
+
+    /// Converts the stream into a Rocket [`DatastarResponse`](crate::rocket::DatastarResponse).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use datastar_core::{
+    ///     prelude::PatchElements, response::IntoDatastarResponse, rocket::DatastarResponse,
+    ///     stream::DatastarStreamExt,
+    /// };
+    /// use rocket::get;
+    /// use tokio_stream::{self as stream, StreamExt};
+    ///
+    /// #[get("/countdown")]
+    /// fn countdown() -> DatastarResponse<impl IntoDatastarResponse> {
+    ///     stream::iter((0..3).rev())
+    ///         .map(|n| PatchElements::new(format!("<div id='count'>{n}</div>")))
+    ///         .into_rocket_events()
+    /// }
+    /// ```
+    #[cfg(feature = "rocket")]
+    fn into_rocket_events(
+        self,
+    ) -> crate::rocket::DatastarResponse<DatastarStream<impl Stream<Item = DatastarEvent> + Send>>
+    where
+        Self: Send + 'static,
+        Self::Item: Into<DatastarEvent>,
+    {
+        use rocket::futures::StreamExt;
+
+        crate::rocket::DatastarResponse(DatastarStream(self.map(Into::into)))
+    }
+}
+
+impl<S: Stream> DatastarStreamExt for S {}
@@ -0,0 +1,69 @@
+//! Renders the elements of a [`PatchElements`] event from a named template, with the
+//! `minijinja` or `tera` feature.
+//!
+//! [`PatchElements::render_with`] renders and wraps the template in one call, returning
+//! [`Error::Template`] if it fails, so a missing template or variable can be handled with `?`
+//! rather than unwrapped inside a stream.
+
+use {
+    crate::{Error, patch_elements::PatchElements},
+    serde::Serialize,
+};
+
+/// [`Templates`] is a template engine which renders templates by name, implemented for
+/// a `minijinja::Environment` and a `tera::Tera`.
+pub trait Templates {
+    /// Renders the template `name` with `context`.
+    fn render_template<C: Serialize>(&self, name: &str, context: C) -> Result<String, Error>;
+}
+
+#[cfg(feature = "minijinja")]
+impl Templates for minijinja::Environment<'_> {
+    fn render_template<C: Serialize>(&self, name: &str, context: C) -> Result<String, Error> {
+        self.get_template(name)
+            .and_then(|template| template.render(context))
+            .map_err(|err| Error::Template(Box::new(err)))
+    }
+}
+
+/// A `tera::Context` is passed with [`into_json`](tera::Context::into_json).
+#[cfg(feature = "tera")]
+impl Templates for tera::Tera {
+    fn render_template<C: Serialize>(&self, name: &str, context: C) -> Result<String, Error> {
+        tera::Context::from_serialize(context)
+            .and_then(|context| self.render(name, &context))
+            .map_err(|err| Error::Template(Box::new(err)))
+    }
+}
+
+impl PatchElements {
+    /// Creates a new [`PatchElements`] event with the template `name` of `templates`,
+    /// rendered with `context`.
+    ///
+    /// # Examples
+    ///
+    /// With the `minijinja` feature:
+    ///
+    #[cfg_attr(feature = "minijinja", doc = "```")]
+    #[cfg_attr(not(feature = "minijinja"), doc = "```ignore")]
+    /// use datastar_core::prelude::PatchElements;
+    /// use minijinja::{Environment, context};
+    ///
+    /// let mut env = Environment::new();
+    /// env.add_template("greeting", "<div id='greeting'>Hello, {{ name }}!</div>")
+    ///     .unwrap();
+    ///
+    /// let patch = PatchElements::render_with(&env, "greeting", context! { name => "Ada" })?;
+    /// assert_eq!(patch.elements.as_deref(), Some("<div id='greeting'>Hello, Ada!</div>"));
+    ///
+    /// assert!(PatchElements::render_with(&env, "farewell", ()).is_err());
+    /// # Ok::<(), datastar_core::Error>(())
+    /// ```
+    pub fn render_with(
+        templates: &impl Templates,
+        name: &str,
+        context: impl Serialize,
+    ) -> Result<Self, Error> {
+        templates.render_template(name, context).map(Self::new)
+    }
+}
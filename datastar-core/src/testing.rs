@@ -0,0 +1,631 @@
+//! [`SseTestClient`] sends requests to a Datastar backend in tests and decodes the event
+//! stream of the response, with the `testing` feature.
+//!
+//! Handlers are tested with the [`DatastarEvent`]s they send, decoded into [`PatchElements`]
+//! and [`PatchSignals`], rather than by matching the raw SSE text. Requests are sent to an
+//! Axum [`Router`](axum::Router) or any other Axum [`Service`] in process, or with the
+//! `reqwest` feature to a running server.
+
+#[cfg(feature = "dom_query")]
+use crate::consts::ElementPatchMode;
+use {
+    crate::{
+        DatastarEvent,
+        consts::{DATASTAR_REQ_HEADER_STR, EventType},
+        parse::{EventStreamDecoder, ParseError},
+        patch_elements::PatchElements,
+        patch_signals::PatchSignals,
+        query,
+    },
+    axum::{
+        body::{self, Body},
+        extract::Request,
+        http::{HeaderMap, Method, StatusCode, header},
+        response::Response,
+    },
+    core::fmt,
+    futures_util::future::{self, BoxFuture},
+    serde::Serialize,
+    serde_json::{Map, Value},
+    std::sync::Arc,
+    tower_service::Service,
+};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+type SendRequest =
+    Arc<dyn Fn(Request) -> BoxFuture<'static, Result<Response, BoxError>> + Send + Sync>;
+
+/// [`SseTestClient`] sends Datastar requests and decodes the events of the responses.
+///
+/// Requests carry the `datastar-request` header and the signals like the Datastar client
+/// sends them: in the `datastar` query parameter for `GET` requests, and as a JSON body
+/// otherwise. The response is read to its end, so the handler has to end its stream.
+///
+/// # Examples
+///
+/// ```
+/// use axum::{Router, http::StatusCode, routing::get};
+/// use datastar_core::{prelude::PatchSignals, testing::SseTestClient};
+/// use serde_json::json;
+///
+/// async fn count() -> PatchSignals {
+///     PatchSignals::new(r#"{"count":1}"#)
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// let client = SseTestClient::new(Router::new().route("/count", get(count)));
+///
+/// client
+///     .get("/count")
+///     .await?
+///     .expect_status(StatusCode::OK)
+///     .expect_patch_signals(json!({ "count": 1 }));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SseTestClient {
+    transport: Transport,
+}
+
+#[derive(Clone)]
+enum Transport {
+    Service(SendRequest),
+    #[cfg(feature = "reqwest")]
+    Url {
+        client: reqwest::Client,
+        base: String,
+    },
+}
+
+impl fmt::Debug for SseTestClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("SseTestClient");
+        match &self.transport {
+            Transport::Service(_) => debug.field("transport", &"service"),
+            #[cfg(feature = "reqwest")]
+            Transport::Url { base, .. } => debug.field("transport", base),
+        };
+        debug.finish()
+    }
+}
+
+impl SseTestClient {
+    /// Creates a new [`SseTestClient`] sending requests to `service`, such as an Axum
+    /// [`Router`](axum::Router), without a server.
+    pub fn new<S>(service: S) -> Self
+    where
+        S: Service<Request, Response = Response> + Clone + Send + Sync + 'static,
+        S::Future: Send + 'static,
+        S::Error: Into<BoxError>,
+    {
+        let send = move |req: Request| -> BoxFuture<'static, Result<Response, BoxError>> {
+            let mut service = service.clone();
+            Box::pin(async move {
+                future::poll_fn(|cx| service.poll_ready(cx))
+                    .await
+                    .map_err(Into::into)?;
+                service.call(req).await.map_err(Into::into)
+            })
+        };
+        Self {
+            transport: Transport::Service(Arc::new(send)),
+        }
+    }
+
+    /// Creates a new [`SseTestClient`] sending requests to the server at `base`,
+    /// such as `http://127.0.0.1:3000`, with the `reqwest` feature.
+    #[cfg(feature = "reqwest")]
+    pub fn url(base: impl Into<String>) -> Self {
+        Self {
+            transport: Transport::Url {
+                client: reqwest::Client::new(),
+                base: base.into().trim_end_matches('/').to_owned(),
+            },
+        }
+    }
+
+    /// Sends a `GET` request without signals to `path`.
+    pub async fn get(&self, path: &str) -> Result<SseResponse, BoxError> {
+        self.send(Method::GET, path.to_owned(), None).await
+    }
+
+    /// Sends a `GET` request with `signals` to `path`.
+    pub async fn get_with_signals(
+        &self,
+        path: &str,
+        signals: &impl Serialize,
+    ) -> Result<SseResponse, BoxError> {
+        self.request(Method::GET, path, signals).await
+    }
+
+    /// Sends a `POST` request with `signals` to `path`.
+    pub async fn post(
+        &self,
+        path: &str,
+        signals: &impl Serialize,
+    ) -> Result<SseResponse, BoxError> {
+        self.request(Method::POST, path, signals).await
+    }
+
+    /// Sends a request with `signals` to `path`, in the query for `GET` requests
+    /// and in the body for any other method.
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        signals: &impl Serialize,
+    ) -> Result<SseResponse, BoxError> {
+        if method == Method::GET {
+            let query = query::to_query(signals)?;
+            let separator = if path.contains('?') { '&' } else { '?' };
+            return self
+                .send(method, format!("{path}{separator}{query}"), None)
+                .await;
+        }
+        let body = serde_json::to_string(signals)?;
+        self.send(method, path.to_owned(), Some(body)).await
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: String,
+        body: Option<String>,
+    ) -> Result<SseResponse, BoxError> {
+        match &self.transport {
+            Transport::Service(send) => {
+                let mut req = Request::builder()
+                    .method(method)
+                    .uri(path)
+                    .header(DATASTAR_REQ_HEADER_STR, "true")
+                    .header(header::ACCEPT, "text/event-stream");
+                if body.is_some() {
+                    req = req.header(header::CONTENT_TYPE, "application/json");
+                }
+                let req = req.body(body.map_or_else(Body::empty, Body::from))?;
+
+                let res = send(req).await?;
+                let (parts, body) = res.into_parts();
+                let body = body::to_bytes(body, usize::MAX).await?;
+                Ok(SseResponse::from_body(parts.status, parts.headers, &body)?)
+            }
+            #[cfg(feature = "reqwest")]
+            Transport::Url { client, base } => {
+                let mut req = client
+                    .request(method, format!("{base}{path}"))
+                    .header(DATASTAR_REQ_HEADER_STR, "true")
+                    .header(header::ACCEPT, "text/event-stream");
+                if let Some(body) = body {
+                    req = req
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .body(body);
+                }
+
+                let res = req.send().await?;
+                let (status, headers) = (res.status(), res.headers().clone());
+                let body = res.bytes().await?;
+                Ok(SseResponse::from_body(status, headers, &body)?)
+            }
+        }
+    }
+}
+
+/// [`SseResponse`] is a response received by [`SseTestClient`], with its decoded events.
+#[derive(Debug, Clone)]
+pub struct SseResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    events: Vec<DatastarEvent>,
+}
+
+impl SseResponse {
+    /// Creates a new [`SseResponse`] from a response received by another client.
+    ///
+    /// The body is only decoded when the response is an event stream, so the events
+    /// of any other response are empty.
+    pub fn from_body(
+        status: StatusCode,
+        headers: HeaderMap,
+        body: &[u8],
+    ) -> Result<Self, ParseError> {
+        let is_event_stream = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+
+        let mut events = Vec::new();
+        if is_event_stream {
+            let mut decoder = EventStreamDecoder::new();
+            decoder.feed(body);
+            while let Some(event) = decoder.next_event()? {
+                events.push(event);
+            }
+        }
+
+        Ok(Self {
+            status,
+            headers,
+            events,
+        })
+    }
+
+    /// Returns the status of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the events of the response, in the order they were sent.
+    pub fn events(&self) -> &[DatastarEvent] {
+        &self.events
+    }
+
+    /// Returns the [`PatchElements`] events of the response.
+    pub fn patch_elements(&self) -> Vec<PatchElements> {
+        self.events
+            .iter()
+            .filter_map(|event| PatchElements::try_from(event.clone()).ok())
+            .collect()
+    }
+
+    /// Returns the [`PatchSignals`] events of the response.
+    pub fn patch_signals(&self) -> Vec<PatchSignals> {
+        self.events
+            .iter()
+            .filter_map(|event| PatchSignals::try_from(event.clone()).ok())
+            .collect()
+    }
+
+    /// Asserts that the response has the given status.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the status differs.
+    #[track_caller]
+    pub fn expect_status(&self, status: StatusCode) -> &Self {
+        assert_eq!(self.status, status, "unexpected status of the response");
+        self
+    }
+
+    /// Asserts that a [`PatchElements`] event of the response contains the HTML `html`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no [`PatchElements`] event contains `html`.
+    #[track_caller]
+    pub fn expect_patch_elements(&self, html: &str) -> &Self {
+        let patches = self.patch_elements();
+        let found = patches
+            .iter()
+            .filter_map(|patch| patch.elements.as_deref())
+            .any(|elements| elements.contains(html));
+        assert!(
+            found,
+            "no patch elements event contains {html:?}, the events are {patches:#?}"
+        );
+        self
+    }
+
+    /// Asserts that a [`PatchSignals`] event of the response patches exactly the JSON
+    /// `signals`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no [`PatchSignals`] event equals `signals`, or if they aren't JSON.
+    #[track_caller]
+    pub fn expect_patch_signals(&self, signals: serde_json::Value) -> &Self {
+        let patches = self.patch_signals();
+        let found = patches.iter().any(|patch| {
+            serde_json::from_str::<serde_json::Value>(&patch.signals)
+                .is_ok_and(|patched| patched == signals)
+        });
+        assert!(
+            found,
+            "no patch signals event patches {signals}, the events are {patches:#?}"
+        );
+        self
+    }
+}
+
+/// [`MockBrowser`] applies the events of responses like the Datastar client, so tests can
+/// assert on the state of the page after a stream rather than on the events.
+///
+/// It keeps a signal store, which [`PatchSignals`] events are merged into as JSON merge
+/// patches, removing the signals set to `null`. With the `dom_query` feature, it also keeps
+/// a page which [`PatchElements`] events are applied to with their
+/// [`ElementPatchMode`](crate::consts::ElementPatchMode),
+/// where an `outer` morph replaces the target. Without it, [`PatchElements`] events are
+/// ignored.
+///
+/// # Examples
+///
+/// ```
+/// use axum::{Router, routing::post};
+/// use datastar_core::{
+///     axum::ReadSignals,
+///     prelude::PatchSignals,
+///     testing::{MockBrowser, SseTestClient},
+/// };
+/// use serde_json::{Value, json};
+///
+/// async fn increment(ReadSignals(signals): ReadSignals<Value>) -> PatchSignals {
+///     let count = signals["count"].as_i64().unwrap_or_default();
+///     PatchSignals::new(json!({ "count": count + 1 }).to_string())
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// let client = SseTestClient::new(Router::new().route("/increment", post(increment)));
+/// let mut browser = MockBrowser::new().with_signals(json!({ "count": 1, "_open": true }));
+///
+/// browser.post(&client, "/increment").await?;
+/// browser.post(&client, "/increment").await?;
+///
+/// browser.expect_signal("count", json!(3));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct MockBrowser {
+    signals: Map<String, Value>,
+    #[cfg(feature = "dom_query")]
+    document: dom_query::Document,
+}
+
+impl fmt::Debug for MockBrowser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("MockBrowser");
+        debug.field("signals", &self.signals);
+        #[cfg(feature = "dom_query")]
+        debug.field("html", &self.html());
+        debug.finish()
+    }
+}
+
+impl MockBrowser {
+    /// Creates a new [`MockBrowser`] without signals, on an empty page.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new [`MockBrowser`] without signals, on the page `html`.
+    #[cfg(feature = "dom_query")]
+    pub fn with_html(html: &str) -> Self {
+        Self {
+            signals: Map::new(),
+            document: dom_query::Document::from(html),
+        }
+    }
+
+    /// Merges `signals` into the signal store, like the `data-signals` attributes of a page.
+    ///
+    /// Anything but a JSON object is ignored.
+    pub fn with_signals(mut self, signals: Value) -> Self {
+        if let Value::Object(signals) = signals {
+            merge_signals(&mut self.signals, signals, false);
+        }
+        self
+    }
+
+    /// Returns the signal store.
+    pub fn signals(&self) -> &Map<String, Value> {
+        &self.signals
+    }
+
+    /// Returns the signal at the dotted `path`, such as `user.name`.
+    pub fn signal(&self, path: &str) -> Option<&Value> {
+        let mut names = path.split('.');
+        let first = self.signals.get(names.next()?)?;
+        names.try_fold(first, |signal, name| signal.get(name))
+    }
+
+    /// Returns the signals the Datastar client sends with a request, which excludes
+    /// the local signals whose names start with `_`.
+    pub fn request_signals(&self) -> Value {
+        fn exclude_local(signals: &Map<String, Value>) -> Map<String, Value> {
+            signals
+                .iter()
+                .filter(|(name, _)| !name.starts_with('_'))
+                .map(|(name, value)| match value {
+                    Value::Object(signals) => (name.clone(), Value::Object(exclude_local(signals))),
+                    value => (name.clone(), value.clone()),
+                })
+                .collect()
+        }
+        Value::Object(exclude_local(&self.signals))
+    }
+
+    /// Sends a `GET` request with the signals to `path` and applies the events of the response.
+    pub async fn get(
+        &mut self,
+        client: &SseTestClient,
+        path: &str,
+    ) -> Result<SseResponse, BoxError> {
+        self.request(client, Method::GET, path).await
+    }
+
+    /// Sends a `POST` request with the signals to `path` and applies the events of the response.
+    pub async fn post(
+        &mut self,
+        client: &SseTestClient,
+        path: &str,
+    ) -> Result<SseResponse, BoxError> {
+        self.request(client, Method::POST, path).await
+    }
+
+    /// Sends a request with the signals to `path` and applies the events of the response.
+    pub async fn request(
+        &mut self,
+        client: &SseTestClient,
+        method: Method,
+        path: &str,
+    ) -> Result<SseResponse, BoxError> {
+        let response = client
+            .request(method, path, &self.request_signals())
+            .await?;
+        self.apply_response(&response)?;
+        Ok(response)
+    }
+
+    /// Applies the events of `response`, in the order they were sent.
+    pub fn apply_response(&mut self, response: &SseResponse) -> Result<(), ParseError> {
+        response
+            .events()
+            .iter()
+            .try_for_each(|event| self.apply(event))
+    }
+
+    /// Applies `event`, ignoring events which aren't [`PatchSignals`] or [`PatchElements`].
+    ///
+    /// Fails if the signals of a [`PatchSignals`] event aren't JSON.
+    pub fn apply(&mut self, event: &DatastarEvent) -> Result<(), ParseError> {
+        match event.event {
+            Some(EventType::PatchSignals) => {
+                let patch = PatchSignals::try_from(event.clone())?;
+                let Ok(Value::Object(signals)) = serde_json::from_str(&patch.signals) else {
+                    return Err(ParseError::InvalidDataLine(format!(
+                        "signals {}",
+                        patch.signals
+                    )));
+                };
+                merge_signals(&mut self.signals, signals, patch.only_if_missing);
+            }
+            #[cfg(feature = "dom_query")]
+            Some(EventType::PatchElements) => {
+                self.patch_elements(&PatchElements::try_from(event.clone())?);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns the HTML of the page.
+    #[cfg(feature = "dom_query")]
+    pub fn html(&self) -> String {
+        self.document.html().to_string()
+    }
+
+    /// Returns the HTML of the first element matching `selector`, including the element.
+    #[cfg(feature = "dom_query")]
+    pub fn element(&self, selector: &str) -> Option<String> {
+        let element = self.document.try_select(selector)?;
+        Some(element.first().html().to_string())
+    }
+
+    /// Returns the text of the first element matching `selector`.
+    #[cfg(feature = "dom_query")]
+    pub fn text(&self, selector: &str) -> Option<String> {
+        let element = self.document.try_select(selector)?;
+        Some(element.first().text().to_string())
+    }
+
+    /// Asserts that the signal at the dotted `path` equals `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the signal is missing or differs.
+    #[track_caller]
+    pub fn expect_signal(&self, path: &str, value: Value) -> &Self {
+        assert_eq!(
+            self.signal(path),
+            Some(&value),
+            "unexpected signal {path}, the signals are {:#?}",
+            self.signals
+        );
+        self
+    }
+
+    /// Asserts that the first element matching `selector` contains the HTML `html`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no element matches `selector`, or it doesn't contain `html`.
+    #[cfg(feature = "dom_query")]
+    #[track_caller]
+    pub fn expect_element(&self, selector: &str, html: &str) -> &Self {
+        match self.element(selector) {
+            Some(element) => assert!(
+                element.contains(html),
+                "the element {selector} doesn't contain {html:?}, it is {element:?}"
+            ),
+            None => panic!(
+                "no element matches {selector}, the page is {:?}",
+                self.html()
+            ),
+        }
+        self
+    }
+
+    #[cfg(feature = "dom_query")]
+    fn patch_elements(&self, patch: &PatchElements) {
+        let elements = patch.elements.as_deref().unwrap_or_default();
+        if let Some(selector) = &patch.selector {
+            if let Some(targets) = self.document.try_select(selector) {
+                patch_targets(&targets, patch.mode, elements);
+            }
+            return;
+        }
+
+        // Without a selector, every top-level element targets the element with its id.
+        let fragment = dom_query::Document::from(format!("<body>{elements}</body>"));
+        for element in fragment.select("body > *").nodes() {
+            let Some(id) = element.attr("id") else {
+                continue;
+            };
+            let targets = self.document.try_select(&crate::selector::id_selector(&id));
+            if let Some(targets) = targets {
+                patch_targets(&targets, patch.mode, &element.html());
+            }
+        }
+    }
+}
+
+/// Merges the `patch` into the `signals` as a JSON merge patch, only adding the signals
+/// which are missing if `only_if_missing` is set.
+fn merge_signals(
+    signals: &mut Map<String, Value>,
+    patch: Map<String, Value>,
+    only_if_missing: bool,
+) {
+    for (name, value) in patch {
+        match value {
+            Value::Null if only_if_missing => {}
+            Value::Null => {
+                signals.remove(&name);
+            }
+            Value::Object(patch) => match signals.get_mut(&name) {
+                Some(Value::Object(signals)) => merge_signals(signals, patch, only_if_missing),
+                Some(_) if only_if_missing => {}
+                _ => {
+                    let mut object = Map::new();
+                    merge_signals(&mut object, patch, only_if_missing);
+                    signals.insert(name, Value::Object(object));
+                }
+            },
+            _ if only_if_missing && signals.contains_key(&name) => {}
+            value => {
+                signals.insert(name, value);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "dom_query")]
+fn patch_targets(targets: &dom_query::Selection<'_>, mode: ElementPatchMode, html: &str) {
+    match mode {
+        ElementPatchMode::Outer | ElementPatchMode::Replace => targets.replace_with_html(html),
+        ElementPatchMode::Inner => targets.set_html(html),
+        ElementPatchMode::Remove => targets.remove(),
+        ElementPatchMode::Prepend => targets.prepend_html(html),
+        ElementPatchMode::Append => targets.append_html(html),
+        ElementPatchMode::Before => targets.before_html(html),
+        ElementPatchMode::After => targets.after_html(html),
+    }
+}
@@ -0,0 +1,1134 @@
+//! Warp integration for Datastar.
+
+#[cfg(feature = "multipart")]
+use crate::multipart::{self, Files};
+#[cfg(feature = "inspector")]
+use {
+    crate::inspector::{Inspection, Inspector},
+    warp::path::FullPath,
+};
+use {
+    crate::{
+        Error,
+        accept::{self, accepts_event_stream},
+        batch::{self, DatastarEventBatch},
+        compression,
+        consts::DATASTAR_REQ_HEADER_STR,
+        fragment::FragmentResponse,
+        json,
+        prelude::{DatastarEvent, ExecuteScript, PatchElements, PatchSignals},
+        replay::{Journal, LAST_EVENT_ID_HEADER_STR, LastEventId},
+        response::{Batch, DatastarStream, IntoDatastarResponse},
+        scripts,
+        sender::DatastarReceiver,
+        signals::{self, ValidateSignals},
+        sse_payload::{SsePayload, ToSsePayload},
+    },
+    bytes::{Buf, BufMut, Bytes, BytesMut},
+    core::{
+        pin::{Pin, pin},
+        task::{Context, Poll},
+    },
+    futures_util::{Stream, StreamExt, stream},
+    serde::{Deserialize, de::DeserializeOwned},
+    std::{
+        convert::Infallible,
+        sync::{Arc, Mutex, PoisonError},
+    },
+    warp::{
+        Filter, Rejection, Reply,
+        filters::sse::Event,
+        http::{HeaderName, HeaderValue, Method, StatusCode},
+        reply::Response,
+    },
+};
+
+/// Datastar's headers, re-exported from [`consts::headers`](crate::consts::headers).
+pub mod header {
+    pub use crate::consts::headers::*;
+}
+
+impl PatchElements {
+    /// Write this [`PatchElements`] into a Warp SSE [`Event`].
+    pub fn write_as_warp_sse_event(&self) -> Event {
+        sse_event(self.sse_payload())
+    }
+}
+
+impl From<PatchElements> for Event {
+    fn from(value: PatchElements) -> Self {
+        value.write_as_warp_sse_event()
+    }
+}
+
+impl From<&PatchElements> for Event {
+    fn from(value: &PatchElements) -> Self {
+        value.write_as_warp_sse_event()
+    }
+}
+
+impl PatchSignals {
+    /// Write this [`PatchSignals`] into a Warp SSE [`Event`].
+    pub fn write_as_warp_sse_event(&self) -> Event {
+        sse_event(self.sse_payload())
+    }
+}
+
+impl From<PatchSignals> for Event {
+    fn from(value: PatchSignals) -> Self {
+        value.write_as_warp_sse_event()
+    }
+}
+
+impl From<&PatchSignals> for Event {
+    fn from(value: &PatchSignals) -> Self {
+        value.write_as_warp_sse_event()
+    }
+}
+
+impl ExecuteScript {
+    /// Write this [`ExecuteScript`] into a Warp SSE [`Event`].
+    pub fn write_as_warp_sse_event(&self) -> Event {
+        sse_event(self.sse_payload())
+    }
+}
+
+impl From<ExecuteScript> for Event {
+    fn from(value: ExecuteScript) -> Self {
+        value.write_as_warp_sse_event()
+    }
+}
+
+impl From<&ExecuteScript> for Event {
+    fn from(value: &ExecuteScript) -> Self {
+        value.write_as_warp_sse_event()
+    }
+}
+
+impl DatastarEvent {
+    /// Turn this [`DatastarEvent`] into a Warp SSE [`Event`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event has custom [`fields`](DatastarEvent::fields) or several
+    /// [`comments`](DatastarEvent::comments), as a Warp [`Event`] holds a single comment and no
+    /// custom fields, rather than dropping them.
+    pub fn write_as_warp_sse_event(&self) -> Event {
+        let payload = self.sse_payload();
+        payload.assert_fits("Warp", 1);
+        sse_event(payload)
+    }
+}
+
+/// Builds the Warp [`Event`] of an [`SsePayload`], which has a single comment at most and no
+/// custom fields.
+fn sse_event(payload: SsePayload<'_>) -> Event {
+    let mut event = Event::default();
+
+    if let Some(comment) = payload.comments.first() {
+        event = event.comment(comment);
+    }
+    if let Some(event_type) = payload.event_name() {
+        event = event.event(event_type);
+    }
+    if let Some(id) = payload.id {
+        event = event.id(id.clone());
+    }
+    if let Some(retry) = payload.retry {
+        event = event.retry(retry);
+    }
+
+    match payload.data {
+        Some(data) => event.data(data),
+        None => event,
+    }
+}
+
+impl From<DatastarEvent> for Event {
+    fn from(value: DatastarEvent) -> Self {
+        value.write_as_warp_sse_event()
+    }
+}
+
+impl From<&DatastarEvent> for Event {
+    fn from(value: &DatastarEvent) -> Self {
+        value.write_as_warp_sse_event()
+    }
+}
+
+/// [`DatastarResponse`] turns any [`IntoDatastarResponse`] into a Warp SSE [`Reply`].
+///
+/// The events are written as Warp SSE [`Event`]s. A Warp [`Event`] can't hold custom
+/// [`fields`](DatastarEvent::fields) or several [`comments`](DatastarEvent::comments), so the
+/// response ends with a [`FieldError::Unsupported`](crate::FieldError::Unsupported) at the
+/// first event that has them, rather than sending it truncated.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     prelude::PatchElements, response::IntoDatastarResponse, warp::DatastarResponse,
+/// };
+/// use warp::Filter;
+///
+/// fn greet(name: &str) -> impl IntoDatastarResponse {
+///     PatchElements::new(format!("<div id='greeting'>Hello, {name}!</div>"))
+/// }
+///
+/// let route = warp::path("greet").map(|| DatastarResponse(greet("world")));
+/// ```
+#[derive(Debug)]
+pub struct DatastarResponse<T>(pub T);
+
+impl<T: IntoDatastarResponse + Send> Reply for DatastarResponse<T> {
+    fn into_response(self) -> Response {
+        let stream = self.0.into_datastar_stream().map(|event| {
+            let payload = event.sse_payload();
+            payload.check_fits("Warp", 1).map(|()| sse_event(payload))
+        });
+        warp::sse::reply(SyncStream::new(stream)).into_response()
+    }
+}
+
+/// [`SyncStream`] makes any [`Send`] stream [`Sync`], as [`warp::sse::reply`] requires,
+/// so the streams of [`IntoDatastarResponse`] don't all have to be.
+///
+/// The stream is only polled through `&mut self`, so its [`Mutex`] is never locked.
+pub(crate) struct SyncStream<S>(Mutex<Pin<Box<S>>>);
+
+impl<S> SyncStream<S> {
+    pub(crate) fn new(stream: S) -> Self {
+        Self(Mutex::new(Box::pin(stream)))
+    }
+}
+
+impl<S: Stream> Stream for SyncStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut()
+            .0
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+            .poll_next(cx)
+    }
+}
+
+impl Reply for PatchElements {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl Reply for PatchSignals {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl Reply for ExecuteScript {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl Reply for DatastarEvent {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl Reply for Batch {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+/// Responds with the events as a single `text/event-stream` body rather than a stream,
+/// closing the connection once it's sent.
+impl Reply for DatastarEventBatch {
+    fn into_response(self) -> Response {
+        let mut response = self.to_text().into_response();
+        let headers = response.headers_mut();
+        for (name, value) in batch::RESPONSE_HEADERS {
+            headers.insert(
+                HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            );
+        }
+        response
+    }
+}
+
+impl Reply for DatastarReceiver {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl<S> Reply for crate::indicator::Indicator<S>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl<S, G> Reply for crate::event_id::WithEventIds<S, G>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    G: crate::event_id::EventIdGenerator,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl<S, C> Reply for crate::stall::Stall<S, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "broadcast")]
+impl Reply for crate::broadcast::Subscription {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+impl<S, C> Reply for crate::heartbeat::Heartbeat<S, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S, C> Reply for crate::coalesce::CoalesceSignals<S, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S, W, C> Reply for crate::recording::Recorder<S, W, C>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+    W: std::io::Write + Send + 'static,
+    C: crate::clock::Clock,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<C: crate::clock::Clock> Reply for crate::recording::Player<C> {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "broadcast")]
+impl Reply for crate::broadcast::HubMetrics {
+    fn into_response(self) -> Response {
+        warp::reply::with_header(
+            self.to_string(),
+            "content-type",
+            crate::broadcast::PROMETHEUS_CONTENT_TYPE,
+        )
+        .into_response()
+    }
+}
+
+#[cfg(feature = "inspector")]
+impl Reply for crate::inspector::InspectorPanel {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "inspector")]
+impl<S> Reply for crate::inspector::Inspected<S>
+where
+    S: futures_core::Stream<Item = DatastarEvent> + Send + 'static,
+{
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Reply for crate::redis::RedisSubscription {
+    fn into_response(self) -> Response {
+        DatastarResponse(self).into_response()
+    }
+}
+
+/// Responds with the elements as HTML and the headers telling Datastar how to patch them, or
+/// with the error of an invalid [`FragmentResponse`].
+impl Reply for FragmentResponse {
+    fn into_response(self) -> Response {
+        if let Err(err) = self.validate() {
+            let status =
+                StatusCode::from_u16(err.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            return warp::reply::with_status(err.to_string(), status).into_response();
+        }
+
+        let headers: Vec<_> = self
+            .headers()
+            .filter_map(|(name, value)| Some((name, HeaderValue::from_str(value).ok()?)))
+            .collect();
+        let mut response = warp::reply::html(self.elements.into_owned()).into_response();
+        for (name, value) in headers {
+            response.headers_mut().insert(name, value);
+        }
+        response
+    }
+}
+
+#[derive(Deserialize)]
+struct DatastarParam {
+    datastar: Option<String>,
+}
+
+impl warp::reject::Reject for Error {}
+
+/// [`ReadSignals`] is a wrapper type for extracted Datastar signals.
+///
+/// Read a [`WithRaw<T>`](crate::signals::WithRaw) to get the untyped JSON of the signals as well,
+/// or a [`Lenient<T>`](crate::signals::Lenient) to fall back to defaults for signals which don't fit.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::warp::{read_signals, ReadSignals};
+/// use serde::Deserialize;
+/// use warp::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     foo: String,
+///     bar: i32,
+/// }
+///
+/// let route = warp::path("hello")
+///     .and(read_signals::<Signals>())
+///     .map(|signals: ReadSignals<Signals>| {
+///         format!("foo: {}, bar: {}", signals.0.foo, signals.0.bar)
+///     });
+/// ```
+#[derive(Debug)]
+pub struct ReadSignals<T>(pub T);
+
+/// The largest body the signal extractors read by default, in bytes.
+pub const DEFAULT_BODY_LIMIT: u64 = 1024 * 1024;
+
+/// Creates a Warp Filter that extracts Datastar signals from the request.
+///
+/// For GET requests, signals are extracted from the `datastar` query parameter.
+/// For POST/PUT/PATCH requests, signals are extracted from the JSON or URL encoded form body,
+/// which is rejected with [`Error::PayloadTooLarge`] if it exceeds [`DEFAULT_BODY_LIMIT`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::warp::{read_signals, ReadSignals};
+/// use serde::Deserialize;
+/// use warp::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     delay: u64,
+/// }
+///
+/// let route = warp::path("hello")
+///     .and(warp::get())
+///     .and(read_signals::<Signals>())
+///     .map(|ReadSignals(signals): ReadSignals<Signals>| {
+///         format!("delay: {}", signals.delay)
+///     });
+/// ```
+pub fn read_signals<T>() -> impl Filter<Extract = (ReadSignals<T>,), Error = Rejection> + Clone
+where
+    T: DeserializeOwned + Send,
+{
+    read_signals_with_limit(DEFAULT_BODY_LIMIT)
+}
+
+/// Creates a Warp Filter that extracts Datastar signals from the request like [`read_signals`],
+/// rejecting bodies larger than `limit` bytes with [`Error::PayloadTooLarge`].
+///
+/// The body is read until it exceeds the limit, so an oversized body is never buffered whole.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::warp::{read_signals_with_limit, ReadSignals};
+/// use serde::Deserialize;
+/// use warp::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     comment: String,
+/// }
+///
+/// let route = warp::path("comment")
+///     .and(warp::post())
+///     .and(read_signals_with_limit::<Signals>(16 * 1024))
+///     .map(|ReadSignals(signals): ReadSignals<Signals>| signals.comment);
+/// ```
+pub fn read_signals_with_limit<T>(
+    limit: u64,
+) -> impl Filter<Extract = (ReadSignals<T>,), Error = Rejection> + Clone
+where
+    T: DeserializeOwned + Send,
+{
+    warp::method()
+        .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+        .and(warp::header::optional::<String>("content-type"))
+        .and(body(limit))
+        .and_then(extract_signals::<T>)
+}
+
+/// Extracts the decoded body of a request, or an empty body if the request has none.
+fn body(limit: u64) -> impl Filter<Extract = (Result<Bytes, Error>,), Error = Infallible> + Clone {
+    warp::header::optional::<String>("content-encoding")
+        .and(warp::body::stream())
+        .then(move |content_encoding: Option<String>, body| async move {
+            let body = read_body(body, limit).await?;
+            let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+            let decoded = compression::decode_body(content_encoding.as_deref(), &body, limit)?;
+            Ok::<_, Error>(decoded.map_or(body, Bytes::from))
+        })
+        .or(warp::any().map(|| Ok::<_, Error>(Bytes::new())))
+        .unify()
+}
+
+/// Reads the body of a request, failing as soon as it exceeds `limit` bytes.
+async fn read_body<B: Buf>(
+    body: impl Stream<Item = Result<B, warp::Error>>,
+    limit: u64,
+) -> Result<Bytes, Error> {
+    let mut body = pin!(body);
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|err| Error::Body(Box::new(err)))?;
+        if (bytes.len() + chunk.remaining()) as u64 > limit {
+            return Err(Error::PayloadTooLarge);
+        }
+        bytes.put(chunk);
+    }
+    Ok(bytes.freeze())
+}
+
+async fn extract_signals<T>(
+    method: Method,
+    query: String,
+    content_type: Option<String>,
+    body: Result<Bytes, Error>,
+) -> Result<ReadSignals<T>, Rejection>
+where
+    T: DeserializeOwned,
+{
+    let body = match method {
+        Method::GET => Bytes::new(),
+        _ => body.map_err(warp::reject::custom)?,
+    };
+
+    // The body was decoded by the `body` filter already.
+    let headers = content_type
+        .as_deref()
+        .map(|content_type| ("content-type", content_type));
+    signals::from_parts(method.as_str(), Some(query.as_str()), headers, &body)
+        .map(ReadSignals)
+        .map_err(warp::reject::custom)
+}
+
+/// Creates a Warp Filter that extracts Datastar signals from the request like [`read_signals`],
+/// rejecting signals whose [`ValidateSignals::validate`] fails with [`Error::Validation`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     signals::{ValidateSignals, ValidationErrors},
+///     warp::{ReadSignals, handle_rejection, read_valid_signals},
+/// };
+/// use serde::Deserialize;
+/// use warp::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct SignUp {
+///     email: String,
+/// }
+///
+/// impl ValidateSignals for SignUp {
+///     fn validate(&self) -> Result<(), ValidationErrors> {
+///         let mut errors = ValidationErrors::new();
+///         if !self.email.contains('@') {
+///             errors.push("email", "Enter a valid email address");
+///         }
+///         errors.into_result()
+///     }
+/// }
+///
+/// let route = warp::path("sign-up")
+///     .and(warp::post())
+///     .and(read_valid_signals::<SignUp>())
+///     .map(|ReadSignals(signals): ReadSignals<SignUp>| format!("Welcome, {}!", signals.email))
+///     .recover(handle_rejection);
+/// ```
+pub fn read_valid_signals<T>() -> impl Filter<Extract = (ReadSignals<T>,), Error = Rejection> + Clone
+where
+    T: DeserializeOwned + ValidateSignals + Send,
+{
+    read_signals::<T>().and_then(|ReadSignals(signals): ReadSignals<T>| async move {
+        match signals.validate() {
+            Ok(()) => Ok(ReadSignals(signals)),
+            Err(errors) => Err(warp::reject::custom(Error::Validation(errors))),
+        }
+    })
+}
+
+/// [`ReadSignalsMultipart`] holds the signals and the uploaded [`Files`] of a `multipart/form-data`
+/// request, such as a form with a file input, as extracted by [`read_signals_multipart`].
+#[cfg(feature = "multipart")]
+#[derive(Debug)]
+pub struct ReadSignalsMultipart<T>(pub T, pub Files);
+
+/// Creates a Warp Filter that extracts the signals and the uploaded files of a multipart request,
+/// rejecting bodies larger than `limit` bytes with [`Error::PayloadTooLarge`].
+///
+/// See the [`multipart`](crate::multipart) module for the fields which are read as the signals.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::warp::{ReadSignalsMultipart, read_signals_multipart};
+/// use serde::Deserialize;
+/// use warp::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct Upload {
+///     album: String,
+/// }
+///
+/// let route = warp::path("upload")
+///     .and(warp::post())
+///     .and(read_signals_multipart::<Upload>(64 * 1024 * 1024))
+///     .then(|ReadSignalsMultipart(signals, mut files): ReadSignalsMultipart<Upload>| async move {
+///         let mut count = 0;
+///         while let Ok(Some(photo)) = files.next_file().await {
+///             drop(photo);
+///             count += 1;
+///         }
+///         format!("added {count} photos to {}", signals.album)
+///     });
+/// ```
+#[cfg(feature = "multipart")]
+pub fn read_signals_multipart<T>(
+    limit: u64,
+) -> impl Filter<Extract = (ReadSignalsMultipart<T>,), Error = Rejection> + Clone
+where
+    T: DeserializeOwned + Send,
+{
+    warp::header::optional::<String>("content-type")
+        .and(warp::body::stream())
+        .and_then(move |content_type: Option<String>, body| async move {
+            let body = StreamExt::map(body, |chunk: Result<_, warp::Error>| {
+                chunk.map(|mut chunk| {
+                    let len = Buf::remaining(&chunk);
+                    Buf::copy_to_bytes(&mut chunk, len)
+                })
+            });
+            multipart::read_signals(content_type.as_deref(), body, limit)
+                .await
+                .map(|(signals, files)| ReadSignalsMultipart(signals, files))
+                .map_err(warp::reject::custom)
+        })
+}
+
+fn query_signals(query: &str) -> Result<Option<String>, Error> {
+    let params: DatastarParam = serde_urlencoded::from_str(query).map_err(|err| {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%err, "failed to parse query string");
+
+        Error::InvalidQuery(Box::new(err))
+    })?;
+    Ok(params.datastar)
+}
+
+/// Creates a Filter that checks for the datastar-request header.
+/// Returns `true` if the header is present, `false` otherwise.
+pub fn is_datastar_request() -> impl Filter<Extract = (bool,), Error = Rejection> + Clone {
+    warp::header::optional::<String>(DATASTAR_REQ_HEADER_STR)
+        .map(|header: Option<String>| header.is_some())
+}
+
+/// Creates a Filter that checks whether the request accepts `text/event-stream`.
+/// Returns `true` if it does, `false` otherwise.
+pub fn accepts_event_stream_request() -> impl Filter<Extract = (bool,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("accept")
+        .map(|accept: Option<String>| accept.as_deref().is_some_and(accepts_event_stream))
+}
+
+/// Rejection for requests which don't accept `text/event-stream`, see [`require_event_stream`].
+#[derive(Debug)]
+pub struct NotAcceptable;
+
+impl warp::reject::Reject for NotAcceptable {}
+
+/// Creates a Filter that rejects requests which don't accept `text/event-stream`
+/// with [`NotAcceptable`].
+///
+/// The rejection is turned into a `406 Not Acceptable` response by [`handle_rejection`].
+/// Combine it with [`Filter::or`] to serve a non-streaming snapshot instead.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     prelude::PatchElements,
+///     warp::{handle_rejection, require_event_stream},
+/// };
+/// use warp::Filter;
+///
+/// let stream = warp::path("counter")
+///     .and(require_event_stream())
+///     .map(|| PatchElements::new("<div id='counter'>0</div>"));
+/// let snapshot = warp::path("counter").map(|| warp::reply::html("<div id='counter'>0</div>"));
+///
+/// let route = stream.or(snapshot).recover(handle_rejection);
+/// ```
+pub fn require_event_stream() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    accepts_event_stream_request()
+        .and_then(|accepted: bool| async move {
+            if accepted {
+                Ok(())
+            } else {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("request does not accept an event stream");
+
+                Err(warp::reject::custom(NotAcceptable))
+            }
+        })
+        .untuple_one()
+}
+
+/// [`DatastarRequest`] surfaces the Datastar specific metadata of a request,
+/// so handlers and filters can branch on it without parsing the request twice.
+///
+/// Unlike [`ReadSignals`], the signals are kept as raw JSON and the extraction does not fail
+/// for requests which were not sent by Datastar.
+#[derive(Debug, Clone)]
+pub struct DatastarRequest {
+    /// Whether the request carries the `datastar-request` header.
+    pub is_datastar: bool,
+    /// The HTTP method of the request.
+    pub method: Method,
+    /// The signals sent with the request, if any.
+    ///
+    /// For `GET` requests these are read from the `datastar` query parameter,
+    /// for all other methods from the JSON body.
+    pub signals: Option<serde_json::Value>,
+    /// The `Last-Event-ID` header of the request, if any.
+    pub last_event_id: Option<LastEventId>,
+}
+
+impl DatastarRequest {
+    /// Deserializes the raw [`DatastarRequest::signals`] into `T`.
+    pub fn signals_as<T: DeserializeOwned>(&self) -> Result<Option<T>, Error> {
+        Ok(self.signals.as_ref().map(T::deserialize).transpose()?)
+    }
+}
+
+/// Creates a Filter that extracts the [`DatastarRequest`] metadata of the request.
+///
+/// The body is limited to [`DEFAULT_BODY_LIMIT`] bytes.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::warp::{DatastarRequest, datastar_request, handle_rejection};
+/// use serde::Deserialize;
+/// use warp::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     delay: u64,
+/// }
+///
+/// let route = warp::path("hello")
+///     .and(datastar_request())
+///     .map(|req: DatastarRequest| {
+///         if !req.is_datastar {
+///             return "full page".to_owned();
+///         }
+///         match req.signals_as::<Signals>() {
+///             Ok(Some(signals)) => format!("delay: {}", signals.delay),
+///             _ => "fragment".to_owned(),
+///         }
+///     })
+///     .recover(handle_rejection);
+/// ```
+pub fn datastar_request() -> impl Filter<Extract = (DatastarRequest,), Error = Rejection> + Clone {
+    is_datastar_request()
+        .and(warp::method())
+        .and(last_event_id())
+        .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+        .and(warp::header::optional::<String>("content-type"))
+        .and(body(DEFAULT_BODY_LIMIT))
+        .and_then(extract_datastar_request)
+}
+
+async fn extract_datastar_request(
+    is_datastar: bool,
+    method: Method,
+    last_event_id: Option<LastEventId>,
+    query: String,
+    content_type: Option<String>,
+    body: Result<Bytes, Error>,
+) -> Result<DatastarRequest, Rejection> {
+    let signals = match method {
+        Method::GET => query_signals(&query)
+            .map_err(warp::reject::custom)?
+            .map(|signals| json::signals_from_slice(signals.as_bytes()))
+            .transpose(),
+        _ => {
+            let body = body.map_err(warp::reject::custom)?;
+            (!body.is_empty())
+                .then(|| json::signals_from_body(content_type.as_deref(), &body))
+                .transpose()
+        }
+    }
+    .map_err(warp::reject::custom)?;
+
+    Ok(DatastarRequest {
+        is_datastar,
+        method,
+        signals,
+        last_event_id,
+    })
+}
+
+/// Creates a Filter that optionally extracts Datastar signals from the request.
+///
+/// Returns `Some(ReadSignals<T>)` if signals are present and parseable,
+/// `None` if the `datastar-request` header is not present.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::warp::{read_signals_optional, ReadSignals};
+/// use serde::Deserialize;
+/// use warp::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     delay: u64,
+/// }
+///
+/// let route = warp::path("hello")
+///     .and(read_signals_optional::<Signals>())
+///     .map(|signals: Option<ReadSignals<Signals>>| {
+///         match signals {
+///             Some(ReadSignals(s)) => format!("delay: {}", s.delay),
+///             None => "no signals".to_string(),
+///         }
+///     });
+/// ```
+pub fn read_signals_optional<T>()
+-> impl Filter<Extract = (Option<ReadSignals<T>>,), Error = Rejection> + Clone
+where
+    T: DeserializeOwned + Send,
+{
+    warp::header::optional::<String>(DATASTAR_REQ_HEADER_STR)
+        .and(
+            read_signals::<T>()
+                .map(Some)
+                .or(warp::any().map(|| None::<ReadSignals<T>>))
+                .unify(),
+        )
+        .map(
+            |is_datastar: Option<String>, signals: Option<ReadSignals<T>>| {
+                if is_datastar.is_some() { signals } else { None }
+            },
+        )
+}
+
+/// Rejection handler for the [`Error`]s of the signal extractors.
+///
+/// Use this with `warp::Filter::recover` to convert rejections into proper HTTP responses,
+/// with the message as plain text. [`RecoverRejections`] writes them in other formats.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::warp::{read_signals, handle_rejection, ReadSignals};
+/// use serde::Deserialize;
+/// use warp::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     delay: u64,
+/// }
+///
+/// let route = warp::path("hello")
+///     .and(read_signals::<Signals>())
+///     .map(|ReadSignals(signals): ReadSignals<Signals>| {
+///         format!("delay: {}", signals.delay)
+///     })
+///     .recover(handle_rejection);
+/// ```
+pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
+    RecoverRejections::text().handle(err).await
+}
+
+/// The media type of the problem details written by [`RejectionFormat::ProblemJson`].
+pub const PROBLEM_JSON_MEDIA_TYPE: &str = "application/problem+json";
+
+/// [`RejectionFormat`] is how [`RecoverRejections`] writes the message of a rejection.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectionFormat {
+    /// The message as plain text, as written by [`handle_rejection`].
+    #[default]
+    Text,
+    /// An [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457) problem details object with the
+    /// message as its `detail`, for clients which read errors as JSON.
+    ProblemJson,
+    /// An [`ExecuteScript`] event which logs the message with `console.error`,
+    /// for errors of requests sent by Datastar.
+    ConsoleError,
+}
+
+/// [`RecoverRejections`] converts the rejections of the Datastar filters into responses with
+/// the status of the [`Error`], in the configured [`RejectionFormat`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::warp::{ReadSignals, RecoverRejections, read_signals};
+/// use serde::Deserialize;
+/// use warp::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct Signals {
+///     delay: u64,
+/// }
+///
+/// let rejections = RecoverRejections::problem_json();
+///
+/// let route = warp::path("hello")
+///     .and(read_signals::<Signals>())
+///     .map(|ReadSignals(signals): ReadSignals<Signals>| {
+///         format!("delay: {}", signals.delay)
+///     })
+///     .recover(move |err| rejections.handle(err));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecoverRejections {
+    format: RejectionFormat,
+}
+
+impl RecoverRejections {
+    /// Creates [`RecoverRejections`] which write the message as plain text.
+    pub const fn new() -> Self {
+        Self::text()
+    }
+
+    /// Creates [`RecoverRejections`] which write the message as plain text.
+    pub const fn text() -> Self {
+        Self {
+            format: RejectionFormat::Text,
+        }
+    }
+
+    /// Creates [`RecoverRejections`] which write problem details as JSON.
+    pub const fn problem_json() -> Self {
+        Self {
+            format: RejectionFormat::ProblemJson,
+        }
+    }
+
+    /// Creates [`RecoverRejections`] which log the message to the browser console.
+    pub const fn console_error() -> Self {
+        Self {
+            format: RejectionFormat::ConsoleError,
+        }
+    }
+
+    /// Sets the [`RejectionFormat`].
+    pub const fn format(mut self, format: RejectionFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Converts `err` into a response, for use with `warp::Filter::recover`.
+    pub async fn handle(self, err: Rejection) -> Result<Response, Infallible> {
+        let (status, message) = if let Some(err) = err.find::<Error>() {
+            (
+                StatusCode::from_u16(err.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                err.to_string(),
+            )
+        } else if err.find::<NotAcceptable>().is_some() {
+            (
+                StatusCode::NOT_ACCEPTABLE,
+                accept::NOT_ACCEPTABLE_MESSAGE.to_owned(),
+            )
+        } else {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal Server Error".to_owned(),
+            )
+        };
+
+        let reply = match self.format {
+            RejectionFormat::Text => message.into_response(),
+            RejectionFormat::ProblemJson => {
+                let problem = serde_json::json!({
+                    "type": "about:blank",
+                    "title": status.canonical_reason().unwrap_or_default(),
+                    "status": status.as_u16(),
+                    "detail": message,
+                });
+                warp::reply::with_header(
+                    problem.to_string(),
+                    "content-type",
+                    PROBLEM_JSON_MEDIA_TYPE,
+                )
+                .into_response()
+            }
+            RejectionFormat::ConsoleError => scripts::console_error(&message).into_response(),
+        };
+        Ok(warp::reply::with_status(reply, status).into_response())
+    }
+}
+
+/// Creates a Filter that extracts the [`LastEventId`] from the `Last-Event-ID` header.
+/// Returns `None` if the header is not present.
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{replay::LastEventId, warp::last_event_id};
+/// use warp::Filter;
+///
+/// let route = warp::path("feed")
+///     .and(last_event_id())
+///     .map(|last_event_id: Option<LastEventId>| match last_event_id {
+///         Some(id) => format!("resuming after {id}"),
+///         None => "starting a new stream".to_owned(),
+///     });
+/// ```
+pub fn last_event_id() -> impl Filter<Extract = (Option<LastEventId>,), Error = Rejection> + Clone {
+    warp::header::optional::<String>(LAST_EVENT_ID_HEADER_STR)
+        .map(|id: Option<String>| id.map(LastEventId))
+}
+
+/// Wraps `filter` so the events a client missed before reconnecting with a `Last-Event-ID`
+/// header, as looked up in the given [`Journal`], are prepended to the events of its reply.
+///
+/// Handlers don't need to be aware of the replay at all, which makes any existing filter
+/// whose reply is an [`IntoDatastarResponse`] resumable. The reply is sent as a
+/// [`DatastarResponse`].
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{DatastarEvent, prelude::PatchElements, replay::Journal, warp::replay};
+/// use warp::Filter;
+///
+/// struct NoJournal;
+///
+/// impl Journal for NoJournal {
+///     async fn events_after(&self, _last_event_id: &str) -> Vec<DatastarEvent> {
+///         Vec::new()
+///     }
+/// }
+///
+/// let feed = warp::path("feed").map(|| PatchElements::new("<ul id='feed'></ul>").id("1"));
+/// let route = replay(NoJournal, feed);
+/// ```
+pub fn replay<J, F, T>(
+    journal: J,
+    filter: F,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    J: Journal,
+    F: Filter<Extract = (T,), Error = Rejection> + Clone + Send + Sync + 'static,
+    T: IntoDatastarResponse + Send,
+{
+    let journal = Arc::new(journal);
+
+    last_event_id()
+        .and(filter)
+        .then(move |last_event_id: Option<LastEventId>, reply: T| {
+            let journal = journal.clone();
+            async move {
+                let missed = match last_event_id {
+                    Some(last_event_id) => {
+                        let missed = journal.events_after(&last_event_id).await;
+                        #[cfg(feature = "tracing")]
+                        if !missed.is_empty() {
+                            tracing::debug!(%last_event_id, count = missed.len(), "replaying missed events");
+                        }
+                        missed
+                    }
+                    None => Vec::new(),
+                };
+                let events = stream::iter(missed).chain(reply.into_datastar_stream());
+                DatastarResponse(DatastarStream(events)).into_response()
+            }
+        })
+}
+
+/// Creates a Filter that starts an [`Inspection`] of the response, which logs every event with
+/// `tracing` and mirrors it to the live panels of `inspector`.
+///
+/// Warp does not allow rewriting the body of a reply once it has been created,
+/// so unlike the Axum and Rocket integrations the handler's stream has to be inspected using
+/// [`InspectExt::inspected`](crate::inspector::InspectExt::inspected).
+///
+/// # Examples
+///
+/// ```
+/// use datastar_core::{
+///     DatastarEvent,
+///     inspector::{InspectExt, Inspection, Inspector},
+///     prelude::PatchElements,
+///     warp::inspection,
+/// };
+/// use warp::Filter;
+///
+/// let inspector = Inspector::new();
+/// let panel = inspector.clone();
+///
+/// let feed = warp::path("feed")
+///     .and(inspection(inspector))
+///     .map(|inspection: Inspection| {
+///         let patch = PatchElements::new("<ul id='feed'></ul>");
+///         futures_util::stream::iter([DatastarEvent::from(patch)]).inspected(inspection)
+///     });
+/// let panel = warp::path!("_datastar" / "inspector").map(move || panel.panel());
+/// ```
+#[cfg(feature = "inspector")]
+pub fn inspection(
+    inspector: Inspector,
+) -> impl Filter<Extract = (Inspection,), Error = Infallible> + Clone {
+    warp::path::full().map(move |path: FullPath| inspector.inspect(path.as_str()))
+}
@@ -3,18 +3,25 @@ use {
     axum::{
         Router,
         extract::Path,
-        response::{Html, IntoResponse, Sse, sse::Event},
+        response::{Html, IntoResponse},
         routing::{get, post},
     },
-    core::{convert::Infallible, error::Error, time::Duration},
+    core::{error::Error, time::Duration},
     datastar::{
         axum::ReadSignals,
-        prelude::{ElementPatchMode, PatchElements, PatchSignals},
+        components::{Counts, Feed, Status},
+        html::el,
+        id,
+        prelude::{DatastarEvent, ElementPatchMode, IndicatorExt},
+        response::Batch,
     },
     serde::{Deserialize, Serialize},
     tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt},
 };
 
+/// The page of the example, which is also used to check the patched ids at compile time.
+const PAGE: &str = include_str!("activity-feed.html");
+
 /// All `data-signals-*` defined in activity-feed.html
 #[derive(Serialize, Deserialize)]
 pub struct Signals {
@@ -24,23 +31,8 @@ pub struct Signals {
     // Activity flags
     pub generating: bool,
     // Output counters
-    pub total: u64,
-    pub done: u64,
-    pub warn: u64,
-    pub fail: u64,
-    pub info: u64,
-}
-
-/// All valid event statuses.
-// Normalizing variants to lowercase allows parsing routes from `/event/{status}`
-// with a `Path<Status>` extractor.
-#[derive(Deserialize, Serialize)]
-#[serde(rename_all = "lowercase")]
-pub enum Status {
-    Done,
-    Fail,
-    Info,
-    Warn,
+    #[serde(flatten)]
+    pub counts: Counts,
 }
 
 #[tokio::main]
@@ -70,93 +62,56 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
 /// Simple handler returning a static HTML page
 async fn index() -> Html<&'static str> {
-    Html(include_str!("activity-feed.html"))
+    Html(PAGE)
 }
 
 /// Generates a number of "done" events with a specified interval.
 async fn generate(ReadSignals(signals): ReadSignals<Signals>) -> impl IntoResponse {
-    // Values we will update in a loop
-    let mut total = signals.total;
-    let mut done = signals.done;
-
-    // Start the SSE stream
-    Sse::new(stream_fn(
-        move |mut yielder: Yielder<Result<Event, Infallible>>| async move {
-            // Signal event generation start
-            let patch = PatchSignals::new(r#"{"generating": true}"#);
-            let sse_event = patch.write_as_axum_sse_event();
-            yielder.yield_item(Ok(sse_event)).await;
-
-            // Yield the events elements and signals to the stream
-            for _ in 1..=signals.events {
-                total += 1;
-                done += 1;
-                // Append a new entry to the activity feed
-                let elements = event_entry(&Status::Done, total, "Auto");
-                let patch = PatchElements::new(elements)
-                    .selector("#feed")
-                    .mode(ElementPatchMode::After);
-                let sse_event = patch.write_as_axum_sse_event();
-                yielder.yield_item(Ok(sse_event)).await;
-
-                // Update the event counts
-                let patch = PatchSignals::new(format!(r#"{{"total": {total}, "done": {done}}}"#));
-                let sse_event = patch.write_as_axum_sse_event();
-                yielder.yield_item(Ok(sse_event)).await;
-                tokio::time::sleep(Duration::from_millis(signals.interval)).await;
+    let mut feed = feed(signals.counts);
+
+    // Start the SSE stream, the `generating` signal is set while it is running
+    stream_fn(move |mut yielder: Yielder<DatastarEvent>| async move {
+        for _ in 1..=signals.events {
+            // Append a new entry to the activity feed and update the event counts
+            let entry = event_entry(Status::Done, feed.counts().total + 1, "Auto");
+            for event in feed.push(Status::Done, entry).into_events() {
+                yielder.yield_item(event).await;
             }
-
-            // Signal event generation end
-            let patch = PatchSignals::new(r#"{"generating": false}"#);
-            let sse_event = patch.write_as_axum_sse_event();
-            yielder.yield_item(Ok(sse_event)).await;
-        },
-    ))
+            tokio::time::sleep(Duration::from_millis(signals.interval)).await;
+        }
+    })
+    .with_indicator("generating")
 }
 
 /// Creates one event with a given status
-async fn event(
-    Path(status): Path<Status>,
-    ReadSignals(signals): ReadSignals<Signals>,
-) -> impl IntoResponse {
-    // Create the event stream, since we're patching both an element and a signal.
-    Sse::new(stream_fn(
-        move |mut yielder: Yielder<Result<Event, Infallible>>| async move {
-            // Signal the updated event counts
-            let total = signals.total + 1;
-            let signals = match status {
-                Status::Done => format!(r#"{{"total": {total}, "done": {}}}"#, signals.done + 1),
-                Status::Warn => format!(r#"{{"total": {total}, "warn": {}}}"#, signals.warn + 1),
-                Status::Fail => format!(r#"{{"total": {total}, "fail": {}}}"#, signals.fail + 1),
-                Status::Info => format!(r#"{{"total": {total}, "info": {}}}"#, signals.info + 1),
-            };
-            let patch = PatchSignals::new(signals);
-            let sse_signal = patch.write_as_axum_sse_event();
-            yielder.yield_item(Ok(sse_signal)).await;
+// Statuses are lowercase, which allows parsing routes from `/event/{status}`
+// with a `Path<Status>` extractor.
+async fn event(Path(status): Path<Status>, ReadSignals(signals): ReadSignals<Signals>) -> Batch {
+    let mut feed = feed(signals.counts);
+    let entry = event_entry(status, feed.counts().total + 1, "Manual");
+    feed.push(status, entry).into()
+}
 
-            // Patch an element and append it to the feed
-            let elements = event_entry(&status, total, "Manual");
-            let patch = PatchElements::new(elements)
-                .selector("#feed")
-                .mode(ElementPatchMode::After);
-            let sse_event = patch.write_as_axum_sse_event();
-            yielder.yield_item(Ok(sse_event)).await;
-        },
-    ))
+/// Returns the activity feed, continuing from the counts of the client
+fn feed(counts: Counts) -> Feed {
+    Feed::new(id!("feed", in PAGE))
+        .mode(ElementPatchMode::After)
+        .with_counts(counts)
 }
 
 /// Returns an HTML string for the entry
-fn event_entry(status: &Status, index: u64, source: &str) -> String {
-    let timestamp = chrono::Utc::now()
-        .format("%Y-%m-%d %H:%M:%S%.3f")
-        .to_string();
+fn event_entry(status: Status, index: u64, source: &str) -> String {
+    let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.3f");
     let (color, indicator) = match status {
         Status::Done => ("green", "✅ Done"),
         Status::Warn => ("yellow", "⚠️ Warn"),
         Status::Fail => ("red", "❌ Fail"),
         Status::Info => ("blue", "ℹ️ Info"),
     };
-    format!(
-        "<div id='event-{index}' class='text-{color}-500'>{timestamp} [ {indicator} ] {source} event {index}</div>"
-    )
+    el("span")
+        .class(format!("text-{color}-500"))
+        .text(format!(
+            "{timestamp} [ {indicator} ] {source} event {index}"
+        ))
+        .into()
 }
@@ -0,0 +1,46 @@
+#!/usr/bin/env bash
+# Vendors the golden cases of the Datastar SDK test suite into datastar-core/golden, which
+# the `conformance` module embeds.
+#
+# Usage: scripts/update-golden.sh [git ref of starfederation/datastar, defaults to main]
+
+set -euo pipefail
+
+REPO="starfederation/datastar"
+REF="${1:-main}"
+SUITE="sdk/test-golden"
+GOLDEN="$(cd "$(dirname "$0")/.." && pwd)/datastar-core/golden"
+
+tmp="$(mktemp -d)"
+trap 'rm -rf "$tmp"' EXIT
+
+sha="$(curl -fsSL "https://api.github.com/repos/$REPO/commits/$REF" | sed -n 's/^  "sha": "\([0-9a-f]*\)",$/\1/p')"
+curl -fsSL "https://codeload.github.com/$REPO/tar.gz/$sha" | tar -xz -C "$tmp"
+suite="$tmp/datastar-$sha/$SUITE"
+
+find "$GOLDEN" -mindepth 1 -maxdepth 1 -type d -exec rm -rf {} +
+
+cases=()
+while IFS= read -r input; do
+    dir="$(dirname "$input")"
+    name="$(basename "$dir")"
+    mkdir -p "$GOLDEN/$name"
+    cp "$dir/input.json" "$dir/output.txt" "$GOLDEN/$name/"
+    cases+=("$name")
+done < <(find "$suite" -name input.json | sort)
+
+{
+    echo "// Generated by scripts/update-golden.sh, do not edit."
+    echo "&["
+    for name in "${cases[@]}"; do
+        echo "    ("
+        echo "        \"$name\","
+        echo "        include_str!(\"$name/input.json\"),"
+        echo "        include_str!(\"$name/output.txt\"),"
+        echo "    ),"
+    done
+    echo "]"
+} > "$GOLDEN/cases.rs"
+
+echo "https://github.com/$REPO/tree/$sha/$SUITE" > "$GOLDEN/SOURCE"
+echo "vendored ${#cases[@]} cases of $REPO@$sha"
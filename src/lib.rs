@@ -1,72 +1,66 @@
 //! Datastar is a Rust implementation of the [Datastar](https://data-star.dev) SDK specification.
+//!
+//! Everything is implemented in the [`datastar_core`] crate and re-exported here,
+//! with the framework integrations enabled through the features of this crate.
 
 #![forbid(missing_docs)]
 #![forbid(missing_debug_implementations)]
 
 #[cfg(feature = "axum")]
-pub mod axum;
+pub use datastar_core::axum;
+#[cfg(feature = "broadcast")]
+pub use datastar_core::broadcast;
+#[cfg(feature = "serde")]
+pub use datastar_core::coalesce;
+#[cfg(feature = "components")]
+pub use datastar_core::components;
+#[cfg(feature = "serde")]
+pub use datastar_core::compression;
+#[cfg(feature = "serde")]
+pub use datastar_core::conformance;
+#[cfg(feature = "html-diff")]
+pub use datastar_core::html_diff;
+#[cfg(feature = "inspector")]
+pub use datastar_core::inspector;
+#[cfg(feature = "minify")]
+pub use datastar_core::minify;
+#[cfg(feature = "multipart")]
+pub use datastar_core::multipart;
+#[cfg(feature = "bytes")]
+pub use datastar_core::pool;
+#[cfg(feature = "serde")]
+pub use datastar_core::query;
+#[cfg(feature = "serde")]
+pub use datastar_core::recording;
+#[cfg(feature = "redis")]
+pub use datastar_core::redis;
 #[cfg(feature = "rocket")]
-pub mod rocket;
+pub use datastar_core::rocket;
+#[cfg(feature = "ammonia")]
+pub use datastar_core::sanitize;
+#[cfg(feature = "serde")]
+pub use datastar_core::signal_tracker;
+#[cfg(feature = "serde")]
+pub use datastar_core::signals;
+#[cfg(feature = "tokio")]
+pub use datastar_core::stall;
+#[cfg(any(feature = "axum", feature = "rocket", feature = "warp"))]
+pub use datastar_core::stream;
+#[cfg(any(feature = "minijinja", feature = "tera"))]
+pub use datastar_core::templates;
+#[cfg(feature = "testing")]
+pub use datastar_core::testing;
 #[cfg(feature = "warp")]
-pub mod warp;
+pub use datastar_core::warp;
 
-pub mod execute_script;
-pub mod patch_elements;
-pub mod patch_signals;
+pub use datastar_core::{
+    DatastarEvent, Error, FieldError, IntoDatastarEvent, accept, batch, chunked, clock, consts,
+    data_lines, event_id, execute_script, fragment, heartbeat, html, id, indicator, migrations,
+    parse, patch_elements, patch_signals, prelude, replay, respond, response, scripts, selector,
+    sender, serialize, signal_path, signal_paths, snapshot, sse_payload,
+};
 
 #[doc = include_str!("../README.md")]
 #[cfg(doctest)]
 #[expect(unused)]
 struct ReadmeDoctests;
-
-pub mod consts;
-
-/// The prelude for the `datastar` crate
-pub mod prelude {
-    pub use crate::{
-        DatastarEvent, consts::ElementPatchMode, execute_script::ExecuteScript,
-        patch_elements::PatchElements, patch_signals::PatchSignals,
-    };
-}
-
-use core::{fmt::Display, time::Duration};
-
-/// [`DatastarEvent`] is a struct that represents a generic Datastar event.
-/// All Datastar events implement `Into<DatastarEvent>`.
-#[derive(Debug)]
-pub struct DatastarEvent {
-    /// `event` is the type of event.
-    pub event: consts::EventType,
-    /// `id` is can be used by the backend to replay events.
-    /// This is part of the SSE spec and is used to tell the browser how to handle the event.
-    /// For more details see <https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#id>
-    pub id: Option<String>,
-    /// `retry` is part of the SSE spec and is used to tell the browser how long to wait before reconnecting if the connection is lost.
-    /// For more details see <https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#retry>
-    pub retry: Duration,
-    /// `data` is the data that is sent with the event.
-    pub data: Vec<String>,
-}
-
-impl Display for DatastarEvent {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "event: {}", self.event.as_str())?;
-
-        if let Some(id) = &self.id {
-            write!(f, "\nid: {id}")?;
-        }
-
-        let millis = self.retry.as_millis();
-        if millis != consts::DEFAULT_SSE_RETRY_DURATION as u128 {
-            write!(f, "\nretry: {millis}")?;
-        }
-
-        for line in &self.data {
-            write!(f, "\ndata: {line}")?;
-        }
-
-        write!(f, "\n\n")?;
-
-        Ok(())
-    }
-}